@@ -0,0 +1,155 @@
+//! A global allocator that counts allocations, to find the solvers that allocate the
+//! most. `aoc2020` installs it when it's built with the `count-allocations` feature,
+//! and `bench` then reports the usage of every phase.
+//!
+//! The counters are shared by all threads, so `track` is only accurate while nothing
+//! else allocates at the same time.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fmt, hint,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// The system allocator, counting what goes through it.
+pub struct Counting;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED: AtomicU64 = AtomicU64::new(0);
+/// The bytes that are currently allocated, and the most there were since the last reset.
+static LIVE: AtomicU64 = AtomicU64::new(0);
+static PEAK: AtomicU64 = AtomicU64::new(0);
+
+impl Counting {
+    fn allocated(size: usize) {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED.fetch_add(size as u64, Ordering::Relaxed);
+        let live = LIVE.fetch_add(size as u64, Ordering::Relaxed) + size as u64;
+        PEAK.fetch_max(live, Ordering::Relaxed);
+    }
+
+    fn freed(size: usize) {
+        LIVE.fetch_sub(size as u64, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::freed(layout.size());
+    }
+
+    // a reallocation counts as a new allocation of `new_size` bytes
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            Self::freed(layout.size());
+            Self::allocated(new_size);
+        }
+        new
+    }
+}
+
+/// Whether `Counting` is the global allocator, i.e. whether `track` measures anything.
+pub fn installed() -> bool {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    drop(hint::black_box(Box::new(0u8)));
+    ALLOCATIONS.load(Ordering::Relaxed) != allocations
+}
+
+/// What was allocated while running something.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub allocations: u64,
+    /// The total size of all allocations.
+    pub bytes: u64,
+    /// The most bytes that were allocated at the same time, on top of what was already
+    /// allocated before.
+    pub peak: u64,
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} allocations, {} allocated, {} peak",
+            self.allocations,
+            Bytes(self.bytes),
+            Bytes(self.peak)
+        )
+    }
+}
+
+/// A number of bytes, formatted with a binary prefix.
+pub struct Bytes(pub u64);
+
+impl fmt::Display for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+        let mut size = self.0 as f64;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < UNITS.len() - 1 {
+            size /= 1024.0;
+            unit += 1;
+        }
+        if unit == 0 {
+            write!(f, "{} B", self.0)
+        } else {
+            write!(f, "{:.1} {}", size, UNITS[unit])
+        }
+    }
+}
+
+/// Runs `f` and returns what it allocated.
+pub fn track<T>(f: impl FnOnce() -> T) -> (T, Usage) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = ALLOCATED.load(Ordering::Relaxed);
+    let live = LIVE.load(Ordering::Relaxed);
+    PEAK.store(live, Ordering::Relaxed);
+    let result = f();
+    let usage = Usage {
+        allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        bytes: ALLOCATED.load(Ordering::Relaxed) - bytes,
+        peak: PEAK.load(Ordering::Relaxed).saturating_sub(live),
+    };
+    (result, usage)
+}
+
+#[test]
+fn usage() {
+    // the tests don't install `Counting`, so only these allocations are counted
+    let (_, usage) = track(|| unsafe {
+        let small = Layout::from_size_align(16, 8).unwrap();
+        let a = Counting.alloc(small);
+        let b = Counting.realloc(a, small, 1000);
+        let c = Counting.alloc_zeroed(small);
+        Counting.dealloc(b, Layout::from_size_align(1000, 8).unwrap());
+        Counting.dealloc(c, small);
+    });
+    assert_eq!(
+        usage,
+        Usage {
+            allocations: 3,
+            bytes: 1032,
+            peak: 1016,
+        }
+    );
+    assert_eq!(Bytes(1000).to_string(), "1000 B");
+    assert_eq!(Bytes(3 << 19).to_string(), "1.5 MiB");
+}
@@ -0,0 +1,170 @@
+use std::hash::Hash;
+
+use fxhash::FxHashSet;
+
+/// The set of cells that influence a cell's next state.
+pub(crate) trait Neighborhood {
+    type Cell: Copy + Eq + Hash;
+    type Neighbors: AsRef<[Self::Cell]>;
+
+    fn neighbors(cell: &Self::Cell) -> Self::Neighbors;
+}
+
+/// Sparse cellular automaton: only the active cells are stored.
+#[derive(Clone)]
+pub(crate) struct Space<Nb: Neighborhood> {
+    pub(crate) cells: FxHashSet<Nb::Cell>,
+}
+
+impl<Nb: Neighborhood> Space<Nb> {
+    pub(crate) fn new(cells: FxHashSet<Nb::Cell>) -> Self {
+        Self { cells }
+    }
+
+    /// Computes the next generation. `rule` receives whether a cell is currently active
+    /// and how many of its neighbors are, and returns whether it should be active next.
+    pub(crate) fn step(&self, rule: impl Fn(bool, usize) -> bool) -> Self {
+        let mut to_visit: FxHashSet<Nb::Cell> = FxHashSet::default();
+
+        for &cell in self.cells.iter() {
+            to_visit.insert(cell);
+            to_visit.extend(Nb::neighbors(&cell).as_ref());
+        }
+        to_visit.retain(|cell| {
+            let active_neighbors = Nb::neighbors(cell)
+                .as_ref()
+                .iter()
+                .filter(|&c| self.cells.contains(c))
+                .count();
+            rule(self.cells.contains(cell), active_neighbors)
+        });
+
+        Self { cells: to_visit }
+    }
+}
+
+pub(crate) type Coordinates<const N: usize> = [i64; N];
+
+pub(crate) const fn number_of_neighbors(dimensions: usize) -> usize {
+    3usize.pow(dimensions as u32) - 1
+}
+
+/*
+// This is slower
+struct Neighbors< const N: usize> {
+    coordinates: Coordinates<N>,
+    counter: usize,
+}
+
+impl< const N: usize> Neighbors< N> {
+    fn new(coordinates: Coordinates<N>) -> Self {
+        Self {
+            coordinates,
+            counter: 0,
+        }
+    }
+}
+
+impl< const N: usize> Iterator for Neighbors< N> {
+    type Item = Coordinates<N>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.counter == number_of_neighbors(N) {
+            return None;
+        }
+        let i = if self.counter >= number_of_neighbors(N) / 2 {
+            self.counter + 1
+        } else {
+            self.counter
+        };
+        self.counter += 1;
+        let mut coordinates = self.coordinates;
+        for dimension in 0..N {
+            match (i / 3usize.pow(dimension as u32)) % 3 {
+                0 => coordinates[dimension] -= 1,
+                1 => {}
+                2 => coordinates[dimension] += 1,
+                _ => unreachable!(),
+            }
+        }
+        Some(coordinates)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (number_of_neighbors(N), Some(number_of_neighbors(N)))
+    }
+}
+*/
+/// All cells that differ by at most one in every dimension.
+pub(crate) struct Moore<const N: usize>;
+
+impl<const N: usize> Neighborhood for Moore<N>
+where
+    [(); number_of_neighbors(N)]:,
+{
+    type Cell = Coordinates<N>;
+    type Neighbors = [Coordinates<N>; number_of_neighbors(N)];
+
+    fn neighbors(input_coordinates: &Coordinates<N>) -> Self::Neighbors {
+        let mut neighbors = [*input_coordinates; number_of_neighbors(N)];
+        for (i, coordinates) in neighbors.iter_mut().enumerate() {
+            let i = if i >= number_of_neighbors(N) / 2 {
+                i + 1
+            } else {
+                i
+            };
+            for (dimension, coordinate) in coordinates.iter_mut().enumerate() {
+                match (i / 3usize.pow(dimension as u32)) % 3 {
+                    0 => *coordinate -= 1,
+                    1 => {}
+                    2 => *coordinate += 1,
+                    _ => unreachable!(),
+                }
+            }
+        }
+        neighbors
+    }
+}
+
+/// The six tiles touching a hexagonal tile, in axial coordinates `[q, r]`.
+pub(crate) struct Hex;
+
+impl Hex {
+    pub(crate) const EAST: Coordinates<2> = [1, 0];
+    pub(crate) const SOUTH_EAST: Coordinates<2> = [0, 1];
+    pub(crate) const SOUTH_WEST: Coordinates<2> = [-1, 1];
+    pub(crate) const WEST: Coordinates<2> = [-1, 0];
+    pub(crate) const NORTH_WEST: Coordinates<2> = [0, -1];
+    pub(crate) const NORTH_EAST: Coordinates<2> = [1, -1];
+
+    const DIRECTIONS: [Coordinates<2>; 6] = [
+        Hex::EAST,
+        Hex::SOUTH_EAST,
+        Hex::SOUTH_WEST,
+        Hex::WEST,
+        Hex::NORTH_WEST,
+        Hex::NORTH_EAST,
+    ];
+}
+
+impl Neighborhood for Hex {
+    type Cell = Coordinates<2>;
+    type Neighbors = [Coordinates<2>; 6];
+
+    fn neighbors(&[q, r]: &Coordinates<2>) -> Self::Neighbors {
+        Hex::DIRECTIONS.map(|[dq, dr]| [q + dq, r + dr])
+    }
+}
+
+#[test]
+fn find_neighbors() {
+    let neighbors = Moore::<1>::neighbors(&[0]);
+    assert_eq!(neighbors, [[-1], [1]]);
+}
+#[test]
+fn hex_neighbors() {
+    for neighbor in Hex::neighbors(&[0, 0]) {
+        assert!(Hex::neighbors(&neighbor).contains(&[0, 0]));
+    }
+}
@@ -0,0 +1,295 @@
+//! Timing of the parse and solve phases of each day, with statistics over repeated samples.
+
+use std::{
+    cmp::Reverse,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    allocations::{self, Usage},
+    error::Result,
+    solution::Entry,
+};
+
+pub struct Config {
+    /// Runs of each phase before measuring, to warm up caches and the allocator.
+    pub warmup: usize,
+    pub samples: usize,
+    /// Sampling stops early once a phase has run for this long, so slow days
+    /// don't take minutes. At least one sample is always taken.
+    pub time_limit: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            warmup: 2,
+            samples: 20,
+            time_limit: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Parse,
+    Solve(u8),
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Phase::Parse => write!(f, "parse"),
+            Phase::Solve(part) => write!(f, "part{}", part),
+        }
+    }
+}
+
+impl Phase {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "parse" => Some(Phase::Parse),
+            _ => name.strip_prefix("part")?.parse().ok().map(Phase::Solve),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stats {
+    pub samples: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub p90: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+}
+
+impl Stats {
+    /// Panics if `samples` is empty.
+    pub fn new(mut samples: Vec<Duration>) -> Self {
+        samples.sort_unstable();
+        let total: Duration = samples.iter().sum();
+        Self {
+            samples: samples.len(),
+            min: samples[0],
+            median: percentile(&samples, 50),
+            p90: percentile(&samples, 90),
+            max: samples[samples.len() - 1],
+            mean: total / samples.len() as u32,
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted samples.
+fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+    let rank = (sorted.len() * percent).div_ceil(100);
+    sorted[rank.max(1) - 1]
+}
+
+/// The timings of one phase of a day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub year: u16,
+    pub day: u8,
+    pub phase: Phase,
+    pub stats: Stats,
+    /// What the first sample allocated, if the counting allocator is installed.
+    pub usage: Option<Usage>,
+}
+
+fn measure<T>(config: &Config, mut run: impl FnMut() -> T) -> (Stats, Option<Usage>, T) {
+    for _ in 0..config.warmup {
+        run();
+    }
+    let started = Instant::now();
+    let mut samples = Vec::with_capacity(config.samples);
+    let mut usage = None;
+    loop {
+        let start = Instant::now();
+        let result = if usage.is_none() && allocations::installed() {
+            let (result, first) = allocations::track(&mut run);
+            usage = Some(first);
+            result
+        } else {
+            run()
+        };
+        samples.push(start.elapsed());
+        if samples.len() >= config.samples || started.elapsed() >= config.time_limit {
+            return (Stats::new(samples), usage, result);
+        }
+    }
+}
+
+/// Measures parsing `input` and solving every part of `entry`.
+pub fn bench(entry: &Entry, input: &str, config: &Config) -> Result<Vec<Record>> {
+    let record = |phase, stats, usage| Record {
+        year: entry.year,
+        day: entry.day,
+        phase,
+        stats,
+        usage,
+    };
+    let (stats, usage, parsed) = measure(config, || entry.parse(input));
+    let parsed = parsed?;
+    let mut records = vec![record(Phase::Parse, stats, usage)];
+    for &part in entry.parts {
+        let (stats, usage, answer) = measure(config, || entry.solve(&parsed, part));
+        answer?;
+        records.push(record(Phase::Solve(part), stats, usage));
+    }
+    Ok(records)
+}
+
+pub fn to_json(records: &[Record]) -> Value {
+    let records: Vec<Value> = records
+        .iter()
+        .map(|record| {
+            let stats = &record.stats;
+            let mut json = json!({
+                "year": record.year,
+                "day": record.day,
+                "phase": record.phase.to_string(),
+                "samples": stats.samples,
+                "min_ns": stats.min.as_nanos() as u64,
+                "median_ns": stats.median.as_nanos() as u64,
+                "p90_ns": stats.p90.as_nanos() as u64,
+                "max_ns": stats.max.as_nanos() as u64,
+                "mean_ns": stats.mean.as_nanos() as u64,
+            });
+            if let Some(usage) = record.usage {
+                json["allocations"] = json!(usage.allocations);
+                json["allocated_bytes"] = json!(usage.bytes);
+                json["peak_heap_bytes"] = json!(usage.peak);
+            }
+            json
+        })
+        .collect();
+    json!({ "results": records })
+}
+
+/// Reads records written by `to_json`. Returns `None` if `json` isn't in that format.
+pub fn from_json(json: &Value) -> Option<Vec<Record>> {
+    let nanos = |record: &Value, key: &str| record[key].as_u64().map(Duration::from_nanos);
+    json["results"]
+        .as_array()?
+        .iter()
+        .map(|record| {
+            Some(Record {
+                year: record["year"].as_u64()?.try_into().ok()?,
+                day: record["day"].as_u64()?.try_into().ok()?,
+                phase: Phase::from_name(record["phase"].as_str()?)?,
+                stats: Stats {
+                    samples: record["samples"].as_u64()? as usize,
+                    min: nanos(record, "min_ns")?,
+                    median: nanos(record, "median_ns")?,
+                    p90: nanos(record, "p90_ns")?,
+                    max: nanos(record, "max_ns")?,
+                    mean: nanos(record, "mean_ns")?,
+                },
+                usage: record["allocations"].as_u64().map(|allocations| Usage {
+                    allocations,
+                    bytes: record["allocated_bytes"].as_u64().unwrap_or(0),
+                    peak: record["peak_heap_bytes"].as_u64().unwrap_or(0),
+                }),
+            })
+        })
+        .collect()
+}
+
+/// The median of a phase in a baseline and in the current run.
+pub struct Comparison {
+    pub year: u16,
+    pub day: u8,
+    pub phase: Phase,
+    pub baseline: Duration,
+    pub current: Duration,
+}
+
+impl Comparison {
+    /// How many times slower the current run is, e.g. `1.5` for 50% slower.
+    pub fn ratio(&self) -> f64 {
+        self.current.as_secs_f64() / self.baseline.as_secs_f64().max(1e-9)
+    }
+
+    /// Whether the current run is more than `threshold` slower, e.g. `0.1` for 10%.
+    pub fn is_regression(&self, threshold: f64) -> bool {
+        self.ratio() > 1.0 + threshold
+    }
+}
+
+/// Pairs up the phases that are in both `current` and `baseline`.
+pub fn compare(current: &[Record], baseline: &[Record]) -> Vec<Comparison> {
+    current
+        .iter()
+        .filter_map(|record| {
+            let old = baseline.iter().find(|old| {
+                (old.year, old.day, old.phase) == (record.year, record.day, record.phase)
+            })?;
+            Some(Comparison {
+                year: record.year,
+                day: record.day,
+                phase: record.phase,
+                baseline: old.stats.median,
+                current: record.stats.median,
+            })
+        })
+        .collect()
+}
+
+/// The phases that allocated the most bytes, most first.
+pub fn heaviest(records: &[Record], count: usize) -> Vec<&Record> {
+    let mut records: Vec<&Record> = records
+        .iter()
+        .filter(|record| record.usage.is_some())
+        .collect();
+    records.sort_by_key(|record| Reverse(record.usage.map(|usage| usage.bytes)));
+    records.truncate(count);
+    records
+}
+
+#[test]
+fn stats() {
+    let samples = (1..=10).rev().map(Duration::from_millis).collect();
+    let stats = Stats::new(samples);
+    assert_eq!(stats.min, Duration::from_millis(1));
+    assert_eq!(stats.median, Duration::from_millis(5));
+    assert_eq!(stats.p90, Duration::from_millis(9));
+    assert_eq!(stats.max, Duration::from_millis(10));
+    assert_eq!(stats.mean, Duration::from_micros(5500));
+    assert_eq!(Stats::new(vec![Duration::from_secs(1)]).p90.as_secs(), 1);
+}
+
+#[test]
+fn json_and_compare() {
+    let entry = crate::solution::find(2020, 1).unwrap();
+    let config = Config {
+        warmup: 0,
+        samples: 3,
+        ..Config::default()
+    };
+    let mut records = bench(entry, "1721\n979\n366\n299\n675\n1456", &config).unwrap();
+    assert_eq!(records.len(), 3);
+    for record in &mut records {
+        record.usage = None;
+    }
+    records[1].usage = Some(Usage {
+        allocations: 3,
+        bytes: 100,
+        peak: 60,
+    });
+    assert_eq!(records[2].phase, Phase::Solve(2));
+    assert_eq!(records[0].stats.samples, 3);
+
+    let baseline = from_json(&to_json(&records)).unwrap();
+    assert_eq!(baseline, records);
+    let mut slower = records.clone();
+    slower[1].stats.median = baseline[1].stats.median * 2 + Duration::from_nanos(2);
+    let comparisons = compare(&slower, &baseline);
+    assert_eq!(comparisons.len(), 3);
+    assert!(!comparisons[0].is_regression(0.1));
+    assert!(comparisons[1].is_regression(0.1));
+    assert_eq!(heaviest(&records, 5), [&records[1]]);
+}
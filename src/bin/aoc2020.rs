@@ -0,0 +1,902 @@
+//! Runs the solutions without `cargo aoc`.
+
+use std::{
+    env, fs,
+    io::{self, BufReader, Read, Write},
+    process, thread,
+    time::{Duration, Instant},
+};
+
+use aoc_2020::{
+    allocations,
+    bench::{self, Record},
+    checkpoint::{self, Checkpoints},
+    day2::{self, All},
+    day4::{self, Schema},
+    generators, property,
+    reference::{Reference, REFERENCES},
+    runner::{self, Job, Outcome, Report},
+    solution::{self, Answer, Entry},
+    stream,
+    trace::{self, Sink},
+    visualize::{self, Format},
+};
+use serde_json::{json, Value};
+
+const USAGE: &str = "usage:
+    aoc2020 run <day> [part] [--input <path>] [--json] [--timeout <secs>] [--trace <path>]
+                [--checkpoint <path>] [--checkpoint-every <secs>]
+    aoc2020 stream <day> [--input <path>]
+    aoc2020 audit [--input <path>] [--policy <policy>]...
+    aoc2020 validate [--input <path>] [--schema <path>]
+    aoc2020 all [--json] [--jobs <n>] [--timeout <secs>] [--trace <path>]
+    aoc2020 bench [day] [--samples <n>] [--warmup <n>] [--time-limit <secs>]
+                  [--output <path>] [--baseline <path>] [--threshold <percent>]
+    aoc2020 generate <day> [--seed <n>] [--size <n>]
+    aoc2020 check [day] [--seeds <n>] [--size <n>]
+    aoc2020 visualize <day> [part] [--input <path>] [--format ansi|ppm|pgm]
+                      [--output <dir>] [--scale <n>] [--delay <ms>]
+
+Inputs are read from input/2020/day<day>.txt unless --input is given.
+Use `--input -` to read from stdin. A byte order mark, Windows line endings and
+blank lines at the end of an input are ignored.
+
+`all` solves --jobs days at the same time (default: one per CPU) and prints a
+summary table. Parsing and every part may take --timeout seconds (default 60),
+after that they are reported as timed out.
+
+--trace writes what the solvers do as JSON Lines, one event per line with its
+`day` and `event` name, e.g. every instruction of day 8, every field elimination
+of day 16, every allergen deduction of day 21 and every round of day 22.
+Use `--trace -` for stdout.
+
+With --checkpoint, `run` saves the state of the simulation of days 11, 15, 17 and
+22 to that file every --checkpoint-every seconds (default 60), and continues from
+it when it already exists. Checkpoints of other inputs are refused. The file is
+deleted once the part is solved. This needs a part and ignores --timeout.
+
+`stream` reads the input one line at a time instead of loading it into memory,
+and prints the peak memory usage afterwards. It supports days 1, 2, 5, 6, 8, 9
+and 14, e.g. `aoc2020 generate 2 --size 10000000 | aoc2020 stream 2 --input -`.
+Day 1 entries can't be negative there.
+
+`audit` lists the passwords of a day 2 input that break a policy, and why. Every
+--policy has to be followed: `count` (part 1, the default), `positions` (part 2),
+`min-length=<n>`, `forbid=<text>` or `matches=<regex>`.
+
+`validate` lists the records of a day 4 input that break a schema, with their lines
+and every field that is missing or invalid, and counts how often every field fails.
+Records are blank-line separated `key:value` fields, checked with the rules of
+part 2 unless --schema gives a JSON file like schemas/passport.json.
+
+`bench` writes its results to target/bench.json unless --output is given.
+With --baseline, medians that got slower than --threshold percent (default 10)
+are reported as regressions. Build with --release for meaningful numbers.
+Built with `--features count-allocations`, it also reports how often and how much
+every phase allocates, and which phases allocate the most.
+
+`generate` prints a random input, e.g. for `aoc2020 run <day> --input -`.
+--size is roughly the number of lines (default 100).
+
+`check` compares the solvers with slow reference solutions on inputs generated
+from the seeds 0 to --seeds (default 100) and prints the smallest inputs they
+disagree on.
+
+`visualize` draws every generation of day 11, every cycle of day 17 as 2D slices
+or the picture of day 20 with its sea monsters. ANSI frames are played in the
+terminal, --delay milliseconds apart (default 200), unless --output is given.
+Images are written to --output (default target/frames) as <day>-<part>-<n>.ppm,
+with --scale pixels per cell (default 4).";
+
+const YEAR: u16 = 2020;
+
+#[cfg(feature = "count-allocations")]
+#[global_allocator]
+static ALLOCATOR: allocations::Counting = allocations::Counting;
+
+struct Options {
+    command: Command,
+    input: Option<String>,
+    json: bool,
+    trace: Option<String>,
+    checkpoint: Option<String>,
+    checkpoint_interval: Duration,
+    policies: Vec<String>,
+    schema: Option<String>,
+    runner: runner::Config,
+    bench: bench::Config,
+    output: Option<String>,
+    baseline: Option<String>,
+    threshold: f64,
+    seed: u64,
+    seeds: u64,
+    size: usize,
+    format: Format,
+    scale: usize,
+    delay: Duration,
+}
+
+enum Command {
+    Run { day: u8, part: Option<u8> },
+    Stream { day: u8 },
+    Audit,
+    Validate,
+    All,
+    Bench { day: Option<u8> },
+    Generate { day: u8 },
+    Check { day: Option<u8> },
+    Visualize { day: u8, part: u8 },
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+    match options.command {
+        Command::Bench { day } => process::exit(run_bench(day, &options)),
+        Command::Check { day } => process::exit(run_check(day, &options)),
+        Command::Stream { day } => process::exit(run_stream(day, &options)),
+        Command::Audit => process::exit(run_audit(&options)),
+        Command::Validate => process::exit(run_validate(&options)),
+        Command::Visualize { day, part } => process::exit(run_visualize(day, part, &options)),
+        Command::Generate { day } => match generators::generate(day, options.seed, options.size) {
+            Some(input) => {
+                println!("{}", input);
+                return;
+            }
+            None => {
+                eprintln!("there is no generator for day {}", day);
+                process::exit(2);
+            }
+        },
+        _ => {}
+    }
+    let sink = match options.trace.as_deref() {
+        None => None,
+        Some("-") => Some(Sink::new(io::stdout())),
+        Some(path) => match fs::File::create(path) {
+            Ok(file) => Some(Sink::new(io::BufWriter::new(file))),
+            Err(e) => {
+                eprintln!("can't create {}: {}", path, e);
+                process::exit(2);
+            }
+        },
+    };
+    let reports = trace::with_sink(sink.clone(), || match options.command {
+        Command::Run {
+            day,
+            part: Some(part),
+        } if options.checkpoint.is_some() => resume(day, part, &options),
+        Command::Run { day, part } => {
+            let entry = match solution::find(YEAR, day) {
+                Some(entry) => entry,
+                None => {
+                    eprintln!("day {} is not solved", day);
+                    process::exit(2);
+                }
+            };
+            let parts = match part {
+                Some(part) if entry.parts.contains(&part) => vec![part],
+                Some(part) => {
+                    eprintln!("day {} has no part {}", day, part);
+                    process::exit(2);
+                }
+                None => entry.parts.to_vec(),
+            };
+            run(
+                vec![entry],
+                Some(parts),
+                options.input.as_deref(),
+                &options.runner,
+            )
+        }
+        Command::All => run(
+            solution::registry().iter().collect(),
+            None,
+            None,
+            &options.runner,
+        ),
+        Command::Bench { .. }
+        | Command::Generate { .. }
+        | Command::Check { .. }
+        | Command::Stream { .. }
+        | Command::Audit
+        | Command::Validate
+        | Command::Visualize { .. } => unreachable!(),
+    });
+    if let Some(Err(e)) = sink.map(|sink| sink.finish()) {
+        eprintln!("can't write the trace: {}", e);
+        process::exit(1);
+    }
+    if options.json {
+        println!("{}", to_json(&reports));
+    } else {
+        print_human(&reports);
+        if let Command::All = options.command {
+            println!();
+            print_summary(&reports);
+        }
+    }
+    let solved = |report: &Report| matches!(report.outcome, Outcome::Solved(_));
+    if !reports.iter().all(solved) {
+        process::exit(1);
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut positional = vec![];
+    let mut input = None;
+    let mut json = false;
+    let mut trace = None;
+    let mut checkpoint = None;
+    let mut checkpoint_interval = Duration::from_secs(60);
+    let mut policies = vec![];
+    let mut schema = None;
+    let mut runner = runner::Config::default();
+    let mut bench = bench::Config::default();
+    let mut output = None;
+    let mut baseline = None;
+    let mut threshold = 10.0;
+    let mut seed = 0;
+    let mut seeds = 100;
+    let mut size = 100;
+    let mut format = Format::Ansi;
+    let mut scale = 4;
+    let mut delay = Duration::from_millis(200);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--json" => json = true,
+            "--input" => input = Some(value()?),
+            "--trace" => trace = Some(value()?),
+            "--checkpoint" => checkpoint = Some(value()?),
+            "--policy" => policies.push(value()?),
+            "--schema" => schema = Some(value()?),
+            "--checkpoint-every" => checkpoint_interval = parse_seconds(&arg, value()?)?,
+            "--jobs" => runner.jobs = parse_value(&arg, value()?)?,
+            "--timeout" => runner.timeout = parse_seconds(&arg, value()?)?,
+            "--samples" => bench.samples = parse_value(&arg, value()?)?,
+            "--warmup" => bench.warmup = parse_value(&arg, value()?)?,
+            "--time-limit" => bench.time_limit = parse_seconds(&arg, value()?)?,
+            "--output" => output = Some(value()?),
+            "--baseline" => baseline = Some(value()?),
+            "--threshold" => threshold = parse_value(&arg, value()?)?,
+            "--seed" => seed = parse_value(&arg, value()?)?,
+            "--seeds" => seeds = parse_value(&arg, value()?)?,
+            "--size" => size = parse_value(&arg, value()?)?,
+            "--format" => {
+                format = match value()?.as_str() {
+                    "ansi" => Format::Ansi,
+                    "ppm" => Format::Ppm,
+                    "pgm" => Format::Pgm,
+                    other => return Err(format!("unknown format `{}`", other)),
+                }
+            }
+            "--scale" => scale = parse_value(&arg, value()?)?,
+            "--delay" => delay = Duration::from_millis(parse_value(&arg, value()?)?),
+            "-h" | "--help" => return Err("aoc2020: Advent of Code 2020 solutions".to_string()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option `{}`", arg)),
+            _ => positional.push(arg),
+        }
+    }
+    let number = |arg: &String, what: &str| {
+        arg.parse::<u8>()
+            .map_err(|_| format!("invalid {} `{}`", what, arg))
+    };
+    let command = match positional.as_slice() {
+        [command, day] if command == "run" => Command::Run {
+            day: number(day, "day")?,
+            part: None,
+        },
+        [command, day, part] if command == "run" => Command::Run {
+            day: number(day, "day")?,
+            part: Some(number(part, "part")?),
+        },
+        [command, day] if command == "stream" => Command::Stream {
+            day: number(day, "day")?,
+        },
+        [command] if command == "audit" => Command::Audit,
+        [command] if command == "validate" => Command::Validate,
+        [command] if command == "all" => {
+            if input.is_some() {
+                return Err("`all` always reads the inputs from input/2020".to_string());
+            }
+            Command::All
+        }
+        [command] if command == "bench" => {
+            if input.is_some() {
+                return Err("--input needs a day to benchmark".to_string());
+            }
+            Command::Bench { day: None }
+        }
+        [command, day] if command == "bench" => Command::Bench {
+            day: Some(number(day, "day")?),
+        },
+        [command, day] if command == "generate" => Command::Generate {
+            day: number(day, "day")?,
+        },
+        [command] if command == "check" => Command::Check { day: None },
+        [command, day] if command == "check" => Command::Check {
+            day: Some(number(day, "day")?),
+        },
+        [command, day] if command == "visualize" => Command::Visualize {
+            day: number(day, "day")?,
+            part: 1,
+        },
+        [command, day, part] if command == "visualize" => Command::Visualize {
+            day: number(day, "day")?,
+            part: number(part, "part")?,
+        },
+        [] => return Err("missing command".to_string()),
+        _ => return Err(format!("invalid arguments `{}`", positional.join(" "))),
+    };
+    if trace.is_some() && !matches!(command, Command::Run { .. } | Command::All) {
+        return Err("--trace only works with `run` and `all`".to_string());
+    }
+    if checkpoint.is_some() && !matches!(command, Command::Run { part: Some(_), .. }) {
+        return Err("--checkpoint needs `run` with a day and a part".to_string());
+    }
+    if runner.jobs == 0 {
+        return Err("--jobs has to be at least 1".to_string());
+    }
+    if scale == 0 {
+        return Err("--scale has to be at least 1".to_string());
+    }
+    if bench.samples == 0 {
+        return Err("--samples has to be at least 1".to_string());
+    }
+    Ok(Options {
+        command,
+        input,
+        json,
+        trace,
+        checkpoint,
+        checkpoint_interval,
+        policies,
+        schema,
+        runner,
+        bench,
+        output,
+        baseline,
+        threshold,
+        seed,
+        seeds,
+        size,
+        format,
+        scale,
+        delay,
+    })
+}
+
+fn parse_value<T: std::str::FromStr>(option: &str, value: String) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value `{}` for {}", value, option))
+}
+
+/// A duration given in seconds, which can have a fraction.
+fn parse_seconds(option: &str, value: String) -> Result<Duration, String> {
+    value
+        .parse()
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or_else(|| format!("invalid value `{}` for {}", value, option))
+}
+
+/// The input as it is. The parsers normalize it the way their day wants.
+fn read_input(day: u8, path: Option<&str>) -> io::Result<String> {
+    match path {
+        Some("-") => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            Ok(input)
+        }
+        Some(path) => fs::read_to_string(path),
+        None => fs::read_to_string(format!("input/{}/day{}.txt", YEAR, day)),
+    }
+}
+
+/// Draws `part` of `day` and returns the exit code.
+fn run_visualize(day: u8, part: u8, options: &Options) -> i32 {
+    let input = match read_input(day, options.input.as_deref()) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("can't read the input: {}", e);
+            return 1;
+        }
+    };
+    let frames = match visualize::frames(day, part, &input) {
+        Some(Ok(frames)) => frames,
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+        None => {
+            eprintln!("day {} can't be visualized", day);
+            return 2;
+        }
+    };
+    if options.format == Format::Ansi && options.output.is_none() {
+        // clear the screen once, afterwards every frame overwrites the previous one
+        print!("\x1b[2J");
+        for (idx, frame) in frames.iter().enumerate() {
+            print!("\x1b[H{}", visualize::ansi(frame));
+            println!(
+                "day {} part {}: frame {}/{}",
+                day,
+                part,
+                idx + 1,
+                frames.len()
+            );
+            thread::sleep(options.delay);
+        }
+        return 0;
+    }
+    let dir = options.output.as_deref().unwrap_or("target/frames");
+    let written = fs::create_dir_all(dir).and_then(|()| {
+        for (idx, frame) in frames.iter().enumerate() {
+            let path = format!(
+                "{}/{}-{}-{:03}.{}",
+                dir,
+                day,
+                part,
+                idx,
+                options.format.extension()
+            );
+            let mut file = io::BufWriter::new(fs::File::create(path)?);
+            match options.format {
+                Format::Ansi => file.write_all(visualize::ansi(frame).as_bytes())?,
+                format => visualize::write_image(frame, format, options.scale, &mut file)?,
+            }
+            file.flush()?;
+        }
+        Ok(())
+    });
+    match written {
+        Ok(()) => {
+            println!("wrote {} frames to {}", frames.len(), dir);
+            0
+        }
+        Err(e) => {
+            eprintln!("can't write the frames: {}", e);
+            1
+        }
+    }
+}
+
+/// Lists the day 2 passwords that break the policies and returns the exit code.
+fn run_audit(options: &Options) -> i32 {
+    let policies = if options.policies.is_empty() {
+        vec!["count".to_string()]
+    } else {
+        options.policies.clone()
+    };
+    let policy = match policies.iter().map(|policy| day2::policy(policy)).collect() {
+        Ok(policies) => All(policies),
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    let input = read_input(2, options.input.as_deref())
+        .map_err(|e| format!("can't read the input: {}", e))
+        .and_then(|input| day2::parse(&input).map_err(|e| e.to_string()));
+    let input = match input {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    let violations = day2::audit(&input, &policy);
+    for violation in &violations {
+        println!("{}", violation);
+    }
+    println!(
+        "{} of {} passwords break the policy",
+        violations.len(),
+        input.passwords.len()
+    );
+    i32::from(!violations.is_empty())
+}
+
+/// Lists the day 4 records that break the schema, and why, and returns the exit code.
+fn run_validate(options: &Options) -> i32 {
+    let schema = match &options.schema {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|e| format!("can't read the schema: {}", e))
+            .and_then(|json| Schema::from_json(&json)),
+        None => Ok(Schema::passport()),
+    };
+    let schema = match schema {
+        Ok(schema) => schema,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    let input = read_input(4, options.input.as_deref())
+        .map_err(|e| format!("can't read the input: {}", e))
+        .and_then(|input| day4::parse(&input).map_err(|e| e.to_string()));
+    let input = match input {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    let rejections = day4::audit(&input, &schema);
+    for rejection in &rejections {
+        println!("{}", rejection);
+    }
+    if !rejections.is_empty() {
+        println!();
+        for (reason, count) in day4::summary(&rejections) {
+            println!("{:>6}  {}", count, reason);
+        }
+        println!();
+    }
+    println!(
+        "{} of {} records are valid",
+        input.passports.len() - rejections.len(),
+        input.passports.len()
+    );
+    i32::from(!rejections.is_empty())
+}
+
+/// Solves `day` with its streaming solver and returns the exit code.
+fn run_stream(day: u8, options: &Options) -> i32 {
+    let streaming = match stream::find(day) {
+        Some(streaming) => streaming,
+        None => {
+            eprintln!("there is no streaming solver for day {}", day);
+            return 2;
+        }
+    };
+    let start = Instant::now();
+    let answers = match options.input.as_deref() {
+        Some("-") => (streaming.solve)(&mut io::stdin().lock()),
+        path => {
+            let default = format!("input/{}/day{}.txt", YEAR, day);
+            match fs::File::open(path.unwrap_or(&default)) {
+                Ok(file) => (streaming.solve)(&mut BufReader::new(file)),
+                Err(e) => {
+                    eprintln!("can't read the input: {}", e);
+                    return 1;
+                }
+            }
+        }
+    };
+    let time = start.elapsed();
+    let answers = match answers {
+        Ok(answers) => answers,
+        Err(e) => {
+            println!("day {:>2}: error: {}", day, e);
+            return 1;
+        }
+    };
+    for (part, answer) in (1..).zip(&answers) {
+        println!("day {:>2} part {}: {}", day, part, answer);
+    }
+    match peak_memory() {
+        Some(bytes) => println!(
+            "took {:?}, peak memory {:.1} MiB",
+            time,
+            bytes as f64 / (1 << 20) as f64
+        ),
+        None => println!("took {:?}", time),
+    }
+    0
+}
+
+/// The peak resident set size of this process in bytes, if the OS tells us.
+fn peak_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kib: u64 = line["VmHWM:".len()..]
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+/// Solves `part` of `day` on this thread, saving checkpoints along the way.
+fn resume(day: u8, part: u8, options: &Options) -> Vec<Report> {
+    let Some(resumable) = checkpoint::find(day) else {
+        eprintln!("day {} can't be checkpointed", day);
+        process::exit(2);
+    };
+    if !(1..=2).contains(&part) {
+        eprintln!("day {} has no part {}", day, part);
+        process::exit(2);
+    }
+    let path = options.checkpoint.as_deref().unwrap();
+    let mut checkpoints = Checkpoints::new(path, day, part, options.checkpoint_interval);
+    if checkpoints.path().exists() {
+        eprintln!("resuming from {}", path);
+    }
+    let outcome = match read_input(day, options.input.as_deref()) {
+        Ok(input) => {
+            let start = Instant::now();
+            let answer = resumable.run(&input, part, &mut checkpoints);
+            let time = start.elapsed();
+            return vec![Report {
+                day,
+                part,
+                outcome: match answer {
+                    Ok(answer) => Outcome::Solved(answer),
+                    Err(e) => Outcome::Failed(e.to_string()),
+                },
+                parse_time: None,
+                solve_time: Some(time),
+            }];
+        }
+        Err(e) => Outcome::Failed(format!("can't read the input: {}", e)),
+    };
+    Report::all(day, &[part], outcome, None)
+}
+
+/// Solves `parts`, or all parts, of the days in `entries`. The inputs are read up front.
+fn run(
+    entries: Vec<&'static Entry>,
+    parts: Option<Vec<u8>>,
+    path: Option<&str>,
+    config: &runner::Config,
+) -> Vec<Report> {
+    let mut jobs = vec![];
+    let mut unreadable = vec![];
+    for entry in entries {
+        let parts = parts.clone().unwrap_or_else(|| entry.parts.to_vec());
+        match read_input(entry.day, path) {
+            Ok(input) => jobs.push(Job {
+                entry,
+                parts,
+                input,
+            }),
+            Err(e) => unreadable.extend(Report::all(
+                entry.day,
+                &parts,
+                Outcome::Failed(format!("can't read the input: {}", e)),
+                None,
+            )),
+        }
+    }
+    let mut reports = runner::run(jobs, config);
+    reports.extend(unreadable);
+    reports.sort_by_key(|report| (report.day, report.part));
+    reports
+}
+
+fn print_human(reports: &[Report]) {
+    for report in reports {
+        let timings = match (report.parse_time, report.solve_time) {
+            (Some(parse_time), Some(solve_time)) => {
+                format!(" (parse {:?}, solve {:?})", parse_time, solve_time)
+            }
+            (Some(parse_time), None) => format!(" (parse {:?})", parse_time),
+            (None, Some(solve_time)) => format!(" (solve {:?})", solve_time),
+            _ => String::new(),
+        };
+        println!(
+            "day {:>2} part {}: {:<20}{}",
+            report.day,
+            report.part,
+            report.outcome.to_string(),
+            timings
+        );
+    }
+}
+
+/// A table with a row per day and the status of its parts.
+fn print_summary(reports: &[Report]) {
+    println!("day  part 1   part 2   time");
+    let mut days: Vec<u8> = reports.iter().map(|report| report.day).collect();
+    days.dedup();
+    for day in days {
+        let reports: Vec<&Report> = reports.iter().filter(|report| report.day == day).collect();
+        let status = |part| match reports.iter().find(|report| report.part == part) {
+            Some(report) => status(&report.outcome),
+            None => "-",
+        };
+        let time: Duration = reports[0].parse_time.unwrap_or_default()
+            + reports
+                .iter()
+                .filter_map(|report| report.solve_time)
+                .sum::<Duration>();
+        println!("{:>3}  {:<7}  {:<7}  {:?}", day, status(1), status(2), time);
+    }
+    let count = |wanted| {
+        reports
+            .iter()
+            .filter(|report| status(&report.outcome) == wanted)
+            .count()
+    };
+    println!(
+        "\n{} parts passed, {} failed, {} timed out",
+        count("pass"),
+        count("fail"),
+        count("timeout")
+    );
+}
+
+fn status(outcome: &Outcome) -> &'static str {
+    match outcome {
+        Outcome::Solved(_) => "pass",
+        Outcome::Failed(_) => "fail",
+        Outcome::TimedOut(_) => "timeout",
+    }
+}
+
+fn to_json(reports: &[Report]) -> Value {
+    let results: Vec<Value> = reports
+        .iter()
+        .map(|report| {
+            let mut result = json!({
+                "year": YEAR,
+                "day": report.day,
+                "part": report.part,
+                "parse_ns": report.parse_time.map(|time| time.as_nanos() as u64),
+                "solve_ns": report.solve_time.map(|time| time.as_nanos() as u64),
+            });
+            match &report.outcome {
+                Outcome::Solved(Answer::Int(n)) => result["answer"] = json!(n),
+                // too large for some JSON parsers, so it's a string
+                Outcome::Solved(Answer::BigInt(n)) => result["answer"] = json!(n.to_string()),
+                Outcome::Solved(Answer::String(s)) => result["answer"] = json!(s),
+                Outcome::Failed(e) => result["error"] = json!(e),
+                Outcome::TimedOut(timeout) => {
+                    result["error"] = json!(report.outcome.to_string());
+                    result["timeout_ns"] = json!(timeout.as_nanos() as u64);
+                }
+            }
+            result
+        })
+        .collect();
+    json!({ "results": results })
+}
+
+/// Benchmarks `day`, or all days, and returns the exit code.
+fn run_bench(day: Option<u8>, options: &Options) -> i32 {
+    let entries: Vec<&Entry> = match day {
+        Some(day) => match solution::find(YEAR, day) {
+            Some(entry) => vec![entry],
+            None => {
+                eprintln!("day {} is not solved", day);
+                return 2;
+            }
+        },
+        None => solution::registry().iter().collect(),
+    };
+    let baseline = match &options.baseline {
+        Some(path) => match read_records(path) {
+            Ok(baseline) => Some(baseline),
+            Err(e) => {
+                eprintln!("can't read the baseline {}: {}", path, e);
+                return 2;
+            }
+        },
+        None => None,
+    };
+
+    let mut failed = false;
+    let mut records: Vec<Record> = vec![];
+    for entry in entries {
+        let path = if day.is_some() {
+            options.input.as_deref()
+        } else {
+            None
+        };
+        let result = read_input(entry.day, path)
+            .map_err(|e| format!("can't read the input: {}", e))
+            .and_then(|input| {
+                bench::bench(entry, &input, &options.bench).map_err(|e| e.to_string())
+            });
+        match result {
+            Ok(day_records) => {
+                for record in &day_records {
+                    let stats = &record.stats;
+                    println!(
+                        "day {:>2} {:<5}  median {:>12?}  p90 {:>12?}  min {:>12?}  ({} samples)",
+                        record.day, record.phase, stats.median, stats.p90, stats.min, stats.samples
+                    );
+                    if let Some(usage) = record.usage {
+                        println!("               {}", usage);
+                    }
+                }
+                records.extend(day_records);
+            }
+            Err(e) => {
+                println!("day {:>2} error: {}", entry.day, e);
+                failed = true;
+            }
+        }
+    }
+
+    let heaviest = bench::heaviest(&records, 5);
+    if !heaviest.is_empty() {
+        println!("\nmost allocated bytes:");
+        for record in heaviest {
+            let usage = record.usage.unwrap_or_default();
+            println!(
+                "day {:>2} {:<5}  {:>10}  {:>10} allocations  {:>10} peak",
+                record.day,
+                record.phase,
+                allocations::Bytes(usage.bytes).to_string(),
+                usage.allocations,
+                allocations::Bytes(usage.peak).to_string()
+            );
+        }
+    }
+
+    let output = options.output.as_deref().unwrap_or("target/bench.json");
+    if let Err(e) = fs::write(output, bench::to_json(&records).to_string()) {
+        eprintln!("can't write {}: {}", output, e);
+        failed = true;
+    }
+
+    if let Some(baseline) = baseline {
+        let threshold = options.threshold / 100.0;
+        let comparisons = bench::compare(&records, &baseline);
+        let regressions: Vec<_> = comparisons
+            .iter()
+            .filter(|comparison| comparison.is_regression(threshold))
+            .collect();
+        println!(
+            "\ncompared {} phases with the baseline, {} regressed by more than {}%",
+            comparisons.len(),
+            regressions.len(),
+            options.threshold
+        );
+        for regression in &regressions {
+            println!(
+                "day {:>2} {:<5}  {:?} -> {:?}  (+{:.0}%)",
+                regression.day,
+                regression.phase,
+                regression.baseline,
+                regression.current,
+                (regression.ratio() - 1.0) * 100.0
+            );
+        }
+        failed |= !regressions.is_empty();
+    }
+    i32::from(failed)
+}
+
+/// Checks the solvers of `day`, or of all days, against their references and returns
+/// the exit code.
+fn run_check(day: Option<u8>, options: &Options) -> i32 {
+    let references: Vec<&Reference> = REFERENCES
+        .iter()
+        .filter(|reference| day.is_none_or(|day| reference.day == day))
+        .collect();
+    if let (Some(day), true) = (day, references.is_empty()) {
+        eprintln!("there is no reference solution for day {}", day);
+        return 2;
+    }
+    let mut failed = false;
+    for reference in references {
+        match property::check(reference, 0..options.seeds, options.size) {
+            Ok(()) => println!(
+                "day {:>2} part {}: ok ({} inputs)",
+                reference.day, reference.part, options.seeds
+            ),
+            Err(counterexample) => {
+                println!("{}\n", counterexample);
+                failed = true;
+            }
+        }
+    }
+    i32::from(failed)
+}
+
+fn read_records(path: &str) -> Result<Vec<Record>, String> {
+    let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let json = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+    bench::from_json(&json).ok_or_else(|| "not a benchmark result".to_string())
+}
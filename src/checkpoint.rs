@@ -0,0 +1,317 @@
+//! Saving the state of long simulations to disk, so that they can be resumed after
+//! they were stopped: the spoken numbers of day 15, the seating area of day 11, the
+//! pocket dimension of day 17 and the games of Recursive Combat of day 22.
+//!
+//! A checkpoint is a small header with the day, the part and a fingerprint of the
+//! input, followed by the state in a compact binary encoding.
+
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    day11, day15, day17, day22,
+    error::{ParseError, Result},
+    grid::{Cell, Grid},
+    solution::{self, Answer},
+};
+
+const MAGIC: &[u8] = b"aoc2020 checkpoint\n";
+const VERSION: u8 = 3;
+
+/// The state of a simulation, which can be written to a checkpoint.
+pub trait State: Sized {
+    fn encode(&self, out: &mut Encoder);
+    fn decode(input: &mut Decoder) -> Result<Self>;
+}
+
+#[derive(Default)]
+pub struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    pub fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.bytes.extend(value.to_le_bytes());
+    }
+
+    pub fn usize(&mut self, value: usize) {
+        self.u64(value as u64);
+    }
+
+    pub fn i64(&mut self, value: i64) {
+        self.bytes.extend(value.to_le_bytes());
+    }
+
+    pub fn grid<T: Cell>(&mut self, grid: &Grid<T>) {
+        self.usize(grid.width());
+        self.usize(grid.height());
+        // one byte per cell, the chars of all cells are ASCII
+        for cell in grid.cells() {
+            self.u8(cell.to_char() as u8);
+        }
+    }
+}
+
+pub struct Decoder<'a> {
+    day: u8,
+    bytes: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    /// An error about a checkpoint that wasn't written by `save`.
+    pub fn corrupt(&self) -> ParseError {
+        ParseError::input(self.day, "the checkpoint is corrupt")
+    }
+
+    pub fn u8(&mut self) -> Result<u8> {
+        let (&value, rest) = self.bytes.split_first().ok_or_else(|| self.corrupt())?;
+        self.bytes = rest;
+        Ok(value)
+    }
+
+    pub fn u64(&mut self) -> Result<u64> {
+        let (value, rest) = self
+            .bytes
+            .split_first_chunk()
+            .ok_or_else(|| self.corrupt())?;
+        self.bytes = rest;
+        Ok(u64::from_le_bytes(*value))
+    }
+
+    pub fn usize(&mut self) -> Result<usize> {
+        let value = self.u64()?;
+        value.try_into().map_err(|_| self.corrupt())
+    }
+
+    pub fn i64(&mut self) -> Result<i64> {
+        Ok(self.u64()? as i64)
+    }
+
+    /// The length of a sequence of items that take at least `size` bytes each.
+    pub fn len(&mut self, size: usize) -> Result<usize> {
+        let len = self.usize()?;
+        if len.saturating_mul(size) > self.bytes.len() {
+            return Err(self.corrupt());
+        }
+        Ok(len)
+    }
+
+    pub fn grid<T: Cell>(&mut self) -> Result<Grid<T>> {
+        let width = self.usize()?;
+        let height = self.usize()?;
+        let len = width.checked_mul(height).ok_or_else(|| self.corrupt())?;
+        if len > self.bytes.len() {
+            return Err(self.corrupt());
+        }
+        let cells = (0..len)
+            .map(|_| T::from_char(self.u8()? as char).ok_or_else(|| self.corrupt()))
+            .collect::<Result<_>>()?;
+        Ok(Grid::new(width, height, cells))
+    }
+}
+
+/// The checkpoint file of a part, and how often to write it.
+pub struct Checkpoints {
+    path: PathBuf,
+    day: u8,
+    part: u8,
+    /// The `fingerprint` of the input the simulation runs on.
+    input: u64,
+    interval: Duration,
+    last: Instant,
+}
+
+impl Checkpoints {
+    pub fn new(path: impl Into<PathBuf>, day: u8, part: u8, interval: Duration) -> Self {
+        Self {
+            path: path.into(),
+            day,
+            part,
+            input: fingerprint(""),
+            interval,
+            last: Instant::now(),
+        }
+    }
+
+    /// Ties the checkpoints to `input`, so that a checkpoint of another input isn't
+    /// resumed. `Resumable::run` does this with the normalized input.
+    pub fn set_input(&mut self, input: &str) {
+        self.input = fingerprint(input);
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The state in the checkpoint file, or `None` if there is no file yet.
+    pub fn load<S: State>(&self) -> Result<Option<S>> {
+        let bytes = match fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(self.io_error("can't read", e)),
+        };
+        let mut decoder = Decoder {
+            day: self.day,
+            bytes: &bytes,
+        };
+        let header = decoder
+            .bytes
+            .strip_prefix(MAGIC)
+            .and_then(|rest| rest.split_first_chunk());
+        let Some((&[version, day, part], rest)) = header else {
+            return Err(decoder.corrupt());
+        };
+        if version != VERSION || (day, part) != (self.day, self.part) {
+            return Err(ParseError::input(
+                self.day,
+                format!(
+                    "{} is a checkpoint of day {} part {}, not of day {} part {}",
+                    self.path.display(),
+                    day,
+                    part,
+                    self.day,
+                    self.part
+                ),
+            ));
+        }
+        decoder.bytes = rest;
+        if decoder.u64()? != self.input {
+            return Err(ParseError::input(
+                self.day,
+                format!(
+                    "{} is a checkpoint of a different input",
+                    self.path.display()
+                ),
+            ));
+        }
+        let state = S::decode(&mut decoder)?;
+        if !decoder.bytes.is_empty() {
+            return Err(decoder.corrupt());
+        }
+        Ok(Some(state))
+    }
+
+    /// Writes `state` to the checkpoint file. The previous checkpoint stays intact if
+    /// this fails half-way.
+    pub fn save<S: State>(&mut self, state: &S) -> Result<()> {
+        let mut encoder = Encoder::default();
+        encoder.bytes.extend(MAGIC);
+        encoder.bytes.extend([VERSION, self.day, self.part]);
+        encoder.u64(self.input);
+        state.encode(&mut encoder);
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".tmp");
+        fs::write(&temporary, &encoder.bytes)
+            .and_then(|()| fs::rename(&temporary, &self.path))
+            .map_err(|e| self.io_error("can't write", e))?;
+        self.last = Instant::now();
+        Ok(())
+    }
+
+    /// Saves `state` if the interval has passed since the last save. Simulations call
+    /// this every few steps.
+    pub fn save_if_due<S: State>(&mut self, state: &S) -> Result<()> {
+        if self.last.elapsed() >= self.interval {
+            self.save(state)?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the checkpoint file once the simulation is done.
+    pub fn finish(&self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(self.io_error("can't delete", e)),
+            _ => Ok(()),
+        }
+    }
+
+    fn io_error(&self, action: &str, e: io::Error) -> ParseError {
+        ParseError::input(
+            self.day,
+            format!("{} the checkpoint {}: {}", action, self.path.display(), e),
+        )
+    }
+}
+
+/// FNV-1a, which unlike the hashers of the standard library is guaranteed to stay the
+/// same between builds.
+fn fingerprint(input: &str) -> u64 {
+    input.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+pub struct Resumable {
+    pub day: u8,
+    solve: fn(&str, u8, &mut Checkpoints) -> Result<Answer>,
+}
+
+impl Resumable {
+    /// Solves `part` of `input`, starting from the checkpoint if there is one.
+    pub fn run(&self, input: &str, part: u8, checkpoints: &mut Checkpoints) -> Result<Answer> {
+        let input = match solution::find(2020, self.day) {
+            Some(entry) => entry.normalize(input),
+            None => input.into(),
+        };
+        checkpoints.set_input(&input);
+        (self.solve)(&input, part, checkpoints)
+    }
+}
+
+pub static RESUMABLE: &[Resumable] = &[
+    Resumable {
+        day: 11,
+        solve: |input, part, checkpoints| {
+            day11::resume(&day11::parse(input)?, part, checkpoints).map(Answer::from)
+        },
+    },
+    Resumable {
+        day: 15,
+        solve: |input, part, checkpoints| {
+            day15::resume(&day15::parse(input)?, part, checkpoints).map(Answer::from)
+        },
+    },
+    Resumable {
+        day: 17,
+        solve: |input, part, checkpoints| {
+            day17::resume(&day17::parse(input)?, part, checkpoints).map(Answer::from)
+        },
+    },
+    Resumable {
+        day: 22,
+        solve: |input, part, checkpoints| {
+            day22::resume(&day22::parse(input)?, part, checkpoints).map(Answer::from)
+        },
+    },
+];
+
+pub fn find(day: u8) -> Option<&'static Resumable> {
+    RESUMABLE.iter().find(|resumable| resumable.day == day)
+}
+
+#[test]
+fn grids() {
+    let grid: Grid<bool> = Grid::new(3, 2, vec![true, false, true, false, false, true]);
+    let mut out = Encoder::default();
+    out.grid(&grid);
+    assert_eq!(out.bytes.len(), 2 * 8 + 6);
+    let mut input = Decoder {
+        day: 17,
+        bytes: &out.bytes,
+    };
+    assert_eq!(input.grid::<bool>(), Ok(grid));
+    assert!(input.bytes.is_empty());
+    let mut input = Decoder {
+        day: 17,
+        bytes: &out.bytes[..out.bytes.len() - 1],
+    };
+    assert!(input.grid::<bool>().is_err());
+}
@@ -1,34 +1,490 @@
-#[aoc(day1, part1)]
-fn part1(input: &str) -> i32 {
-    let input: Vec<i32> = input.split('\n').map(|s| s.parse().unwrap()).collect();
-    for &a in input.iter() {
-        if a >= 2020 / 2 {
-            continue;
+use std::{collections::BinaryHeap, io::BufRead, ops::ControlFlow};
+
+use fxhash::FxHashSet;
+
+use crate::{
+    error::{ParseError, Result, Source},
+    normalize,
+    number_theory::gcd,
+    parser::{int, lines},
+    stream::Lines,
+};
+
+pub struct Input {
+    pub entries: Vec<i64>,
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day1))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(1, input);
+    Ok(Input {
+        entries: Source::new(1, input).run(lines(int()))?,
+    })
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day1, part1))]
+pub fn part1(input: &Input) -> Result<i64> {
+    product_of_sum(&input.entries, 2)
+}
+#[cfg_attr(feature = "aoc-runner", aoc(day1, part2))]
+pub fn part2(input: &Input) -> Result<i64> {
+    product_of_sum(&input.entries, 3)
+}
+
+/// The product of `k` entries that sum to 2020.
+fn product_of_sum(entries: &[i64], k: usize) -> Result<i64> {
+    let Some(indices) = first_k_sum(entries, k, 2020) else {
+        let count = if k == 2 { "two" } else { "three" };
+        return Err(ParseError::input(
+            1,
+            format!("no {} entries sum to 2020", count),
+        ));
+    };
+    indices
+        .iter()
+        .try_fold(1i64, |product, &idx| product.checked_mul(entries[idx]))
+        .ok_or_else(|| ParseError::input(1, "the product of the entries is too large"))
+}
+
+/// All ways to pick `k` entries of `values` that sum to `target`, as increasing indices
+/// in lexicographic order. Equal values are different entries.
+pub fn k_sum(values: &[i64], k: usize, target: i64) -> Vec<Vec<usize>> {
+    let mut tuples = vec![];
+    let distinct = Distinct::new(values);
+    let _ = distinct.sums(k, target, |positions| {
+        distinct.expand(positions, &mut |tuple| tuples.push(tuple));
+        ControlFlow::<()>::Continue(())
+    });
+    tuples.sort_unstable();
+    tuples
+}
+
+/// A way to pick `k` entries of `values` that sum to `target`, as increasing indices.
+pub fn first_k_sum(values: &[i64], k: usize, target: i64) -> Option<Vec<usize>> {
+    let distinct = Distinct::new(values);
+    let found = distinct.sums(k, target, |positions| {
+        ControlFlow::Break(positions.to_vec())
+    });
+    let ControlFlow::Break(positions) = found else {
+        return None;
+    };
+    // the first entries with each value
+    let mut tuple: Vec<usize> = positions
+        .chunk_by(|a, b| a == b)
+        .flat_map(|run| distinct.indices[run[0]][..run.len()].iter().copied())
+        .collect();
+    tuple.sort_unstable();
+    Some(tuple)
+}
+
+/// The distinct values of the entries, in increasing order, with the indices of the entries
+/// that have them. Sums are searched among these, so that duplicates are only tried once.
+struct Distinct {
+    values: Vec<i64>,
+    indices: Vec<Vec<usize>>,
+}
+
+impl Distinct {
+    fn new(values: &[i64]) -> Self {
+        let mut order: Vec<usize> = (0..values.len()).collect();
+        order.sort_by_key(|&idx| (values[idx], idx));
+        let mut distinct = Distinct {
+            values: vec![],
+            indices: vec![],
+        };
+        for idx in order {
+            if distinct.values.last() != Some(&values[idx]) {
+                distinct.values.push(values[idx]);
+                distinct.indices.push(vec![]);
+            }
+            distinct.indices.last_mut().unwrap().push(idx);
+        }
+        distinct
+    }
+
+    /// Calls `found` with every multiset of `k` positions whose values sum to `target`, as
+    /// non-decreasing positions, until it breaks.
+    ///
+    /// Tries every choice of all but the last four positions, and finds the last four in
+    /// the middle: as two pairs, taken from two streams of pairs that walk towards each
+    /// other in the order of their sums. That takes about `d^2 log d` steps for the four
+    /// instead of `d^4`, for `d` distinct values. Fewer than four are found with two
+    /// pointers over the values. Choices that can't reach `target` anymore are skipped.
+    fn sums<B>(
+        &self,
+        k: usize,
+        target: i64,
+        mut found: impl FnMut(&[usize]) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        // all values have the same remainder modulo `step`, and so have all sums of `k`
+        if let Some(&first) = self.values.first() {
+            let step = self
+                .values
+                .iter()
+                .fold(0, |step, &value| gcd(step, value.abs_diff(first)));
+            let offset = target as i128 - k as i128 * first as i128;
+            if step > 1 && offset.rem_euclid(step as i128) != 0 {
+                return ControlFlow::Continue(());
+            }
         }
-        let b = 2020 - a;
-        if input.contains(&b) {
-            return a * b;
+        self.search(k, 0, target as i128, &mut Vec::with_capacity(k), &mut found)
+    }
+
+    fn search<B>(
+        &self,
+        k: usize,
+        start: usize,
+        rest: i128,
+        positions: &mut Vec<usize>,
+        found: &mut impl FnMut(&[usize]) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        let value = |position: usize| self.values[position] as i128;
+        match k {
+            0 if rest == 0 => found(positions),
+            0 => ControlFlow::Continue(()),
+            1 => {
+                let values = &self.values[start..];
+                let position = i64::try_from(rest)
+                    .ok()
+                    .and_then(|rest| values.binary_search(&rest).ok());
+                match position.map(|position| start + position) {
+                    Some(position) if self.unused(positions, position) > 0 => {
+                        self.found_with(positions, &[position], found)
+                    }
+                    _ => ControlFlow::Continue(()),
+                }
+            }
+            2 => {
+                // values too big to go with the smallest one that is left
+                let lowest = self.values.get(start).map_or(0, |&v| v as i128);
+                let mut high = self
+                    .values
+                    .partition_point(|&v| (v as i128) + lowest <= rest);
+                let mut low = start;
+                while low < high {
+                    let sum = value(low) + value(high - 1);
+                    if sum < rest {
+                        low += 1;
+                    } else if sum > rest {
+                        high -= 1;
+                    } else {
+                        let needed = if low == high - 1 { 2 } else { 1 };
+                        if self.unused(positions, low) >= needed
+                            && self.unused(positions, high - 1) > 0
+                        {
+                            self.found_with(positions, &[low, high - 1], found)?;
+                        }
+                        low += 1;
+                        high -= 1;
+                    }
+                }
+                ControlFlow::Continue(())
+            }
+            4 => self.two_pairs(start, rest, positions, found),
+            _ => {
+                let Some(&largest) = self.values.last() else {
+                    return ControlFlow::Continue(());
+                };
+                if (largest as i128) * (k as i128) < rest {
+                    return ControlFlow::Continue(());
+                }
+                for position in start..self.values.len() {
+                    // the values only get bigger from here
+                    if value(position) * k as i128 > rest {
+                        break;
+                    }
+                    if self.unused(positions, position) == 0 {
+                        continue;
+                    }
+                    positions.push(position);
+                    let result =
+                        self.search(k - 1, position, rest - value(position), positions, found);
+                    positions.pop();
+                    result?;
+                }
+                ControlFlow::Continue(())
+            }
         }
     }
-    unreachable!();
-}
-#[aoc(day1, part2)]
-fn part2(input: &str) -> i32 {
-    let mut vec: Vec<i32> = input.split('\n').map(|s| s.parse().unwrap()).collect();
-    vec.sort_unstable();
-    for &a in vec.iter() {
-        for &b in vec.iter() {
-            if b >= a {
-                continue;
+
+    /// The last four positions of `search`, as a pair with a small sum and a pair with a
+    /// big one. The same four can be split into pairs in several ways, so they are only
+    /// passed to `found` the first time.
+    fn two_pairs<B>(
+        &self,
+        start: usize,
+        rest: i128,
+        positions: &mut Vec<usize>,
+        found: &mut impl FnMut(&[usize]) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        let mut low = Pairs::new(self, start, true);
+        let mut high = Pairs::new(self, start, false);
+        let mut seen = FxHashSet::default();
+        let mut small = low.next(i128::MIN);
+        let mut big = high.next(i128::MAX);
+        while let (Some((small_sum, small_pairs)), Some((big_sum, big_pairs))) = (&small, &big) {
+            let (small_sum, big_sum) = (*small_sum, *big_sum);
+            if small_sum > big_sum {
+                break;
+            }
+            if small_sum + big_sum < rest {
+                small = low.next(rest - big_sum);
+            } else if small_sum + big_sum > rest {
+                big = high.next(rest - small_sum);
+            } else {
+                for &(a, b) in small_pairs {
+                    for &(c, d) in big_pairs {
+                        let mut four = [a, b, c, d];
+                        four.sort_unstable();
+                        let available = four
+                            .chunk_by(|a, b| a == b)
+                            .all(|run| self.unused(positions, run[0]) >= run.len());
+                        if available && seen.insert(four) {
+                            self.found_with(positions, &four, found)?;
+                        }
+                    }
+                }
+                small = low.next(i128::MIN);
+                big = high.next(i128::MAX);
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Calls `found` with `positions` followed by `more`.
+    fn found_with<B>(
+        &self,
+        positions: &mut Vec<usize>,
+        more: &[usize],
+        found: &mut impl FnMut(&[usize]) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        let len = positions.len();
+        positions.extend(more);
+        let result = found(positions);
+        positions.truncate(len);
+        result
+    }
+
+    /// How many entries with the value at `position` are not in `positions` yet.
+    fn unused(&self, positions: &[usize], position: usize) -> usize {
+        let used = positions.iter().filter(|&&p| p == position).count();
+        self.indices[position].len().saturating_sub(used)
+    }
+
+    /// Calls `f` with every tuple of increasing indices of entries that have the values
+    /// at `positions`.
+    fn expand(&self, positions: &[usize], f: &mut impl FnMut(Vec<usize>)) {
+        let runs: Vec<&[usize]> = positions.chunk_by(|a, b| a == b).collect();
+        self.expand_runs(&runs, &mut vec![], f);
+    }
+
+    fn expand_runs(
+        &self,
+        runs: &[&[usize]],
+        tuple: &mut Vec<usize>,
+        f: &mut impl FnMut(Vec<usize>),
+    ) {
+        let Some((run, rest)) = runs.split_first() else {
+            let mut tuple = tuple.clone();
+            tuple.sort_unstable();
+            return f(tuple);
+        };
+        // choose run.len() of the entries with this value
+        let indices = &self.indices[run[0]];
+        let mut choice: Vec<usize> = (0..run.len()).collect();
+        loop {
+            let len = tuple.len();
+            tuple.extend(choice.iter().map(|&i| indices[i]));
+            self.expand_runs(rest, tuple, f);
+            tuple.truncate(len);
+            // the next choice in lexicographic order
+            let Some(i) = (0..choice.len())
+                .rev()
+                .find(|&i| choice[i] < indices.len() - choice.len() + i)
+            else {
+                return;
+            };
+            choice[i] += 1;
+            for j in i + 1..choice.len() {
+                choice[j] = choice[j - 1] + 1;
             }
-            let c = 2020 - a - b;
-            if c >= b {
-                continue;
+        }
+    }
+}
+
+/// The pairs of positions from `start` on that can be picked together, in the order of
+/// their sums. Every first position of a pair is in the heap once, with its next partner.
+struct Pairs<'a> {
+    distinct: &'a Distinct,
+    increasing: bool,
+    /// The sums are negated when they increase, because the heap pops the biggest.
+    heap: BinaryHeap<(i128, usize, usize)>,
+}
+
+impl<'a> Pairs<'a> {
+    fn new(distinct: &'a Distinct, start: usize, increasing: bool) -> Self {
+        let mut pairs = Pairs {
+            distinct,
+            increasing,
+            heap: BinaryHeap::new(),
+        };
+        let last = distinct.values.len().saturating_sub(1);
+        for first in start..distinct.values.len() {
+            pairs.push(first, Some(if increasing { first } else { last }));
+        }
+        pairs
+    }
+
+    fn sum(&self, first: usize, second: usize) -> i128 {
+        self.distinct.values[first] as i128 + self.distinct.values[second] as i128
+    }
+
+    /// Queues the pair of `first` and `second`, or the next one with `first` if a value
+    /// can't be picked twice.
+    fn push(&mut self, first: usize, second: Option<usize>) {
+        let Some(mut second) = second else {
+            return;
+        };
+        if second == first && self.distinct.indices[first].len() < 2 {
+            if !self.increasing {
+                return;
             }
-            if vec.binary_search(&c).is_ok() {
-                return a * b * c;
+            second += 1;
+        }
+        if first <= second && second < self.distinct.values.len() {
+            let sum = self.sum(first, second);
+            let key = if self.increasing { -sum } else { sum };
+            self.heap.push((key, first, second));
+        }
+    }
+
+    /// The next pairs that all have the same sum, with that sum. Pairs with sums before
+    /// `bound` are skipped.
+    fn next(&mut self, bound: i128) -> Option<(i128, Vec<(usize, usize)>)> {
+        let mut group: Option<(i128, Vec<(usize, usize)>)> = None;
+        while let Some(&(_, first, second)) = self.heap.peek() {
+            let sum = self.sum(first, second);
+            match &mut group {
+                Some((group_sum, _)) if *group_sum != sum => break,
+                Some((_, pairs)) => pairs.push((first, second)),
+                None if (sum < bound) == self.increasing && sum != bound => {
+                    // jump to the first partner that isn't before `bound`
+                    self.heap.pop();
+                    let values = &self.distinct.values;
+                    let value = values[first] as i128;
+                    let partner = if self.increasing {
+                        let partner = values.partition_point(|&v| value + (v as i128) < bound);
+                        Some(partner.max(first))
+                    } else {
+                        values
+                            .partition_point(|&v| value + (v as i128) <= bound)
+                            .checked_sub(1)
+                    };
+                    self.push(first, partner);
+                    continue;
+                }
+                None => group = Some((sum, vec![(first, second)])),
             }
+            self.heap.pop();
+            let partner = if self.increasing {
+                Some(second + 1)
+            } else {
+                second.checked_sub(1)
+            };
+            self.push(first, partner);
+        }
+        group
+    }
+}
+
+/// Both parts, reading the entries one at a time. Unlike `parse`, this only takes entries
+/// that aren't negative: then only entries up to 2020 can be part of a sum, so it just
+/// counts how often it has seen each of those, up to three times. The answers are the
+/// same as those of `part1` and `part2` for such inputs.
+pub fn stream(input: impl BufRead) -> Result<(i64, i64)> {
+    let mut lines = Lines::new(1, input);
+    let mut counts = [0u8; 2021];
+    while let Some(entry) = lines.parse(int::<i64>())? {
+        if entry < 0 {
+            return Err(lines.error("entries can't be negative"));
+        }
+        if let Some(count) = counts.get_mut(entry as usize) {
+            *count = (*count + 1).min(3);
         }
     }
-    unreachable!();
+    let entries = (0..=2020)
+        .flat_map(|entry| std::iter::repeat_n(entry, counts[entry as usize] as usize))
+        .collect();
+    let input = Input { entries };
+    Ok((part1(&input)?, part2(&input)?))
+}
+
+#[test]
+fn k_sums() {
+    let values = [1010, -5, 1010, 3, 1010, 2025];
+    assert_eq!(k_sum(&values, 2, 2020), [[0, 2], [0, 4], [1, 5], [2, 4]]);
+    assert_eq!(
+        k_sum(&values, 3, 2023),
+        [[0, 2, 3], [0, 3, 4], [1, 3, 5], [2, 3, 4]]
+    );
+    assert_eq!(k_sum(&values, 4, 4040).len(), 3);
+    assert!(k_sum(&values, 4, 4045).is_empty());
+    assert_eq!(k_sum(&values, 1, -5), [[1]]);
+    assert_eq!(first_k_sum(&values, 2, 2020), Some(vec![1, 5]));
+    assert_eq!(first_k_sum(&values, 0, 0), Some(vec![]));
+
+    // every way to pick, tried one by one
+    fn all_tuples(values: &[i64], k: usize, target: i64, from: usize) -> Vec<Vec<usize>> {
+        if k == 0 {
+            return if target == 0 { vec![vec![]] } else { vec![] };
+        }
+        (from..values.len())
+            .flat_map(|idx| {
+                all_tuples(values, k - 1, target - values[idx], idx + 1)
+                    .into_iter()
+                    .map(move |tuple| [vec![idx], tuple].concat())
+            })
+            .collect()
+    }
+    let values = [3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5, -2, 0, 7];
+    for k in 0..=6 {
+        for target in -4..=40 {
+            assert_eq!(k_sum(&values, k, target), all_tuples(&values, k, target, 0));
+        }
+    }
+
+    // distinct even numbers, so no four of them have an odd sum
+    let values: Vec<i64> = (0..100_000).map(|i| (i * 7919 % 100_000) * 2).collect();
+    assert_eq!(first_k_sum(&values, 4, 2001), None);
+    let tuple = first_k_sum(&values, 4, 4000).unwrap();
+    assert_eq!(tuple.iter().map(|&i| values[i]).sum::<i64>(), 4000);
+    let largest = 2 * (99_999 + 99_998 + 99_997 + 99_996);
+    let tuple = first_k_sum(&values, 4, largest).unwrap();
+    assert_eq!(tuple.iter().map(|&i| values[i]).sum::<i64>(), largest);
+    assert_eq!(k_sum(&values, 4, largest).len(), 1);
+    assert_eq!(first_k_sum(&values, 4, 200_001), None);
+    let tuple = first_k_sum(&values, 4, 200_000).unwrap();
+    assert_eq!(tuple.iter().map(|&i| values[i]).sum::<i64>(), 200_000);
+
+    // only one value isn't a multiple of 5, so no four of them have a sum of 10_002, but
+    // that can't be seen from the remainders
+    let mut values: Vec<i64> = (0..1000).map(|i| i * 5).collect();
+    values.push(1);
+    assert_eq!(first_k_sum(&values, 4, 10_002), None);
+    assert!(first_k_sum(&values, 4, 10_001).unwrap().contains(&1000));
+}
+
+#[test]
+fn streamed() {
+    let input = "1721\n979\n366\n299\n675\n1456\n5000\n1010\n";
+    let entries = parse(input).unwrap();
+    assert_eq!(
+        stream(input.as_bytes()),
+        Ok((part1(&entries).unwrap(), part2(&entries).unwrap()))
+    );
+    assert_eq!(
+        stream("1721\n-5\n299".as_bytes()).unwrap_err().to_string(),
+        "day 1, line 2, column 1: entries can't be negative"
+    );
+    assert!(part1(&parse("2025\n-5").unwrap()).is_ok());
 }
@@ -1,8 +1,30 @@
 use bitvec::prelude::BitVec;
 
-#[aoc(day10, part1)]
-fn part1(input: &str) -> u32 {
-    let jolts = collect_to_bitvec(input);
+use crate::{
+    error::{ParseError, Result, Source},
+    normalize,
+    parser::{int, lines},
+};
+
+pub struct Input {
+    /// The output joltage of every adapter.
+    pub adapters: Vec<usize>,
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day10))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(10, input);
+    let src = Source::new(10, input);
+    let adapters: Vec<usize> = src.run(lines(int()))?;
+    if adapters.is_empty() {
+        return Err(src.error_input("no adapters"));
+    }
+    Ok(Input { adapters })
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day10, part1))]
+pub fn part1(input: &Input) -> Result<u32> {
+    let jolts = collect_to_bitvec(&input.adapters);
     let mut one_counter = 0;
     let mut three_counter = 1;
     let mut prev = 0;
@@ -16,14 +38,19 @@ fn part1(input: &str) -> u32 {
         } else if diff == 3 {
             three_counter += 1;
         }
-        debug_assert!(diff <= 3, "{}, {}", jolt, diff);
+        if diff > 3 {
+            return Err(ParseError::input(
+                10,
+                format!("no adapter between {} and {} jolts", prev, jolt),
+            ));
+        }
         prev = jolt;
     }
-    one_counter * three_counter
+    Ok(one_counter * three_counter)
 }
-fn collect_to_bitvec(input: &str) -> BitVec {
+fn collect_to_bitvec(adapters: &[usize]) -> BitVec {
     let mut bv = BitVec::new();
-    for jolt in input.lines().map(|l| l.parse::<usize>().unwrap()) {
+    for &jolt in adapters {
         if bv.len() <= jolt {
             bv.resize(jolt + 1, false);
         }
@@ -32,9 +59,9 @@ fn collect_to_bitvec(input: &str) -> BitVec {
     bv
 }
 
-#[aoc(day10, part2)]
-fn part2(input: &str) -> u64 {
-    let mut jolts = collect_to_bitvec(input);
+#[cfg_attr(feature = "aoc-runner", aoc(day10, part2))]
+pub fn part2(input: &Input) -> Result<u64> {
+    let mut jolts = collect_to_bitvec(&input.adapters);
     jolts.set(0, true);
     let mut possibilities = [0; 4];
     possibilities[1] = 1;
@@ -52,7 +79,7 @@ fn part2(input: &str) -> u64 {
             }
         }
     }
-    possibilities[0]
+    Ok(possibilities[0])
 }
 
 #[test]
@@ -68,7 +95,7 @@ fn e1() {
 6
 12
 4";
-    assert_eq!(part1(input), 7 * 5);
+    assert_eq!(part1(&parse(input).unwrap()), Ok(7 * 5));
 }
 #[test]
 fn e2() {
@@ -83,7 +110,7 @@ fn e2() {
 6
 12
 4";
-    assert_eq!(part2(input), 8);
+    assert_eq!(part2(&parse(input).unwrap()), Ok(8));
 }
 #[test]
 fn e3() {
@@ -118,5 +145,5 @@ fn e3() {
 34
 10
 3";
-    assert_eq!(part2(input), 19208);
+    assert_eq!(part2(&parse(input).unwrap()), Ok(19208));
 }
@@ -1,197 +1,241 @@
-use std::{
-    cmp::{max, min},
-    fmt::Display,
+use std::{mem, ops::ControlFlow};
+
+use crate::{
+    checkpoint::{Checkpoints, Decoder, Encoder, State},
+    error::{Result, Source},
+    grid::{Cell, Grid, DIRECTIONS8},
+    normalize,
 };
 
-#[aoc(day11, part1)]
-fn part1(input: &str) -> usize {
-    let grid = Grid::new(input);
-    occupied_after_all_moves(grid)
+/// The seat layout of the waiting area.
+pub type Input = Grid<Position>;
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day11))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(11, input);
+    Grid::parse(&Source::new(11, input))
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day11, part1))]
+pub fn part1(grid: &Input) -> Result<usize> {
+    occupied_after_all_moves(Seating::new(grid), 1, None)
 }
-#[aoc(day11, part2)]
-fn part2(input: &str) -> usize {
-    let grid = Grid::new(input);
-    occupied_after_all_moves2(grid)
+#[cfg_attr(feature = "aoc-runner", aoc(day11, part2))]
+pub fn part2(grid: &Input) -> Result<usize> {
+    occupied_after_all_moves(Seating::new(grid), 2, None)
 }
 
-fn occupied_after_all_moves(mut grid: Grid) -> usize {
-    let mut other_grid = grid.clone();
-    loop {
-        if tick(&grid, &mut other_grid) {
-            std::mem::swap(&mut grid, &mut other_grid);
-        } else {
-            return grid
-                .grid
-                .iter()
-                .filter(|e| matches!(e, Position::Occupied))
-                .count();
-        }
-    }
+/// Solves `part`, continuing from the seat layout in the checkpoint if there is one.
+pub fn resume(grid: &Input, part: u8, checkpoints: &mut Checkpoints) -> Result<usize> {
+    let seating = checkpoints.load()?.unwrap_or_else(|| Seating::new(grid));
+    let answer = occupied_after_all_moves(seating, part, Some(checkpoints))?;
+    checkpoints.finish()?;
+    Ok(answer)
 }
 
-fn occupied_after_all_moves2(mut grid: Grid) -> usize {
-    loop {
-        if let Some(new_grid) = tick2(&grid) {
-            grid = new_grid;
-        } else {
-            return grid
-                .grid
-                .iter()
-                .filter(|e| matches!(e, Position::Occupied))
-                .count();
+/// Every seat layout from `grid` on until nobody moves anymore, with the rules of
+/// `part` 1 or 2.
+pub fn generations(grid: &Input, part: u8) -> Vec<Input> {
+    let mut generations = vec![];
+    let _ = settle(&mut Seating::new(grid), part, |seating| {
+        generations.push(seating.grid.clone());
+        ControlFlow::<()>::Continue(())
+    });
+    generations
+}
+
+/// The seats that still change in generation `generations` with the rules of `part`
+/// 1 or 2. Empty if nobody moves anymore by then.
+pub(crate) fn unsettled(grid: &Input, part: u8, generations: usize) -> Vec<(usize, usize)> {
+    if generations == 0 {
+        return vec![];
+    }
+    // the layout before the last generation
+    let mut before = None;
+    let changes = settle(&mut Seating::new(grid), part, |seating| {
+        if seating.generation + 1 < generations {
+            return ControlFlow::Continue(());
         }
+        let Some(before) = &before else {
+            before = Some(seating.grid.clone());
+            return ControlFlow::Continue(());
+        };
+        let grid = &seating.grid;
+        ControlFlow::Break(
+            grid.positions()
+                .filter(|&pos| grid[pos] != before[pos])
+                .collect(),
+        )
+    });
+    match changes {
+        ControlFlow::Break(changes) => changes,
+        ControlFlow::Continue(()) => vec![],
     }
 }
 
-#[derive(Clone)]
-struct Grid {
-    grid: Vec<Position>,
-    width: usize,
-    height: usize,
+/// The seat layout after some generations.
+pub(crate) struct Seating {
+    grid: Input,
+    generation: usize,
 }
 
-impl Display for Grid {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (idx, p) in self.grid.iter().enumerate() {
-            if idx % self.width == 0 {
-                writeln!(f)?;
-            }
-            let c = match p {
-                Position::Seat => 'L',
-                Position::Occupied => '#',
-                Position::Floor => '.',
-            };
-            write!(f, "{}", c)?;
+impl Seating {
+    fn new(grid: &Input) -> Self {
+        Self {
+            grid: grid.clone(),
+            generation: 0,
         }
-        Ok(())
     }
 }
 
-impl Grid {
-    fn new(input: &str) -> Self {
-        let mut grid = Vec::new();
-        for char in input.chars() {
-            match char {
-                'L' => grid.push(Position::Seat),
-                '#' => grid.push(Position::Occupied),
-                '.' => grid.push(Position::Floor),
-                _ => {}
-            }
-        }
-        let width = input.chars().position(|c| c == '\n').unwrap();
-        let height = input.lines().count();
-        assert_eq!(width * height, grid.len());
-        Self {
-            grid,
-            width,
-            height,
-        }
+impl State for Seating {
+    fn encode(&self, out: &mut Encoder) {
+        out.usize(self.generation);
+        out.grid(&self.grid);
     }
-    fn occupied(&self, pos: (usize, usize)) -> u8 {
-        let mut occupied = 0;
-        for x in max(pos.0, 1) - 1..=min(pos.0, self.width - 2) + 1 {
-            for y in max(pos.1, 1) - 1..=min(pos.1, self.height - 2) + 1 {
-                if (x, y) == pos {
-                    continue;
-                }
-                if let Position::Occupied = self.grid[y * self.width + x] {
-                    occupied += 1;
-                }
-            }
+
+    fn decode(input: &mut Decoder) -> Result<Self> {
+        Ok(Self {
+            generation: input.usize()?,
+            grid: input.grid()?,
+        })
+    }
+}
+
+fn occupied_after_all_moves(
+    mut seating: Seating,
+    part: u8,
+    mut checkpoints: Option<&mut Checkpoints>,
+) -> Result<usize> {
+    let saved = settle(&mut seating, part, |seating| {
+        match checkpoints.as_deref_mut().map(|c| c.save_if_due(seating)) {
+            Some(Err(error)) => ControlFlow::Break(error),
+            _ => ControlFlow::Continue(()),
         }
-        occupied
+    });
+    if let ControlFlow::Break(error) = saved {
+        return Err(error);
     }
-    fn get(&self, pos: (usize, usize)) -> Option<&Position> {
-        if (0..self.width).contains(&pos.0) && (0..self.height).contains(&pos.1) {
-            Some(&self.grid[pos.1 * self.width + pos.0])
+    Ok(seating
+        .grid
+        .cells()
+        .filter(|e| matches!(e, Position::Occupied))
+        .count())
+}
+
+/// Lets everybody move with the rules of `part` 1 or 2 until nobody moves anymore.
+/// `each` sees every seat layout on the way, including the first and the last, and can
+/// stop early.
+fn settle<B>(
+    seating: &mut Seating,
+    part: u8,
+    mut each: impl FnMut(&Seating) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    let mut other_grid = seating.grid.clone();
+    loop {
+        each(seating)?;
+        let changed = if part == 1 {
+            tick(&seating.grid, &mut other_grid)
+        } else if let Some(new_grid) = tick2(&seating.grid) {
+            other_grid = new_grid;
+            true
         } else {
-            None
+            false
+        };
+        if !changed {
+            return ControlFlow::Continue(());
         }
+        mem::swap(&mut seating.grid, &mut other_grid);
+        seating.generation += 1;
     }
-    fn occupied2(&self, pos: (usize, usize)) -> u8 {
-        let mut occupied = 0;
-        let directions = [
-            (1, 1),
-            (1, 0),
-            (0, 1),
-            (-1, -1),
-            (-1, 0),
-            (-1, 1),
-            (0, -1),
-            (1, -1),
-        ];
-        'directions: for direction in &directions {
-            let mut pos = pos;
-            loop {
-                pos.0 = (pos.0 as i32 + direction.0) as usize;
-                pos.1 = (pos.1 as i32 + direction.1) as usize;
-                match self.get(pos) {
-                    Some(Position::Floor) => {}
-                    Some(Position::Occupied) => {
-                        occupied += 1;
-                        continue 'directions;
-                    }
-                    None | Some(Position::Seat) => {
-                        continue 'directions;
-                    }
-                }
-            }
+}
+
+fn occupied(grid: &Input, pos: (usize, usize)) -> u8 {
+    grid.neighbors8(pos.0, pos.1)
+        .filter(|&neighbor| matches!(grid[neighbor], Position::Occupied))
+        .count() as u8
+}
+
+fn occupied2(grid: &Input, pos: (usize, usize)) -> u8 {
+    let mut occupied = 0;
+    for &direction in &DIRECTIONS8 {
+        // the first seat in that direction, if any
+        let seat = grid
+            .ray(pos.0, pos.1, direction)
+            .find(|p| !matches!(p, Position::Floor));
+        if let Some(Position::Occupied) = seat {
+            occupied += 1;
         }
-        occupied
     }
+    occupied
 }
 
-#[derive(Clone)]
-enum Position {
+#[derive(Clone, PartialEq, Eq)]
+pub enum Position {
     Seat,
     Occupied,
     Floor,
 }
 
-fn tick(grid: &Grid, target: &mut Grid) -> bool {
+impl Cell for Position {
+    fn from_char(char: char) -> Option<Self> {
+        match char {
+            'L' => Some(Position::Seat),
+            '#' => Some(Position::Occupied),
+            '.' => Some(Position::Floor),
+            _ => None,
+        }
+    }
+
+    fn to_char(&self) -> char {
+        match self {
+            Position::Seat => 'L',
+            Position::Occupied => '#',
+            Position::Floor => '.',
+        }
+    }
+}
+
+fn tick(grid: &Input, target: &mut Input) -> bool {
     let mut changed = false;
-    for x in 0..grid.width {
-        for y in 0..grid.height {
-            let occupied = grid.occupied((x, y));
-            match &grid.grid[y * grid.width + x] {
-                Position::Seat if occupied == 0 => {
-                    target.grid[y * grid.width + x] = Position::Occupied;
-                    changed = true;
-                }
-                Position::Occupied if occupied >= 4 => {
-                    target.grid[y * grid.width + x] = Position::Seat;
-                    changed = true;
-                }
-                p => {
-                    target.grid[y * grid.width + x] = p.clone();
-                }
+    for pos in grid.positions() {
+        let occupied = occupied(grid, pos);
+        match &grid[pos] {
+            Position::Seat if occupied == 0 => {
+                target[pos] = Position::Occupied;
+                changed = true;
+            }
+            Position::Occupied if occupied >= 4 => {
+                target[pos] = Position::Seat;
+                changed = true;
+            }
+            p => {
+                target[pos] = p.clone();
             }
         }
     }
     changed
 }
 
-fn tick2(grid: &Grid) -> Option<Grid> {
+fn tick2(grid: &Input) -> Option<Input> {
     let mut new_grid = grid.clone();
     let mut changed = false;
-    for x in 0..grid.width {
-        for y in 0..grid.height {
-            let occupied = grid.occupied2((x, y));
-            match grid.grid[y * grid.width + x] {
-                Position::Seat => {
-                    if occupied == 0 {
-                        new_grid.grid[y * grid.width + x] = Position::Occupied;
-                        changed = true;
-                    }
+    for pos in grid.positions() {
+        let occupied = occupied2(grid, pos);
+        match grid[pos] {
+            Position::Seat => {
+                if occupied == 0 {
+                    new_grid[pos] = Position::Occupied;
+                    changed = true;
                 }
-                Position::Occupied => {
-                    if occupied >= 5 {
-                        new_grid.grid[y * grid.width + x] = Position::Seat;
-                        changed = true;
-                    }
+            }
+            Position::Occupied => {
+                if occupied >= 5 {
+                    new_grid[pos] = Position::Seat;
+                    changed = true;
                 }
-                Position::Floor => {}
             }
+            Position::Floor => {}
         }
     }
     if changed {
@@ -213,8 +257,7 @@ L.LLLLL.LL
 LLLLLLLLLL
 L.LLLLLL.L
 L.LLLLL.LL";
-    let grid = Grid::new(input);
-    assert_eq!(occupied_after_all_moves(grid), 37);
+    assert_eq!(part1(&parse(input).unwrap()), Ok(37));
 }
 #[test]
 fn e2() {
@@ -228,6 +271,5 @@ L.LLLLL.LL
 LLLLLLLLLL
 L.LLLLLL.L
 L.LLLLL.LL";
-    let grid = Grid::new(input);
-    assert_eq!(occupied_after_all_moves2(grid), 26);
+    assert_eq!(part2(&parse(input).unwrap()), Ok(26));
 }
@@ -1,44 +1,81 @@
-use core::panic;
-
 use euclid::default::Vector2D;
 
-#[aoc(day12, part1)]
-fn part1(input: &str) -> i32 {
+use crate::{
+    error::{ParseError, Result, Source},
+    normalize,
+    parser::{int, lines, one_of},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    North,
+    South,
+    East,
+    West,
+    Left,
+    Right,
+    Forward,
+}
+
+pub struct Input {
+    pub instructions: Vec<(Action, i32)>,
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day12))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(12, input);
+    static ACTIONS: &[(&str, Action)] = &[
+        ("N", Action::North),
+        ("S", Action::South),
+        ("E", Action::East),
+        ("W", Action::West),
+        ("L", Action::Left),
+        ("R", Action::Right),
+        ("F", Action::Forward),
+    ];
+    Ok(Input {
+        instructions: Source::new(12, input).run(lines((one_of(ACTIONS), int())))?,
+    })
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day12, part1))]
+pub fn part1(input: &Input) -> Result<i32> {
     let mut ship = Ship::new();
-    for line in input.lines() {
-        let (op, payload) = line.split_at(1);
-        let payload: i32 = payload.parse().unwrap();
-        match op {
-            "N" => ship.move_in_dir(NORTH, payload),
-            "S" => ship.move_in_dir(SOUTH, payload),
-            "W" => ship.move_in_dir(WEST, payload),
-            "E" => ship.move_in_dir(EAST, payload),
-            "L" => ship.direction.rotate_left(payload),
-            "R" => ship.direction.rotate_right(payload),
-            "F" => ship.move_in_dir(ship.direction, payload),
-            _ => panic!(),
+    for (idx, &(action, payload)) in input.instructions.iter().enumerate() {
+        match action {
+            Action::North => ship.move_in_dir(NORTH, payload),
+            Action::South => ship.move_in_dir(SOUTH, payload),
+            Action::West => ship.move_in_dir(WEST, payload),
+            Action::East => ship.move_in_dir(EAST, payload),
+            Action::Left => ship.direction.rotate_left(payload),
+            Action::Right => ship.direction.rotate_right(payload),
+            Action::Forward => ship.move_in_dir(ship.direction, payload),
         }
+        .ok_or_else(|| too_far(idx))?;
     }
-    ship.position.x.abs() + ship.position.y.abs()
+    ship.distance()
 }
-#[aoc(day12, part2)]
-fn part2(input: &str) -> i32 {
+#[cfg_attr(feature = "aoc-runner", aoc(day12, part2))]
+pub fn part2(input: &Input) -> Result<i32> {
     let mut ship = Ship::new();
-    for line in input.lines() {
-        let (op, payload) = line.split_at(1);
-        let payload: i32 = payload.parse().unwrap();
-        match op {
-            "N" => ship.move_waypoint(NORTH, payload),
-            "S" => ship.move_waypoint(SOUTH, payload),
-            "W" => ship.move_waypoint(WEST, payload),
-            "E" => ship.move_waypoint(EAST, payload),
-            "L" => ship.waypoint.rotate_left(payload),
-            "R" => ship.waypoint.rotate_right(payload),
-            "F" => ship.move_to_waypoint(payload),
-            _ => panic!(),
+    for (idx, &(action, payload)) in input.instructions.iter().enumerate() {
+        match action {
+            Action::North => ship.move_waypoint(NORTH, payload),
+            Action::South => ship.move_waypoint(SOUTH, payload),
+            Action::West => ship.move_waypoint(WEST, payload),
+            Action::East => ship.move_waypoint(EAST, payload),
+            Action::Left => ship.waypoint.rotate_left(payload),
+            Action::Right => ship.waypoint.rotate_right(payload),
+            Action::Forward => ship.move_to_waypoint(payload),
         }
+        .ok_or_else(|| too_far(idx))?;
     }
-    ship.position.x.abs() + ship.position.y.abs()
+    ship.distance()
+}
+
+/// The ship or the waypoint leaves the range of `i32` with the instruction at `idx`.
+fn too_far(idx: usize) -> ParseError {
+    ParseError::new(12, idx + 1, 1, "the ship gets too far away")
 }
 
 const EAST: Vector2D<i32> = Vector2D::new(1, 0);
@@ -61,33 +98,67 @@ impl Ship {
         }
     }
 
-    fn move_in_dir(&mut self, dir: Vector2D<i32>, n: i32) {
-        self.position += dir * n;
+    fn move_in_dir(&mut self, dir: Vector2D<i32>, n: i32) -> Option<()> {
+        self.position = add(self.position, scale(dir, n)?)?;
+        Some(())
     }
-    fn move_waypoint(&mut self, dir: Vector2D<i32>, n: i32) {
-        self.waypoint += dir * n;
+    fn move_waypoint(&mut self, dir: Vector2D<i32>, n: i32) -> Option<()> {
+        self.waypoint = add(self.waypoint, scale(dir, n)?)?;
+        Some(())
     }
-    fn move_to_waypoint(&mut self, n: i32) {
-        self.position += self.waypoint * n;
+    fn move_to_waypoint(&mut self, n: i32) -> Option<()> {
+        self.position = add(self.position, scale(self.waypoint, n)?)?;
+        Some(())
+    }
+
+    /// The Manhattan distance from the start.
+    fn distance(&self) -> Result<i32> {
+        self.position
+            .x
+            .checked_abs()
+            .zip(self.position.y.checked_abs())
+            .and_then(|(x, y)| x.checked_add(y))
+            .ok_or_else(|| ParseError::input(12, "the ship gets too far away"))
     }
 }
 
+fn add(a: Vector2D<i32>, b: Vector2D<i32>) -> Option<Vector2D<i32>> {
+    Some(Vector2D::new(a.x.checked_add(b.x)?, a.y.checked_add(b.y)?))
+}
+
+fn scale(v: Vector2D<i32>, n: i32) -> Option<Vector2D<i32>> {
+    Some(Vector2D::new(v.x.checked_mul(n)?, v.y.checked_mul(n)?))
+}
+
 trait Rotate {
-    fn rotate_right(&mut self, degrees: i32);
-    fn rotate_left(&mut self, degrees: i32) {
-        self.rotate_right(-degrees)
+    fn rotate_right(&mut self, degrees: i32) -> Option<()>;
+    fn rotate_left(&mut self, degrees: i32) -> Option<()> {
+        self.rotate_right(degrees.checked_neg()?)
     }
 }
 
 impl Rotate for Vector2D<i32> {
-    fn rotate_right(&mut self, degrees: i32) {
+    fn rotate_right(&mut self, degrees: i32) -> Option<()> {
         let d = (degrees / 90).rem_euclid(4);
         (self.x, self.y) = match d {
             0 => (self.x, self.y),
-            1 => (-self.y, self.x),
-            2 => (-self.x, -self.y),
-            3 => (self.y, -self.x),
+            1 => (self.y.checked_neg()?, self.x),
+            2 => (self.x.checked_neg()?, self.y.checked_neg()?),
+            3 => (self.y, self.x.checked_neg()?),
             _ => unreachable!(),
-        }
+        };
+        Some(())
     }
 }
+
+#[test]
+fn overflow() {
+    let input = parse("F2147483647\nF2147483647").unwrap();
+    assert_eq!(
+        part1(&input).unwrap_err().to_string(),
+        "day 12, line 2, column 1: the ship gets too far away"
+    );
+    assert!(part2(&parse("F2147483647").unwrap()).is_err());
+    assert_eq!(part1(&parse("F10\nN3\nF7\nR90\nF11").unwrap()), Ok(25));
+    assert_eq!(part2(&parse("F10\nN3\nF7\nR90\nF11").unwrap()), Ok(286));
+}
@@ -1,61 +1,96 @@
-use ring_algorithm::chinese_remainder_theorem;
+use crate::{
+    error::{ParseError, Result, Source},
+    normalize,
+    number_theory::{chinese_remainder, CrtError},
+    parser::{alt, int, line, separated, tag, Parser},
+};
 
-#[aoc(day13, part1)]
-fn part1(input: &str) -> u64 {
-    let mut lines = input.lines();
-    let start: u64 = lines.next().unwrap().parse().unwrap();
-    let id = lines
-        .next()
-        .unwrap()
-        .split(',')
-        .filter_map(|str| match str {
-            "x" => None,
-            id => Some(id.parse::<u64>().unwrap()),
-        })
-        .min_by_key(|id| {
+pub struct Input {
+    pub earliest_departure: u64,
+    /// The bus ids in the order of the schedule, `None` for an `x`.
+    pub buses: Vec<Option<u64>>,
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day13))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(13, input);
+    let bus = alt(
+        tag("x").map(|_| None),
+        int().try_map(|id| match id {
+            0 => Err("bus ids have to be positive"),
+            id => Ok(Some(id)),
+        }),
+    );
+    let (earliest_departure, buses) =
+        Source::new(13, input).run((line(int()), line(separated(bus, tag(",")))))?;
+    Ok(Input {
+        earliest_departure,
+        buses,
+    })
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day13, part1))]
+pub fn part1(input: &Input) -> Result<u64> {
+    let start = input.earliest_departure;
+    let id = input
+        .buses
+        .iter()
+        .flatten()
+        .min_by_key(|&id| {
             let before = start % id;
             id - before
         })
-        .unwrap();
+        .ok_or_else(|| ParseError::input(13, "no bus is in service"))?;
     let before = start % id;
     let delay = id - before;
-    id * delay
+    Ok(id * delay)
 }
-#[aoc(day13, part2)]
-fn part2(input: &str) -> i64 {
-    let mut lines = input.lines();
-    let ids: Vec<(i64, i64)> = lines
-        .nth(1)
-        .unwrap()
-        .split(',')
+#[cfg_attr(feature = "aoc-runner", aoc(day13, part2))]
+pub fn part2(input: &Input) -> Result<i64> {
+    let ids: Vec<(i64, i64)> = input
+        .buses
+        .iter()
         .enumerate()
-        .filter_map(|(offset, str)| match str {
-            "x" => None,
-            id => {
-                let id = id.parse::<i64>().unwrap();
-                Some((id, -(offset as i64)))
-            }
-        })
+        .filter_map(|(offset, id)| id.map(|id| (id as i64, -(offset as i64))))
         .collect();
-    let a = chinese_remainder_theorem(
+    let a = chinese_remainder(
         &ids.iter()
             .map(|&(_id, remainder)| remainder)
             .collect::<Vec<i64>>(),
         &ids.iter().map(|&(id, _remainder)| id).collect::<Vec<i64>>(),
     );
-    a.unwrap()
+    a.map_err(|error| {
+        let message = match error {
+            CrtError::NotCoprime => "the bus ids are not pairwise coprime",
+            CrtError::Overflow => "the earliest timestamp is too large",
+        };
+        ParseError::input(13, message)
+    })
 }
 
 #[test]
 fn e1() {
     let input = "939
 7,13,x,x,59,x,31,19";
-    assert_eq!(part1(input), 295);
+    assert_eq!(part1(&parse(input).unwrap()), Ok(295));
 }
 
 #[test]
 fn e2() {
     let input = "939
 7,13,x,x,59,x,31,19";
-    assert_eq!(part2(input), 1068781);
+    assert_eq!(part2(&parse(input).unwrap()), Ok(1068781));
+}
+
+#[test]
+fn no_timestamp() {
+    let error = |buses| part2(&parse(buses).unwrap()).unwrap_err().to_string();
+    assert_eq!(
+        error("0\n4,x,6"),
+        "day 13: the bus ids are not pairwise coprime"
+    );
+    assert_eq!(
+        error("0\n1000000007,998244353,1000000009,999999937,999999929"),
+        "day 13: the earliest timestamp is too large"
+    );
 }
@@ -1,68 +1,144 @@
+use std::io::BufRead;
+
 use fxhash::FxHashMap;
 
-#[aoc(day14, part1)]
-fn part1(input: &str) -> u64 {
-    let mut memory: FxHashMap<u64, u64> = FxHashMap::default();
-    let (mut ones_mask, mut zeros_mask) = (0u64, u64::MAX);
-    for line in input.lines() {
-        match &line[..2] {
-            "me" => {
-                let addr: u64 = line["mem[".len()..]
-                    .split_once(']')
-                    .unwrap()
-                    .0
-                    .parse()
-                    .unwrap();
-                let mut payload: u64 = line.split_once("= ").unwrap().1.parse().unwrap();
-                payload |= ones_mask;
-                payload &= zeros_mask;
-
-                memory.insert(addr, payload);
+use crate::{
+    error::{ParseError, Result, Source},
+    normalize,
+    parser::{alt, int, lines, preceded, tag, take_while1, Parser},
+    stream::Lines,
+};
+
+pub struct Input {
+    pub program: Vec<Instruction>,
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day14))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(14, input);
+    Ok(Input {
+        program: Source::new(14, input).run(lines(instruction()))?,
+    })
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day14, part1))]
+pub fn part1(input: &Input) -> Result<u64> {
+    let mut chip = Chip::default();
+    for instruction in &input.program {
+        chip.execute_v1(instruction);
+    }
+    Ok(chip.sum())
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day14, part2))]
+pub fn part2(input: &Input) -> Result<u64> {
+    let mut chip = Chip::default();
+    for instruction in &input.program {
+        chip.execute_v2(instruction)?;
+    }
+    Ok(chip.sum())
+}
+
+/// Both parts, running the program one instruction at a time. The memory of the
+/// program still has to be kept.
+pub fn stream(input: impl BufRead) -> Result<(u64, u64)> {
+    let mut lines = Lines::new(14, input);
+    let (mut v1, mut v2) = (Chip::default(), Chip::default());
+    while let Some(instruction) = lines.parse(instruction())? {
+        v1.execute_v1(&instruction);
+        v2.execute_v2(&instruction)?;
+    }
+    Ok((v1.sum(), v2.sum()))
+}
+
+/// The memory of the docking program and the decoder chip's current mask.
+struct Chip {
+    memory: FxHashMap<u64, u64>,
+    mask: Option<String>,
+    ones_mask: u64,
+    zeros_mask: u64,
+}
+
+impl Default for Chip {
+    fn default() -> Self {
+        Self {
+            memory: FxHashMap::default(),
+            mask: None,
+            ones_mask: 0,
+            zeros_mask: u64::MAX,
+        }
+    }
+}
+
+impl Chip {
+    /// Version 1 of the decoder chip masks the values that are written.
+    fn execute_v1(&mut self, instruction: &Instruction) {
+        match instruction {
+            &Instruction::Mem { addr, mut payload } => {
+                payload |= self.ones_mask;
+                payload &= self.zeros_mask;
+
+                self.memory.insert(addr, payload);
             }
-            "ma" => {
-                let mask = line.split_once("= ").unwrap().1;
-                (ones_mask, zeros_mask) = (0, u64::MAX);
+            Instruction::Mask(mask) => {
+                (self.ones_mask, self.zeros_mask) = (0, u64::MAX);
                 for (idx, char) in mask.chars().rev().enumerate() {
                     match char {
-                        '1' => ones_mask |= 1 << idx,
-                        '0' => zeros_mask ^= 1 << idx,
-                        c => assert_eq!(c, 'X'),
+                        '1' => self.ones_mask |= 1 << idx,
+                        '0' => self.zeros_mask ^= 1 << idx,
+                        c => debug_assert_eq!(c, 'X'),
                     }
                 }
             }
-            _ => panic!("unknown instruction"),
         }
     }
-    memory.values().sum()
-}
 
-#[aoc(day14, part2)]
-fn part2(input: &str) -> u64 {
-    let mut memory: FxHashMap<u64, u64> = FxHashMap::default();
-    let mut mask = "";
-    for line in input.lines() {
-        match &line[..2] {
-            "me" => {
-                let addr: u64 = line["mem[".len()..]
-                    .split_once(']')
-                    .unwrap()
-                    .0
-                    .parse()
-                    .unwrap();
-                let payload: u64 = line.split_once("= ").unwrap().1.parse().unwrap();
-                write_to_masked_address(&mut memory, addr, mask, payload);
+    /// Version 2 masks the addresses instead.
+    fn execute_v2(&mut self, instruction: &Instruction) -> Result<()> {
+        match instruction {
+            &Instruction::Mem { addr, payload } => {
+                let mask = self
+                    .mask
+                    .as_deref()
+                    .ok_or_else(|| ParseError::input(14, "memory write before the first mask"))?;
+                write_to_masked_address(&mut self.memory, addr, mask, payload);
             }
-            "ma" => {
-                mask = line.split_once("= ").unwrap().1;
+            Instruction::Mask(mask) => {
+                self.mask = Some(mask.clone());
             }
-            _ => panic!("unknown instruction"),
         }
+        Ok(())
     }
-    memory.values().sum()
+
+    fn sum(&self) -> u64 {
+        self.memory.values().sum()
+    }
+}
+
+pub enum Instruction {
+    /// 36 bits, most significant first, each `0`, `1` or `X`.
+    Mask(String),
+    Mem {
+        addr: u64,
+        payload: u64,
+    },
+}
+
+fn instruction<'a>() -> impl Parser<'a, Instruction> {
+    let bits = take_while1("a mask", |c| matches!(c, '0' | '1' | 'X'));
+    let mask = preceded(tag("mask = "), bits).try_map(|mask: &str| {
+        if mask.len() == 36 {
+            Ok(Instruction::Mask(mask.to_string()))
+        } else {
+            Err("masks have to be 36 bits long")
+        }
+    });
+    let mem = (tag("mem["), int(), tag("] = "), int())
+        .map(|(_, addr, _, payload)| Instruction::Mem { addr, payload });
+    alt(mask, mem)
 }
 
 fn write_to_masked_address(memory: &mut FxHashMap<u64, u64>, addr: u64, mask: &str, payload: u64) {
-    assert!(!mask.is_empty());
     // Override with 1s on mask 1s and with 0s on mask X
     let mut first_pass_addr = addr;
     let mut x_count = 0;
@@ -99,7 +175,7 @@ fn e1() {
 mem[8] = 11
 mem[7] = 101
 mem[8] = 0";
-    assert_eq!(part1(input), 165);
+    assert_eq!(part1(&parse(input).unwrap()), Ok(165));
 }
 #[test]
 fn e2() {
@@ -107,7 +183,7 @@ fn e2() {
 mem[42] = 100
 mask = 00000000000000000000000000000000X0XX
 mem[26] = 1";
-    assert_eq!(part2(input), 208);
+    assert_eq!(part2(&parse(input).unwrap()), Ok(208));
 }
 #[test]
 fn e3() {
@@ -115,12 +191,12 @@ fn e3() {
 mem[8] = 4
 mask = XX0000000000000000000000000000000000
 mem[0] = 5";
-    assert_eq!(part2(input), 52);
+    assert_eq!(part2(&parse(input).unwrap()), Ok(52));
 }
 #[test]
 fn e4() {
     let input = "mask = 00000000000000000000000000000000000X
 mem[1] = 7
 mem[0] = 3";
-    assert_eq!(part2(input), 6);
+    assert_eq!(part2(&parse(input).unwrap()), Ok(6));
 }
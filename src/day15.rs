@@ -1,47 +1,144 @@
 use fxhash::FxHashMap;
 
-#[aoc(day15, part1)]
-fn part1(input: &str) -> usize {
-    after_steps(input, 2020)
-}
-#[aoc(day15, part2)]
-fn part2(input: &str) -> usize {
-    after_steps(input, 30000000)
-}
-
-fn after_steps(input: &str, steps: usize) -> usize {
-    let mut spoken_numbers: FxHashMap<usize, usize> = FxHashMap::default();
-    let mut previous = 0;
-    for (idx, num) in input.split(',').enumerate() {
-        let num = num.parse().unwrap();
-        if idx != 0 {
-            spoken_numbers.insert(previous, idx);
+use crate::{
+    checkpoint::{Checkpoints, Decoder, Encoder, State},
+    error::{Result, Source},
+    normalize,
+    parser::{int, line, separated, tag},
+};
+
+pub struct Input {
+    pub starting_numbers: Vec<usize>,
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day15))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(15, input);
+    Ok(Input {
+        starting_numbers: Source::new(15, input).run(line(separated(int(), tag(","))))?,
+    })
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day15, part1))]
+pub fn part1(input: &Input) -> Result<usize> {
+    Game::new(&input.starting_numbers).play(steps(1), None)
+}
+#[cfg_attr(feature = "aoc-runner", aoc(day15, part2))]
+pub fn part2(input: &Input) -> Result<usize> {
+    Game::new(&input.starting_numbers).play(steps(2), None)
+}
+
+/// Solves `part`, continuing the game in the checkpoint if there is one.
+pub fn resume(input: &Input, part: u8, checkpoints: &mut Checkpoints) -> Result<usize> {
+    let game = checkpoints.load()?;
+    let game = game.unwrap_or_else(|| Game::new(&input.starting_numbers));
+    let answer = game.play(steps(part), Some(checkpoints))?;
+    checkpoints.finish()?;
+    Ok(answer)
+}
+
+fn steps(part: u8) -> usize {
+    if part == 1 {
+        2020
+    } else {
+        30000000
+    }
+}
+
+/// The memory game after some turns.
+pub(crate) struct Game {
+    /// The turn each number was last spoken in, except for the previous number.
+    spoken_numbers: FxHashMap<usize, usize>,
+    /// The number of the next turn.
+    turn: usize,
+    previous: usize,
+}
+
+impl Game {
+    pub(crate) fn new(starting_numbers: &[usize]) -> Self {
+        let mut spoken_numbers: FxHashMap<usize, usize> = FxHashMap::default();
+        let mut previous = 0;
+        for (idx, &num) in starting_numbers.iter().enumerate() {
+            if idx != 0 {
+                spoken_numbers.insert(previous, idx);
+            }
+            previous = num;
+        }
+        Self {
+            turn: spoken_numbers.len() + 1,
+            spoken_numbers,
+            previous,
         }
-        previous = num;
     }
-    for idx in (spoken_numbers.len() + 1)..steps {
-        let say = if let Some(&prev_idx) = spoken_numbers.get(&previous) {
-            idx - prev_idx
-        } else {
-            0
-        };
-        spoken_numbers.insert(previous, idx);
-        previous = say;
+
+    /// The number spoken in turn `steps`.
+    pub(crate) fn play(
+        mut self,
+        steps: usize,
+        mut checkpoints: Option<&mut Checkpoints>,
+    ) -> Result<usize> {
+        while self.turn < steps {
+            if self.turn.is_multiple_of(1 << 20) {
+                if let Some(checkpoints) = checkpoints.as_deref_mut() {
+                    checkpoints.save_if_due(&self)?;
+                }
+            }
+            let say = if let Some(&prev_idx) = self.spoken_numbers.get(&self.previous) {
+                self.turn - prev_idx
+            } else {
+                0
+            };
+            self.spoken_numbers.insert(self.previous, self.turn);
+            self.previous = say;
+            self.turn += 1;
+        }
+        Ok(self.previous)
+    }
+}
+
+impl State for Game {
+    fn encode(&self, out: &mut Encoder) {
+        out.usize(self.turn);
+        out.usize(self.previous);
+        out.usize(self.spoken_numbers.len());
+        for (&number, &turn) in &self.spoken_numbers {
+            out.usize(number);
+            out.usize(turn);
+        }
+    }
+
+    fn decode(input: &mut Decoder) -> Result<Self> {
+        let turn = input.usize()?;
+        let previous = input.usize()?;
+        let len = input.len(16)?;
+        let mut spoken_numbers = FxHashMap::default();
+        spoken_numbers.reserve(len);
+        for _ in 0..len {
+            spoken_numbers.insert(input.usize()?, input.usize()?);
+        }
+        // `play` counts back from the turn to when a number was last spoken
+        if turn == 0 || spoken_numbers.values().any(|&spoken| spoken >= turn) {
+            return Err(input.corrupt());
+        }
+        Ok(Self {
+            spoken_numbers,
+            turn,
+            previous,
+        })
     }
-    previous
 }
 
 #[test]
 fn e0() {
-    assert_eq!(after_steps("0,3,6", 5), 3);
-    assert_eq!(after_steps("0,3,6", 10), 0);
+    assert_eq!(Game::new(&[0, 3, 6]).play(5, None), Ok(3));
+    assert_eq!(Game::new(&[0, 3, 6]).play(10, None), Ok(0));
 }
 #[test]
 fn e1() {
-    assert_eq!(part1("1,3,2"), 1);
-    assert_eq!(part1("2,1,3"), 10);
-    assert_eq!(part1("1,2,3"), 27);
-    assert_eq!(part1("2,3,1"), 78);
-    assert_eq!(part1("3,2,1"), 438);
-    assert_eq!(part1("3,1,2"), 1836);
+    assert_eq!(part1(&parse("1,3,2").unwrap()), Ok(1));
+    assert_eq!(part1(&parse("2,1,3").unwrap()), Ok(10));
+    assert_eq!(part1(&parse("1,2,3").unwrap()), Ok(27));
+    assert_eq!(part1(&parse("2,3,1").unwrap()), Ok(78));
+    assert_eq!(part1(&parse("3,2,1").unwrap()), Ok(438));
+    assert_eq!(part1(&parse("3,1,2").unwrap()), Ok(1836));
 }
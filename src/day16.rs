@@ -2,19 +2,50 @@ use std::ops::RangeInclusive;
 
 use fxhash::FxHashSet;
 
-#[aoc(day16, part1)]
-fn part1(input: &str) -> i64 {
-    let mut components = input.split("\n\n");
-    let fields = parse_fields(components.next().unwrap());
-    let _my_ticket = parse_ticket(components.next().unwrap().lines().nth(1).unwrap());
-    let other_tickets: Vec<Vec<i64>> = components
-        .next()
-        .unwrap()
-        .lines()
-        .skip(1)
-        .map(parse_ticket)
-        .collect();
-    other_tickets
+use crate::{
+    error::{ParseError, Result, Source},
+    normalize,
+    parser::{block, field, int, line, lines, preceded, separated, tag, until, Parser},
+    trace::trace,
+};
+
+pub type Ticket = Vec<i64>;
+
+pub struct Input {
+    pub fields: Vec<Field>,
+    pub my_ticket: Ticket,
+    pub nearby_tickets: Vec<Ticket>,
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day16))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(16, input);
+    let range = (int(), tag("-"), int()).map(|(from, _, to)| from..=to);
+    let field =
+        field(until(": "), ": ", separated(range, tag(" or "))).map(|(name, ranges)| Field {
+            ranges,
+            name: name.to_string(),
+        });
+    let ticket = || separated(int(), tag(","));
+    let my_ticket = preceded(line(tag("your ticket:")), line(ticket()));
+    let nearby_tickets = preceded(line(tag("nearby tickets:")), lines(ticket()));
+    let (fields, my_ticket, nearby_tickets) = Source::new(16, input).run((
+        block(lines(field)),
+        block(my_ticket),
+        block(nearby_tickets),
+    ))?;
+    Ok(Input {
+        fields,
+        my_ticket,
+        nearby_tickets,
+    })
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day16, part1))]
+pub fn part1(input: &Input) -> Result<i64> {
+    let fields = &input.fields;
+    Ok(input
+        .nearby_tickets
         .iter()
         .map(|nearby_ticket| {
             nearby_ticket
@@ -22,66 +53,47 @@ fn part1(input: &str) -> i64 {
                 .filter(|&&value| fields.iter().all(|field| !field.is_valid(value)))
                 .sum::<i64>()
         })
-        .sum()
+        .sum())
 }
-#[aoc(day16, part2)]
-fn part2(input: &str) -> i64 {
-    let mut components = input.split("\n\n");
-    let fields = parse_fields(components.next().unwrap());
-    let _my_ticket = parse_ticket(components.next().unwrap().lines().nth(1).unwrap());
-    let other_tickets: Vec<Vec<i64>> = components
-        .next()
-        .unwrap()
-        .lines()
-        .skip(1)
-        .map(parse_ticket)
+#[cfg_attr(feature = "aoc-runner", aoc(day16, part2))]
+pub fn part2(input: &Input) -> Result<i64> {
+    let fields = &input.fields;
+    let my_ticket = &input.my_ticket;
+    let other_tickets: Vec<Ticket> = input
+        .nearby_tickets
+        .iter()
         .filter(|ticket| {
             ticket
                 .iter()
                 .all(|&value| fields.iter().any(|field| field.is_valid(value)))
         })
+        .cloned()
         .collect();
-    let field_order = find_field_order(&fields, &other_tickets);
+    let field_order = find_field_order(fields, &other_tickets)?;
+    if my_ticket.len() != field_order.len() {
+        return Err(ParseError::input(
+            16,
+            "your ticket has the wrong number of fields",
+        ));
+    }
     let mut acc = 1;
     for (index, field) in field_order.iter().enumerate() {
         if field.name.starts_with("departure") {
-            acc *= _my_ticket[index];
+            acc *= my_ticket[index];
         }
     }
-    acc
-}
-
-fn parse_fields(input: &str) -> Vec<Field> {
-    input.split('\n').map(parse_field).collect()
-}
-
-fn parse_field(line: &str) -> Field {
-    let mut components = line.split(": ");
-    let (name, valid_values) = (components.next().unwrap(), components.next().unwrap());
-    Field {
-        name,
-        ranges: parse_ranges(valid_values),
-    }
+    Ok(acc)
 }
 
-fn parse_ranges(ranges: &str) -> Vec<RangeInclusive<i64>> {
-    let ranges = ranges.split(" or ");
-    ranges.map(parse_range).collect()
-}
-
-fn parse_range(range: &str) -> RangeInclusive<i64> {
-    let mut from_to = range.split('-');
-    from_to.next().unwrap().parse().unwrap()..=from_to.next().unwrap().parse().unwrap()
-}
-
-fn parse_ticket(line: &str) -> Vec<i64> {
-    line.split(',').map(|v| v.parse().unwrap()).collect()
-}
-
-fn find_field_order<'a>(fields: &'a [Field], tickets: &[Vec<i64>]) -> Vec<&'a Field<'a>> {
+fn find_field_order<'a>(fields: &'a [Field], tickets: &[Ticket]) -> Result<Vec<&'a Field>> {
     let len = fields.len();
-    assert!(tickets.iter().all(|t| t.len() == len));
-    let mut positioned: Vec<Vec<&'a Field<'a>>> = vec![vec![]; len];
+    if tickets.iter().any(|t| t.len() != len) {
+        return Err(ParseError::input(
+            16,
+            format!("every ticket has to have {} fields", len),
+        ));
+    }
+    let mut positioned: Vec<Vec<&'a Field>> = vec![vec![]; len];
     let mut found_rules: FxHashSet<*const Field> = FxHashSet::default();
 
     for rule in fields {
@@ -105,14 +117,20 @@ fn find_field_order<'a>(fields: &'a [Field], tickets: &[Vec<i64>]) -> Vec<&'a Fi
                 if idx == origin {
                     continue;
                 }
-                let r_idx = rules.iter().position(|&r| to_remove == (r as *const _));
+                let r_idx = rules.iter().position(|&r| std::ptr::eq(to_remove, r));
                 if let Some(r_idx) = r_idx {
-                    rules.swap_remove(r_idx);
+                    let rule = rules.swap_remove(r_idx);
+                    trace!(16, "eliminate", {
+                        "field": rule.name,
+                        "position": idx,
+                        "candidates": rules.len(),
+                    });
                 }
             }
         }
         for (idx, rules) in positioned.iter().enumerate() {
             if rules.len() == 1 && !found_rules.contains(&(rules[0] as *const _)) {
+                trace!(16, "assign", { "field": rules[0].name, "position": idx });
                 to_remove = Some((rules[0], idx));
                 found_rules.insert(rules[0] as *const _);
                 continue 'outer;
@@ -121,17 +139,20 @@ fn find_field_order<'a>(fields: &'a [Field], tickets: &[Vec<i64>]) -> Vec<&'a Fi
         break;
     }
 
-    assert!(positioned.iter().all(|p| p.len() == 1));
+    if positioned.iter().any(|p| p.len() != 1) {
+        return Err(ParseError::input(16, "the field order is ambiguous"));
+    }
 
-    positioned.into_iter().map(|v| v[0]).collect()
+    Ok(positioned.into_iter().map(|v| v[0]).collect())
 }
 
-struct Field<'a> {
-    ranges: Vec<RangeInclusive<i64>>,
-    name: &'a str,
+/// A field on a ticket with the values it may have.
+pub struct Field {
+    pub ranges: Vec<RangeInclusive<i64>>,
+    pub name: String,
 }
-impl<'a> Field<'a> {
-    fn is_valid(&self, value: i64) -> bool {
+impl Field {
+    pub fn is_valid(&self, value: i64) -> bool {
         self.ranges.iter().any(|range| range.contains(&value))
     }
 }
@@ -150,5 +171,5 @@ nearby tickets:
 40,4,50
 55,2,20
 38,6,12";
-    assert_eq!(part1(input), 71);
+    assert_eq!(part1(&parse(input).unwrap()), Ok(71));
 }
@@ -1,168 +1,212 @@
+use std::ops::ControlFlow;
+
 use fxhash::FxHashSet;
 
-#[aoc(day17, part1)]
-fn part1(input: &str) -> usize {
-    let mut space: Space<3> = Space::from(input);
-    for _ in 0..6 {
-        space = space.step();
-    }
-    space.cubes.len()
-}
-#[aoc(day17, part2)]
-fn part2(input: &str) -> usize {
-    let mut space: Space<4> = Space::from(input);
-    for _ in 0..6 {
-        space = space.step();
-    }
-    space.cubes.len()
+use crate::{
+    automaton::{number_of_neighbors, Coordinates, Moore, Space},
+    checkpoint::{Checkpoints, Decoder, Encoder, State},
+    error::{Result, Source},
+    grid::Grid,
+    normalize,
+};
+
+/// The active cubes of the initial 2D slice.
+pub struct Input {
+    pub active: Vec<[i64; 2]>,
 }
 
-type Coordinates<const N: usize> = [i64; N];
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day17))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(17, input);
+    let slice = Grid::<bool>::parse(&Source::new(17, input))?;
+    let active = slice
+        .positions()
+        .filter(|&pos| slice[pos])
+        .map(|(x, y)| [x as i64, y as i64])
+        .collect();
+    Ok(Input { active })
+}
 
-const fn number_of_neighbors(dimensions: usize) -> usize {
-    3usize.pow(dimensions as u32) - 1
+#[cfg_attr(feature = "aoc-runner", aoc(day17, part1))]
+pub fn part1(input: &Input) -> Result<usize> {
+    Ok(active_after_boot::<3>(input))
 }
-/*
-// This is slower
-struct Neighbors< const N: usize> {
-    coordinates: Coordinates<N>,
-    counter: usize,
+#[cfg_attr(feature = "aoc-runner", aoc(day17, part2))]
+pub fn part2(input: &Input) -> Result<usize> {
+    Ok(active_after_boot::<4>(input))
 }
 
-impl< const N: usize> Neighbors< N> {
-    fn new(coordinates: Coordinates<N>) -> Self {
-        Self {
-            coordinates,
-            counter: 0,
-        }
-    }
+fn active_after_boot<const N: usize>(input: &Input) -> usize
+where
+    [(); number_of_neighbors(N)]:,
+{
+    let mut pocket = Pocket::<N>::new(input);
+    let _ = boot(&mut pocket, |_| ControlFlow::<()>::Continue(()));
+    pocket.space.cells.len()
 }
 
-impl< const N: usize> Iterator for Neighbors< N> {
-    type Item = Coordinates<N>;
+/// Solves `part`, continuing from the cycle in the checkpoint if there is one.
+pub fn resume(input: &Input, part: u8, checkpoints: &mut Checkpoints) -> Result<usize> {
+    let answer = if part == 1 {
+        simulate::<3>(input, checkpoints)?
+    } else {
+        simulate::<4>(input, checkpoints)?
+    };
+    checkpoints.finish()?;
+    Ok(answer)
+}
 
-    #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.counter == number_of_neighbors(N) {
-            return None;
-        }
-        let i = if self.counter >= number_of_neighbors(N) / 2 {
-            self.counter + 1
-        } else {
-            self.counter
-        };
-        self.counter += 1;
-        let mut coordinates = self.coordinates;
-        for dimension in 0..N {
-            match (i / 3usize.pow(dimension as u32)) % 3 {
-                0 => coordinates[dimension] -= 1,
-                1 => {}
-                2 => coordinates[dimension] += 1,
-                _ => unreachable!(),
-            }
+fn simulate<const N: usize>(input: &Input, checkpoints: &mut Checkpoints) -> Result<usize>
+where
+    [(); number_of_neighbors(N)]:,
+{
+    let pocket = checkpoints.load()?;
+    let mut pocket = pocket.unwrap_or_else(|| Pocket::<N>::new(input));
+    let saved = boot(&mut pocket, |pocket| {
+        match checkpoints.save_if_due(pocket) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(error) => ControlFlow::Break(error),
         }
-        Some(coordinates)
-    }
-
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        (number_of_neighbors(N), Some(number_of_neighbors(N)))
+    });
+    if let ControlFlow::Break(error) = saved {
+        return Err(error);
     }
+    Ok(pocket.space.cells.len())
 }
-*/
-fn neighbors<const N: usize>(
-    input_coordinates: &Coordinates<N>,
-) -> [Coordinates<N>; number_of_neighbors(N)] {
-    let mut neighbors = [*input_coordinates; number_of_neighbors(N)];
-    for (i, coordinates) in neighbors.iter_mut().enumerate() {
-        let i = if i >= number_of_neighbors(N) / 2 {
-            i + 1
-        } else {
-            i
-        };
-        for (dimension, coordinate) in coordinates.iter_mut().enumerate() {
-            match (i / 3usize.pow(dimension as u32)) % 3 {
-                0 => *coordinate -= 1,
-                1 => {}
-                2 => *coordinate += 1,
-                _ => unreachable!(),
-            }
+
+/// Runs the cycles of the boot process that `pocket` hasn't gone through yet. `each`
+/// sees the pocket before every cycle and after the last one, and can stop the boot.
+fn boot<B, const N: usize>(
+    pocket: &mut Pocket<N>,
+    mut each: impl FnMut(&Pocket<N>) -> ControlFlow<B>,
+) -> ControlFlow<B>
+where
+    [(); number_of_neighbors(N)]:,
+{
+    loop {
+        each(pocket)?;
+        if pocket.cycle >= 6 {
+            return ControlFlow::Continue(());
         }
+        pocket.space = pocket.space.step(conway);
+        pocket.cycle += 1;
     }
-    neighbors
 }
 
-#[derive(Clone)]
-struct Space<const N: usize>
+/// The pocket dimension after some cycles.
+struct Pocket<const N: usize>
 where
-    [(); number_of_neighbors(N)]: ,
+    [(); number_of_neighbors(N)]:,
 {
-    cubes: FxHashSet<Coordinates<N>>,
+    space: Space<Moore<N>>,
+    cycle: usize,
 }
 
-impl<const N: usize> Space<N>
+impl<const N: usize> Pocket<N>
 where
-    [(); number_of_neighbors(N)]: ,
+    [(); number_of_neighbors(N)]:,
 {
-    #[inline]
-    fn should_be_active(&self, coordinates: &Coordinates<N>) -> bool {
-        let neighbors = neighbors(coordinates);
-        let mut active_neighbors = neighbors.iter().filter(|&c| self.cubes.contains(c));
-        if self.cubes.contains(coordinates) {
-            // cube is currently active and should remain active if 2 or 3 neighbors are active
-            active_neighbors.nth(1).is_some() && active_neighbors.nth(1).is_none()
-        } else {
-            // cube is currently inactive and should become active if 3 neighbors are active
-            active_neighbors.nth(2).is_some() && active_neighbors.next().is_none()
+    fn new(input: &Input) -> Self {
+        Self {
+            space: space(input),
+            cycle: 0,
         }
     }
+}
 
-    fn step(&self) -> Self {
-        let mut to_visit: FxHashSet<Coordinates<N>> = FxHashSet::default();
-
-        for &coordinates in self.cubes.iter() {
-            to_visit.insert(coordinates);
-            to_visit.extend(&neighbors(&coordinates));
+impl<const N: usize> State for Pocket<N>
+where
+    [(); number_of_neighbors(N)]:,
+{
+    fn encode(&self, out: &mut Encoder) {
+        out.usize(N);
+        out.usize(self.cycle);
+        out.usize(self.space.cells.len());
+        for cube in &self.space.cells {
+            for &coordinate in cube {
+                out.i64(coordinate);
+            }
         }
-        to_visit.retain(|coordinates| self.should_be_active(coordinates));
+    }
 
-        Self { cubes: to_visit }
+    fn decode(input: &mut Decoder) -> Result<Self> {
+        if input.usize()? != N {
+            return Err(input.corrupt());
+        }
+        let cycle = input.usize()?;
+        if cycle > 6 {
+            return Err(input.corrupt());
+        }
+        let len = input.len(8 * N)?;
+        let mut cells = FxHashSet::default();
+        for _ in 0..len {
+            let mut cube = [0; N];
+            for coordinate in &mut cube {
+                *coordinate = input.i64()?;
+            }
+            cells.insert(cube);
+        }
+        Ok(Self {
+            space: Space::new(cells),
+            cycle,
+        })
     }
 }
 
-impl<const N: usize> From<&str> for Space<N>
+/// The pocket dimension with `N` dimensions, initially and after each of the six cycles.
+pub(crate) fn cycles<const N: usize>(input: &Input) -> Vec<Space<Moore<N>>>
 where
-    [(); number_of_neighbors(N)]: ,
+    [(); number_of_neighbors(N)]:,
 {
-    fn from(input: &str) -> Self {
-        let mut cubes = FxHashSet::default();
-        for (x, line) in input.lines().enumerate() {
-            for (y, char) in line.chars().enumerate() {
-                match char {
-                    '#' => {
-                        let mut coordinates = [0; N];
-                        coordinates[0] = x as i64;
-                        coordinates[1] = y as i64;
-                        cubes.insert(coordinates);
-                    }
-                    '.' => {}
-                    _ => unreachable!(),
-                };
-            }
-        }
-        Self { cubes }
+    let mut cycles = vec![];
+    let _ = boot(&mut Pocket::new(input), |pocket| {
+        cycles.push(Space::new(pocket.space.cells.clone()));
+        ControlFlow::<()>::Continue(())
+    });
+    cycles
+}
+
+fn conway(active: bool, active_neighbors: usize) -> bool {
+    if active {
+        // cube is currently active and should remain active if 2 or 3 neighbors are active
+        active_neighbors == 2 || active_neighbors == 3
+    } else {
+        // cube is currently inactive and should become active if 3 neighbors are active
+        active_neighbors == 3
     }
 }
 
-#[test]
-fn find_neighbors() {
-    let neighbors = neighbors(&[0]);
-    assert_eq!(neighbors, [[-1], [1]]);
+fn space<const N: usize>(input: &Input) -> Space<Moore<N>>
+where
+    [(); number_of_neighbors(N)]:,
+{
+    let cubes: FxHashSet<Coordinates<N>> = input
+        .active
+        .iter()
+        .map(|&[x, y]| {
+            let mut coordinates = [0; N];
+            coordinates[0] = x;
+            coordinates[1] = y;
+            coordinates
+        })
+        .collect();
+    Space::new(cubes)
 }
+
 #[test]
 fn e1() {
     let input = ".#.
 ..#
 ###";
-    assert_eq!(part1(input), 112);
-    assert_eq!(part2(input), 848);
+    let input = parse(input).unwrap();
+    assert_eq!(part1(&input), Ok(112));
+    assert_eq!(part2(&input), Ok(848));
+}
+#[test]
+fn invalid() {
+    assert_eq!(
+        parse(".#.\n.x.").err().map(|e| e.to_string()),
+        Some("day 17, line 2, column 2: unexpected `x`".to_string())
+    );
+    assert!(parse(".#.\n.#").is_err());
 }
@@ -1,51 +1,105 @@
 use std::mem;
 
-#[aoc(day18, part1)]
-fn part1(input: &str) -> i64 {
-    input.lines().map(parse_line::<{ Priorities::None }>).sum()
+use crate::{
+    error::{ParseError, Result, Source},
+    normalize,
+};
+
+pub struct Input {
+    /// One for every line of the input, in order.
+    pub expressions: Vec<String>,
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day18))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(18, input);
+    let src = Source::new(18, input);
+    let mut expressions = Vec::new();
+    for line in input.lines() {
+        // evaluating is cheap, so we do it once to report syntax errors with their position
+        parse_line::<{ Priorities::None }>(&src, line)?;
+        expressions.push(line.to_string());
+    }
+    Ok(Input { expressions })
 }
-#[aoc(day18, part2)]
-fn part2(input: &str) -> i64 {
+
+#[cfg_attr(feature = "aoc-runner", aoc(day18, part1))]
+pub fn part1(input: &Input) -> Result<i64> {
+    sum_of_results::<{ Priorities::None }>(input)
+}
+#[cfg_attr(feature = "aoc-runner", aoc(day18, part2))]
+pub fn part2(input: &Input) -> Result<i64> {
+    sum_of_results::<{ Priorities::Inverse }>(input)
+}
+
+fn sum_of_results<const P: Priorities>(input: &Input) -> Result<i64> {
     input
-        .lines()
-        .map(parse_line::<{ Priorities::Inverse }>)
-        .sum()
+        .expressions
+        .iter()
+        .enumerate()
+        .map(|(idx, line)| {
+            // errors are found within the line, but reported where it is in the input
+            parse_line::<P>(&Source::new(18, line), line).map_err(|error| ParseError {
+                line: idx + 1,
+                ..error
+            })
+        })
+        .try_fold(0, checked_sum)
 }
 
-fn parse_line<const P: Priorities>(input: &str) -> i64 {
+fn checked_sum(sum: i64, result: Result<i64>) -> Result<i64> {
+    sum.checked_add(result?)
+        .ok_or_else(|| ParseError::input(18, "the sum of the results is too large"))
+}
+
+fn parse_line<const P: Priorities>(src: &Source, input: &str) -> Result<i64> {
     let mut tokens = Tokenizer::new(input);
 
-    parse_expression::<P>(&mut tokens).eval()
+    let ast = parse_expression::<P>(src, &mut tokens)?;
+    if let Some(token) = tokens.next() {
+        return Err(src.error(token, format!("unexpected `{}`", token)));
+    }
+    ast.eval()
+        .ok_or_else(|| src.error(input, "the result is too large"))
 }
 
-fn parse_expression<const P: Priorities>(tokens: &mut Tokenizer) -> AstNode<P> {
-    let mut lhs = parse_value_or_parens(tokens);
-    let op = tokens.next().unwrap();
-    let rhs = Box::new(parse_value_or_parens(tokens));
-    lhs = lhs.append(op, rhs);
+fn parse_expression<const P: Priorities>(
+    src: &Source,
+    tokens: &mut Tokenizer,
+) -> Result<AstNode<P>> {
+    let mut lhs = parse_value_or_parens(src, tokens)?;
     loop {
         if matches!(tokens.peek(), None | Some(")")) {
             break;
         }
         let op = tokens.next().unwrap();
-        let rhs = Box::new(parse_value_or_parens(tokens));
+        if !matches!(op, "+" | "*") {
+            return Err(src.error(op, format!("expected `+` or `*`, found `{}`", op)));
+        }
+        let rhs = Box::new(parse_value_or_parens(src, tokens)?);
         lhs = lhs.append(op, rhs);
     }
-    lhs
+    Ok(lhs)
 }
 
-fn parse_value_or_parens<const P: Priorities>(tokens: &mut Tokenizer) -> AstNode<P> {
-    match tokens.next().unwrap() {
-        "(" => {
-            let val = parse_expression(tokens);
-            assert_eq!(tokens.next(), Some(")"));
-            AstNode::Paren(Box::new(val))
+fn parse_value_or_parens<const P: Priorities>(
+    src: &Source,
+    tokens: &mut Tokenizer,
+) -> Result<AstNode<P>> {
+    match tokens.next() {
+        Some("(") => {
+            let val = parse_expression(src, tokens)?;
+            if tokens.next().is_none() {
+                return Err(src.error(tokens.remaining, "expected `)`"));
+            }
+            Ok(AstNode::Paren(Box::new(val)))
         }
-        val => AstNode::Number(val.parse().unwrap()),
+        Some(val) => Ok(AstNode::Number(src.parse(val)?)),
+        None => Err(src.error(tokens.remaining, "unexpected end of expression")),
     }
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, std::marker::ConstParamTy)]
 enum Priorities {
     None,
     Inverse,
@@ -88,7 +142,7 @@ impl<const P: Priorities> AstNode<P> {
         } else if let AstNode::Times(_, prev_rhs) = &mut self {
             let mut tmp = AstNode::<P>::Number(-1);
             mem::swap(&mut tmp, prev_rhs);
-            *prev_rhs = Box::new(AstNode::from_op(Box::new(tmp), op, rhs));
+            **prev_rhs = AstNode::from_op(Box::new(tmp), op, rhs);
             self
         } else {
             unreachable!();
@@ -103,12 +157,13 @@ impl<const P: Priorities> AstNode<P> {
         }
     }
 
-    fn eval(&self) -> i64 {
+    /// The value, or `None` if it doesn't fit into an `i64`.
+    fn eval(&self) -> Option<i64> {
         match self {
             AstNode::Paren(node) => node.eval(),
-            AstNode::Plus(lhs, rhs) => lhs.eval() + rhs.eval(),
-            AstNode::Times(lhs, rhs) => lhs.eval() * rhs.eval(),
-            AstNode::Number(num) => *num,
+            AstNode::Plus(lhs, rhs) => lhs.eval()?.checked_add(rhs.eval()?),
+            AstNode::Times(lhs, rhs) => lhs.eval()?.checked_mul(rhs.eval()?),
+            AstNode::Number(num) => Some(*num),
         }
     }
 }
@@ -148,18 +203,18 @@ impl<'a> Iterator for Tokenizer<'a> {
         if let Some(to) = to {
             if to == 0 {
                 let token = &self.remaining[..=0];
-                self.remaining = &self.remaining[1..].trim_start();
+                self.remaining = self.remaining[1..].trim_start();
                 Some(token)
             } else {
                 let token = &self.remaining[..to];
-                self.remaining = &self.remaining[to..].trim_start();
+                self.remaining = self.remaining[to..].trim_start();
                 Some(token)
             }
         } else if self.remaining.is_empty() {
             None
         } else {
             let token = self.remaining;
-            self.remaining = "";
+            self.remaining = &self.remaining[self.remaining.len()..];
             Some(token)
         }
     }
@@ -168,34 +223,91 @@ impl<'a> Iterator for Tokenizer<'a> {
 #[test]
 fn e1() {
     let input = "1 + 2 * 3 + 4 * 5 + 6";
-    assert_eq!(parse_line::<{ Priorities::None }>(input), 71);
+    assert_eq!(
+        parse_line::<{ Priorities::None }>(&Source::new(18, input), input),
+        Ok(71)
+    );
 }
 #[test]
 fn e2() {
     let input = "1 + (2 * 3) + (4 * (5 + 6))";
-    assert_eq!(parse_line::<{ Priorities::None }>(input), 51);
+    assert_eq!(
+        parse_line::<{ Priorities::None }>(&Source::new(18, input), input),
+        Ok(51)
+    );
     let input = "2 * 3 + (4 * 5)";
-    assert_eq!(parse_line::<{ Priorities::None }>(input), 26);
+    assert_eq!(
+        parse_line::<{ Priorities::None }>(&Source::new(18, input), input),
+        Ok(26)
+    );
     let input = "5 + (8 * 3 + 9 + 3 * 4 * 3)";
-    assert_eq!(parse_line::<{ Priorities::None }>(input), 437);
+    assert_eq!(
+        parse_line::<{ Priorities::None }>(&Source::new(18, input), input),
+        Ok(437)
+    );
     let input = "5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))";
-    assert_eq!(parse_line::<{ Priorities::None }>(input), 12240);
+    assert_eq!(
+        parse_line::<{ Priorities::None }>(&Source::new(18, input), input),
+        Ok(12240)
+    );
     let input = "((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2";
-    assert_eq!(parse_line::<{ Priorities::None }>(input), 13632);
+    assert_eq!(
+        parse_line::<{ Priorities::None }>(&Source::new(18, input), input),
+        Ok(13632)
+    );
 }
 
 #[test]
 fn e3() {
     let input = "1 + 2 * 3 + 4 * 5 + 6";
-    assert_eq!(parse_line::<{ Priorities::Inverse }>(input), 231);
+    assert_eq!(
+        parse_line::<{ Priorities::Inverse }>(&Source::new(18, input), input),
+        Ok(231)
+    );
     let input = "1 + (2 * 3) + (4 * (5 + 6))";
-    assert_eq!(parse_line::<{ Priorities::Inverse }>(input), 51);
+    assert_eq!(
+        parse_line::<{ Priorities::Inverse }>(&Source::new(18, input), input),
+        Ok(51)
+    );
     let input = "2 * 3 + (4 * 5)";
-    assert_eq!(parse_line::<{ Priorities::Inverse }>(input), 46);
+    assert_eq!(
+        parse_line::<{ Priorities::Inverse }>(&Source::new(18, input), input),
+        Ok(46)
+    );
     let input = "5 + (8 * 3 + 9 + 3 * 4 * 3)";
-    assert_eq!(parse_line::<{ Priorities::Inverse }>(input), 1445);
+    assert_eq!(
+        parse_line::<{ Priorities::Inverse }>(&Source::new(18, input), input),
+        Ok(1445)
+    );
     let input = "5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))";
-    assert_eq!(parse_line::<{ Priorities::Inverse }>(input), 669060);
+    assert_eq!(
+        parse_line::<{ Priorities::Inverse }>(&Source::new(18, input), input),
+        Ok(669060)
+    );
     let input = "((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2";
-    assert_eq!(parse_line::<{ Priorities::Inverse }>(input), 23340);
+    assert_eq!(
+        parse_line::<{ Priorities::Inverse }>(&Source::new(18, input), input),
+        Ok(23340)
+    );
+}
+#[test]
+fn overflow() {
+    assert_eq!(
+        parse("999999999 * 999999999 * 999999999")
+            .err()
+            .map(|e| e.to_string()),
+        Some("day 18, line 1, column 1: the result is too large".to_string())
+    );
+    let input = parse("4611686018427387904 + 1\n4611686018427387904 * 1").unwrap();
+    assert_eq!(
+        part1(&input).unwrap_err().to_string(),
+        "day 18: the sum of the results is too large"
+    );
+    // only too large with addition first
+    let input = parse("1\n2\n0 * 9000000000000000000 + 9000000000000000000").unwrap();
+    assert_eq!(part1(&input), Ok(9000000000000000003));
+    assert_eq!(
+        part2(&input).unwrap_err().to_string(),
+        "day 18, line 3, column 1: the result is too large"
+    );
 }
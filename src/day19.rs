@@ -1,34 +1,153 @@
-use std::str::Split;
+use crate::{
+    error::{ParseError, Result, Source},
+    normalize,
+    parser::{alt, block, field, int, lines, separated, spanned, tag, until, word, Parser},
+};
 
-#[aoc(day19, part1)]
-fn part1(input: &str) -> usize {
-    valid_messages(input, false)
+pub struct Input {
+    /// The rules, indexed by their number.
+    pub rules: Vec<Rule>,
+    pub messages: Vec<String>,
 }
 
-#[aoc(day19, part2)]
-fn part2(input: &str) -> usize {
-    valid_messages(input, true)
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day19))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(19, input);
+    let src = Source::new(19, input);
+    // only the first letter counts, the examples have some placeholder rules with text
+    let letter = (tag("\""), until("\""), tag("\""))
+        .map(|(_, text, _): (_, &str, _)| Rule::Letter(text.chars().next().unwrap()));
+    let alternatives = separated(separated(int(), tag(" ")), tag(" | ")).map(|mut alternatives| {
+        if alternatives.len() == 1 {
+            Rule::Combination(alternatives.pop().unwrap())
+        } else {
+            Rule::Or(alternatives.into_iter().map(Rule::Combination).collect())
+        }
+    });
+    let rule = spanned(field(int::<usize>(), ": ", alt(letter, alternatives)));
+    let message = word().map(str::to_string);
+    let (mut rules, messages) = src.run((block(lines(rule)), lines(message)))?;
+    rules.sort_by_key(|&(_, (idx, _))| idx);
+    if rules
+        .iter()
+        .enumerate()
+        .any(|(expected, &(_, (idx, _)))| idx != expected)
+    {
+        return Err(src.error_input("rules have to be numbered from 0 without gaps"));
+    }
+    let (lines, rules): (Vec<&str>, Vec<Rule>) = rules
+        .into_iter()
+        .map(|(line, (_, rule))| (line, rule))
+        .unzip();
+    if let Some(idx) = rules
+        .iter()
+        .position(|rule| !rule.references_valid(rules.len()))
+    {
+        return Err(src.error_input(format!(
+            "rule {} is empty or references a missing rule",
+            idx
+        )));
+    }
+    if let Some(idx) = left_recursive(&rules) {
+        return Err(src.error(lines[idx], left_recursion(idx)));
+    }
+    Ok(Input { rules, messages })
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day19, part1))]
+pub fn part1(input: &Input) -> Result<usize> {
+    Ok(valid_messages(&input.rules, &input.messages))
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day19, part2))]
+pub fn part2(input: &Input) -> Result<usize> {
+    if input.rules.len() <= 42 {
+        return Err(ParseError::input(
+            19,
+            "part 2 needs rules 8, 11, 31 and 42 to exist",
+        ));
+    }
+    let mut rules = input.rules.clone();
+    rules[8] = Rule::Or(vec![
+        Rule::Combination(vec![42]),
+        Rule::Combination(vec![42, 8]),
+    ]);
+    rules[11] = Rule::Or(vec![
+        Rule::Combination(vec![42, 31]),
+        Rule::Combination(vec![42, 11, 31]),
+    ]);
+    if let Some(idx) = left_recursive(&rules) {
+        return Err(ParseError::input(19, left_recursion(idx)));
+    }
+    Ok(valid_messages(&rules, &input.messages))
+}
+
+/// A rule that can get back to itself before matching a letter, which would make `walk`
+/// recurse forever.
+fn left_recursive(rules: &[Rule]) -> Option<usize> {
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum Visit {
+        New,
+        Open,
+        Done,
+    }
+    fn visit(rules: &[Rule], idx: usize, visits: &mut [Visit]) -> Option<usize> {
+        match visits[idx] {
+            Visit::Open => return Some(idx),
+            Visit::Done => return None,
+            Visit::New => {}
+        }
+        visits[idx] = Visit::Open;
+        let mut firsts = vec![];
+        rules[idx].firsts(&mut firsts);
+        for first in firsts {
+            if let Some(idx) = visit(rules, first, visits) {
+                return Some(idx);
+            }
+        }
+        visits[idx] = Visit::Done;
+        None
+    }
+    let mut visits = vec![Visit::New; rules.len()];
+    (0..rules.len()).find_map(|idx| visit(rules, idx, &mut visits))
+}
+
+fn left_recursion(idx: usize) -> String {
+    format!("rule {} can match itself before matching a letter", idx)
 }
 
-fn valid_messages(input: &str, part2: bool) -> usize {
-    let (rules, messages) = input.split_once("\n\n").unwrap();
-    let mut rules: Vec<(usize, Rule)> = rules.lines().map(|rule| parse_rule(rule, part2)).collect();
-    rules.sort_by_key(|&(idx, _)| idx);
-    assert_eq!(rules.last().unwrap().0, rules.len() - 1);
-    let rules: Vec<Rule> = rules.into_iter().map(|(_, rule)| rule).collect();
+fn valid_messages(rules: &[Rule], messages: &[String]) -> usize {
     messages
-        .lines()
-        .filter(|message| rules[0].walk(message, &rules, &mut vec![]))
+        .iter()
+        .filter(|message| rules[0].walk(message, rules, &mut vec![]))
         .count()
 }
 
-enum Rule {
+#[derive(Clone, Debug)]
+pub enum Rule {
     Letter(char),
     Combination(Vec<usize>),
     Or(Vec<Rule>),
 }
 
 impl Rule {
+    fn references_valid(&self, rule_count: usize) -> bool {
+        match self {
+            Rule::Letter(_) => true,
+            Rule::Combination(list) => !list.is_empty() && list.iter().all(|&r| r < rule_count),
+            Rule::Or(options) => options.iter().all(|o| o.references_valid(rule_count)),
+        }
+    }
+
+    /// The rules that `walk` goes to before matching a letter.
+    fn firsts(&self, firsts: &mut Vec<usize>) {
+        match self {
+            Rule::Letter(_) => {}
+            Rule::Combination(list) => firsts.push(list[0]),
+            Rule::Or(options) => options.iter().for_each(|o| o.firsts(firsts)),
+        }
+    }
+
     fn walk(&self, remaining: &str, rules: &[Rule], back_stack: &mut Vec<usize>) -> bool {
         match self {
             &Rule::Letter(l) => {
@@ -65,44 +184,22 @@ impl Rule {
     }
 }
 
-fn parse_rule(line: &str, part2: bool) -> (usize, Rule) {
-    let (idx, mut body) = line.split_once(": ").unwrap();
-    if part2 {
-        if idx == "8" {
-            body = "42 | 42 8"
-        }
-        if idx == "11" {
-            body = "42 31 | 42 11 31";
-        }
-    }
-    let rule = if body.starts_with('"') {
-        Rule::Letter(body.chars().nth(1).unwrap())
-    } else {
-        let mut tokens = body.split(' ');
-        let first_numbers = parse_numbers(&mut tokens);
-        let second_numbers = parse_numbers(&mut tokens);
-        if second_numbers.is_empty() {
-            Rule::Combination(first_numbers)
-        } else {
-            Rule::Or(vec![
-                Rule::Combination(first_numbers),
-                Rule::Combination(second_numbers),
-            ])
-        }
-    };
-    (idx.parse().unwrap(), rule)
-}
-
-fn parse_numbers(tokens: &mut Split<char>) -> Vec<usize> {
-    let mut numbers = vec![];
-    for token in tokens {
-        if token != "|" {
-            numbers.push(token.parse().unwrap());
-        } else {
-            break;
-        }
-    }
-    numbers
+#[test]
+fn left_recursion_is_rejected() {
+    let error = |input| parse(input).err().unwrap().to_string();
+    assert_eq!(
+        error("0: 0 1\n1: \"a\"\n\naa"),
+        "day 19, line 1, column 1: rule 0 can match itself before matching a letter"
+    );
+    assert_eq!(
+        error("0: 1\n1: 2 1 | 3\n2: 0 2\n3: \"b\"\n\nb"),
+        "day 19, line 1, column 1: rule 0 can match itself before matching a letter"
+    );
+    assert_eq!(
+        error("0: 1 0 | 1\n1: \"a\"\n2: 2 1\n\naa"),
+        "day 19, line 3, column 1: rule 2 can match itself before matching a letter"
+    );
+    assert_eq!(part1(&parse("0: 1 0 | 1\n1: \"a\"\n\naa").unwrap()), Ok(1));
 }
 
 #[test]
@@ -114,7 +211,7 @@ fn e0() {
 
 aba
 aab"#;
-    assert_eq!(part1(input), 2);
+    assert_eq!(part1(&parse(input).unwrap()), Ok(2));
 }
 #[test]
 fn e1() {
@@ -130,7 +227,7 @@ bababa
 abbbab
 aaabbb
 aaaabbb"#;
-    assert_eq!(part1(input), 2);
+    assert_eq!(part1(&parse(input).unwrap()), Ok(2));
 }
 
 #[test]
@@ -194,6 +291,7 @@ aaaabbaaaabbaaa
 aaaabbaabbaaaaaaabbbabbbaaabbaabaaa
 babaaabbbaaabaababbaabababaaab
 aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba"#;
-    assert_eq!(part1(input), 3);
-    assert_eq!(part2(input), 12);
+    let input = parse(input).unwrap();
+    assert_eq!(part1(&input), Ok(3));
+    assert_eq!(part2(&input), Ok(12));
 }
@@ -1,52 +1,281 @@
-#[aoc(day2, part1)]
-fn part1(input: &str) -> usize {
-    input
-        .lines()
-        .map(|p| parse_pass(p))
-        .filter(|p| p.is_valid())
-        .count()
+use std::{fmt, io::BufRead};
+
+use regex::Regex;
+
+use crate::{
+    error::{Result, Source},
+    normalize,
+    parser::{any_char, int, lines, tag, word, Parser},
+    stream::Lines,
+};
+
+pub struct Input {
+    pub passwords: Vec<Pass>,
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day2))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(2, input);
+    Ok(Input {
+        passwords: Source::new(2, input).run(lines(pass()))?,
+    })
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day2, part1))]
+pub fn part1(input: &Input) -> Result<usize> {
+    Ok(count_valid(input, &CountRange))
 }
-#[aoc(day2, part2)]
-fn part2(input: &str) -> usize {
+#[cfg_attr(feature = "aoc-runner", aoc(day2, part2))]
+pub fn part2(input: &Input) -> Result<usize> {
+    Ok(count_valid(input, &Positions))
+}
+
+fn count_valid(input: &Input, policy: &dyn PasswordPolicy) -> usize {
     input
-        .lines()
-        .map(|p| parse_pass(p))
-        .filter(|p| p.is_valid_2())
+        .passwords
+        .iter()
+        .filter(|p| policy.check(p).is_ok())
         .count()
 }
 
-fn parse_pass(pass: &str) -> Pass {
-    let mut components = pass.split_ascii_whitespace();
-    let mut from_to = components.next().unwrap().split('-');
-    let c = components.next().unwrap().chars().next().unwrap();
-    let str = components.next().unwrap();
-    Pass {
-        from: from_to.next().unwrap().parse().unwrap(),
-        to: from_to.next().unwrap().parse().unwrap(),
-        letter: c,
-        pass: str,
+/// Both parts, reading the passwords one at a time.
+pub fn stream(input: impl BufRead) -> Result<(usize, usize)> {
+    let mut lines = Lines::new(2, input);
+    let (mut valid, mut valid_2) = (0, 0);
+    while let Some(pass) = lines.parse(pass())? {
+        valid += usize::from(CountRange.check(&pass).is_ok());
+        valid_2 += usize::from(Positions.check(&pass).is_ok());
+    }
+    Ok((valid, valid_2))
+}
+
+fn pass<'a>() -> impl Parser<'a, Pass> {
+    (
+        int(),
+        tag("-"),
+        int(),
+        tag(" "),
+        any_char(),
+        tag(": "),
+        word(),
+    )
+        .map(|(from, _, to, _, letter, _, pass)| Pass {
+            letter,
+            from,
+            to,
+            pass: pass.to_string(),
+        })
+}
+
+/// A password along with the policy that was in place when it was set.
+pub struct Pass {
+    pub letter: char,
+    pub from: usize,
+    pub to: usize,
+    pub pass: String,
+}
+
+/// A rule that passwords have to follow.
+pub trait PasswordPolicy {
+    /// Why `pass` breaks the rule, if it does.
+    fn check(&self, pass: &Pass) -> Result<(), String>;
+}
+
+/// The letter has to appear `from` to `to` times, the policy of the sled rental place.
+pub struct CountRange;
+
+impl PasswordPolicy for CountRange {
+    fn check(&self, pass: &Pass) -> Result<(), String> {
+        let n = pass.pass.chars().filter(|&c| c == pass.letter).count();
+        if n >= pass.from && n <= pass.to {
+            Ok(())
+        } else {
+            Err(format!(
+                "`{}` appears {} times, not {} to {} times",
+                pass.letter, n, pass.from, pass.to
+            ))
+        }
+    }
+}
+
+/// The letter has to be at exactly one of the 1-based positions `from` and `to`, the
+/// policy of the Official Toboggan Corporate Authentication System. Positions past the
+/// end of the password don't have the letter.
+pub struct Positions;
+
+impl PasswordPolicy for Positions {
+    fn check(&self, pass: &Pass) -> Result<(), String> {
+        let at = |position: usize| {
+            position
+                .checked_sub(1)
+                .and_then(|idx| pass.pass.chars().nth(idx))
+                == Some(pass.letter)
+        };
+        match (at(pass.from), at(pass.to)) {
+            (true, false) | (false, true) => Ok(()),
+            (true, true) => Err(format!(
+                "`{}` is at both positions {} and {}",
+                pass.letter, pass.from, pass.to
+            )),
+            (false, false) => Err(format!(
+                "`{}` is at neither position {} nor {}",
+                pass.letter, pass.from, pass.to
+            )),
+        }
+    }
+}
+
+/// The password has to have at least this many characters.
+pub struct MinLength(pub usize);
+
+impl PasswordPolicy for MinLength {
+    fn check(&self, pass: &Pass) -> Result<(), String> {
+        let len = pass.pass.chars().count();
+        if len >= self.0 {
+            Ok(())
+        } else {
+            Err(format!(
+                "is {} characters long, not at least {}",
+                len, self.0
+            ))
+        }
+    }
+}
+
+/// The password must not contain any of these.
+pub struct Forbidden(pub Vec<String>);
+
+impl PasswordPolicy for Forbidden {
+    fn check(&self, pass: &Pass) -> Result<(), String> {
+        match self
+            .0
+            .iter()
+            .find(|forbidden| pass.pass.contains(forbidden.as_str()))
+        {
+            Some(forbidden) => Err(format!("contains `{}`", forbidden)),
+            None => Ok(()),
+        }
     }
 }
 
-struct Pass<'a> {
-    letter: char,
-    from: usize,
-    to: usize,
-    pass: &'a str,
+/// The password has to match the regex.
+pub struct Matches(pub Regex);
+
+impl PasswordPolicy for Matches {
+    fn check(&self, pass: &Pass) -> Result<(), String> {
+        if self.0.is_match(&pass.pass) {
+            Ok(())
+        } else {
+            Err(format!("doesn't match `{}`", self.0))
+        }
+    }
 }
 
-impl<'a> Pass<'a> {
-    fn is_valid(&self) -> bool {
-        let n = self
-            .pass
-            .chars()
-            .into_iter()
-            .filter(|&c| c == self.letter)
-            .count();
-        n >= self.from && n <= self.to
+/// All of the policies have to be followed. Reports every policy that is broken.
+pub struct All(pub Vec<Box<dyn PasswordPolicy>>);
+
+impl PasswordPolicy for All {
+    fn check(&self, pass: &Pass) -> Result<(), String> {
+        let reasons: Vec<String> = self
+            .0
+            .iter()
+            .filter_map(|policy| policy.check(pass).err())
+            .collect();
+        if reasons.is_empty() {
+            Ok(())
+        } else {
+            Err(reasons.join(", "))
+        }
     }
-    fn is_valid_2(&self) -> bool {
-        let chars: Vec<_> = self.pass.chars().collect();
-        (chars[self.from - 1] == self.letter) ^ (chars[self.to - 1] == self.letter)
+}
+
+/// A policy from its description: `count`, `positions`, `min-length=<n>`,
+/// `forbid=<text>` or `matches=<regex>`.
+pub fn policy(description: &str) -> Result<Box<dyn PasswordPolicy>, String> {
+    let (name, value) = match description.split_once('=') {
+        Some((name, value)) => (name, Some(value)),
+        None => (description, None),
+    };
+    Ok(match (name, value) {
+        ("count", None) => Box::new(CountRange),
+        ("positions", None) => Box::new(Positions),
+        ("min-length", Some(len)) => Box::new(MinLength(
+            len.parse()
+                .map_err(|_| format!("invalid length `{}`", len))?,
+        )),
+        ("forbid", Some(text)) => Box::new(Forbidden(vec![text.to_string()])),
+        ("matches", Some(regex)) => Box::new(Matches(
+            Regex::new(regex).map_err(|e| format!("invalid regex `{}`: {}", regex, e))?,
+        )),
+        _ => return Err(format!("unknown policy `{}`", description)),
+    })
+}
+
+/// A password that breaks a policy.
+pub struct Violation {
+    /// The 1-based line of the password in the input.
+    pub line: usize,
+    pub pass: String,
+    pub reason: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: `{}` {}", self.line, self.pass, self.reason)
     }
 }
+
+/// Every password in `input` that breaks `policy`, with the reason.
+pub fn audit(input: &Input, policy: &dyn PasswordPolicy) -> Vec<Violation> {
+    input
+        .passwords
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, pass)| {
+            let reason = policy.check(pass).err()?;
+            Some(Violation {
+                line: idx + 1,
+                pass: pass.pass.clone(),
+                reason,
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn policies() {
+    let input = parse("1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc\n3-40 d: dd").unwrap();
+    assert_eq!(part1(&input), Ok(2));
+    assert_eq!(part2(&input), Ok(1));
+    let violations: Vec<String> = audit(&input, &Positions)
+        .iter()
+        .map(|violation| violation.to_string())
+        .collect();
+    assert_eq!(
+        violations,
+        [
+            "line 2: `cdefg` `b` is at neither position 1 nor 3",
+            "line 3: `ccccccccc` `c` is at both positions 2 and 9",
+            "line 4: `dd` `d` is at neither position 3 nor 40",
+        ]
+    );
+
+    let combined = All(vec![
+        policy("count").unwrap(),
+        policy("min-length=6").unwrap(),
+        policy("forbid=cde").unwrap(),
+        policy("matches=^[a-e]+$").unwrap(),
+    ]);
+    let violations = audit(&input, &combined);
+    assert_eq!(violations.len(), 3);
+    assert_eq!(
+        violations[0].reason,
+        "is 5 characters long, not at least 6, contains `cde`"
+    );
+    assert_eq!(
+        violations[1].reason,
+        "`b` appears 0 times, not 1 to 3 times, is 5 characters long, not at least 6, \
+         contains `cde`, doesn't match `^[a-e]+$`"
+    );
+    assert!(policy("length=3").is_err());
+}
@@ -1,80 +1,90 @@
-use std::{fmt::Display, io::Write, mem};
+use std::fmt::Display;
 
 use fxhash::{FxHashMap, FxHashSet};
 
-#[aoc(day20, part1)]
-fn part1(input: &str) -> u64 {
-    let tiles = solve(input.split("\n\n").map(Tile::from).collect());
-    corners_product(&tiles)
+use crate::{
+    error::{ParseError, Result, Source},
+    grid::{grid, Cell, Grid},
+    normalize,
+    parser::{blocks, int, line, tag, Parser},
+};
+
+pub struct Input {
+    pub tiles: Vec<Tile>,
 }
 
-#[aoc(day20, part2)]
-fn part2(input: &str) -> usize {
-    let mut picture = Picture::new(solve(input.split("\n\n").map(Tile::from).collect()));
-    picture.hit_test_all(
-        "                  # 
-#    ##    ##    ###
- #  #  #  #  #  #   
-",
-    );
-    picture
-        .pixels
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day20))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(20, input);
+    let tiles = Source::new(20, input).run(blocks(Tile::parser()))?;
+    if tiles
         .iter()
+        .any(|tile| tile.pixels.width() != tiles[0].pixels.width())
+    {
+        return Err(ParseError::input(20, "all tiles have to be the same size"));
+    }
+    Ok(Input { tiles })
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day20, part1))]
+pub fn part1(input: &Input) -> Result<u64> {
+    let tiles = solve(input.tiles.clone())?;
+    corners_product(&tiles)
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day20, part2))]
+pub fn part2(input: &Input) -> Result<usize> {
+    Ok(sea(input)?
+        .cells()
         .filter(|p| matches!(p, Pixel::Black { hit_count: 0 }))
-        .count()
+        .count())
+}
+
+/// The assembled picture, with the number of sea monsters each black pixel is part of.
+pub(crate) fn sea(input: &Input) -> Result<Grid<Pixel>> {
+    let mut picture = Picture::new(solve(input.tiles.clone())?)?;
+    let monster = "                  # 
+#    ##    ##    ###
+ #  #  #  #  #  #   ";
+    picture.hit_test_all(&Source::new(20, monster).run(grid(|char| Some(char == '#')))?);
+    Ok(picture.pixels)
 }
 
 struct Picture {
-    pixels: Vec<Pixel>,
-    edge_len: usize,
+    pixels: Grid<Pixel>,
 }
 
 impl Picture {
-    fn new(initial_tiles: Vec<Tile>) -> Self {
+    fn new(initial_tiles: Vec<Tile>) -> Result<Self> {
         let mut tiles = FxHashMap::<(i32, i32), Tile>::default();
-        for tile in solve(initial_tiles) {
+        for tile in solve(initial_tiles)? {
             tiles.insert(tile.coordinates.unwrap(), tile);
         }
-        let [x_min, x_max, y_min, y_max] = min_max_coordinates(tiles.values());
-        assert_eq!(x_max - x_min, y_max - y_min);
+        let [x_min, x_max, y_min, y_max] = min_max_coordinates(tiles.values())?;
         let tiles_per_edge = (x_max - x_min + 1) as usize;
-        let tile_len = tiles.values().next().unwrap().edges[0].len() - 2;
+        let tile_len = tiles.values().next().unwrap().pixels.width() - 2;
         let edge_len = tiles_per_edge * tile_len;
-        let mut picture = Picture {
-            pixels: vec![Pixel::White; edge_len * edge_len],
-            edge_len,
-        };
+        let mut pixels = Grid::filled(edge_len, edge_len, Pixel::White);
         for y in y_min..=y_max {
             for x in x_min..=x_max {
                 let offset = (
                     (x - x_min) as usize * tile_len,
                     (y - y_min) as usize * tile_len,
                 );
-                let tile = tiles.get(&(x, y)).unwrap();
-                for (tile_y, line) in tile.raw.iter().enumerate() {
-                    if tile_y == 0 || tile_y == tile_len + 1 {
-                        continue;
-                    }
-                    for (tile_x, char) in line.char_indices() {
-                        if tile_x == 0 || tile_x == tile_len + 1 {
-                            continue;
-                        }
-                        if char == '#' {
-                            *picture.get_pixel_mut(offset.0 + tile_x - 1, offset.1 + tile_y - 1) =
-                                Pixel::Black { hit_count: 0 };
-                        }
+                let tile = tiles.get(&(x, y)).ok_or_else(|| {
+                    ParseError::input(20, "the tiles don't form a complete square")
+                })?;
+                // without the borders
+                let inner = tile.pixels.sub_grid(1, 1, tile_len, tile_len);
+                for (tile_x, tile_y) in inner.positions() {
+                    if inner[(tile_x, tile_y)] {
+                        pixels[(offset.0 + tile_x, offset.1 + tile_y)] =
+                            Pixel::Black { hit_count: 0 };
                     }
                 }
             }
         }
-        picture
-    }
-
-    fn get_pixel_mut(&mut self, x: usize, y: usize) -> &mut Pixel {
-        &mut self.pixels[y * self.edge_len + x]
-    }
-    fn get_pixel(&mut self, x: usize, y: usize) -> &Pixel {
-        &self.pixels[y * self.edge_len + x]
+        Ok(Picture { pixels })
     }
 
     fn hit_test(
@@ -83,14 +93,18 @@ impl Picture {
         image_height: usize,
         image_width: usize,
     ) {
-        for y in 0..=(self.edge_len - image_height) {
-            for x in 0..=(self.edge_len - image_width) {
+        let (width, height) = (self.pixels.width(), self.pixels.height());
+        if height < image_height || width < image_width {
+            return;
+        }
+        for y in 0..=(height - image_height) {
+            for x in 0..=(width - image_width) {
                 let matches_all = coordinates.iter().all(|(c_x, c_y)| {
-                    matches!(self.get_pixel(c_x + x, c_y + y), Pixel::Black { .. })
+                    matches!(self.pixels[(c_x + x, c_y + y)], Pixel::Black { .. })
                 });
                 if matches_all {
                     for (c_x, c_y) in coordinates.iter() {
-                        if let Pixel::Black { hit_count } = self.get_pixel_mut(c_x + x, c_y + y) {
+                        if let Pixel::Black { hit_count } = &mut self.pixels[(c_x + x, c_y + y)] {
                             *hit_count += 1;
                         } else {
                             unreachable!()
@@ -101,74 +115,30 @@ impl Picture {
         }
     }
 
-    fn hit_test_all(&mut self, input: &str) {
-        /* for (idx, pixel) in self.pixels.iter().enumerate() {
-            print!(
-                "{}",
-                match pixel {
-                    Pixel::Black { .. } => "X",
-                    _ => " ",
-                }
-            );
-            if idx % self.edge_len == 0 {
-                println!()
-            }
-        }
-        println!(); */
-        std::io::stdout().flush().unwrap();
-        let mut coordinates: Vec<(usize, usize)> = vec![];
-        let mut height = 0;
-        let mut width = 0;
-        for (y, line) in input.lines().enumerate() {
-            for (x, char) in line.char_indices() {
-                if char == '#' {
-                    coordinates.push((x, y));
-                }
-                width = x;
-            }
-            height = y;
-        }
-        height += 1;
-        width += 1;
-        for _ in 0..4 {
-            self.hit_test(&coordinates, height, width);
-            rotate_coordinates(&mut coordinates, &mut height, &mut width);
-        }
-        flip_coordinates(&mut coordinates, &mut height, &mut width);
-        for _ in 0..4 {
-            self.hit_test(&coordinates, height, width);
-            rotate_coordinates(&mut coordinates, &mut height, &mut width);
+    /// Counts for every black pixel how often it is part of `image`, in any orientation.
+    fn hit_test_all(&mut self, image: &Grid<bool>) {
+        for image in image.orientations() {
+            let coordinates: Vec<(usize, usize)> = image
+                .positions()
+                .filter(|&position| image[position])
+                .collect();
+            self.hit_test(&coordinates, image.height(), image.width());
         }
     }
 }
 
-fn rotate_coordinates(
-    coordinates: &mut Vec<(usize, usize)>,
-    height: &mut usize,
-    width: &mut usize,
-) {
-    for (x, y) in coordinates {
-        (*x, *y) = (*height - *y - 1, *x);
-    }
-    mem::swap(height, width);
-}
-fn flip_coordinates(coordinates: &mut Vec<(usize, usize)>, height: &mut usize, width: &mut usize) {
-    for (x, y) in coordinates {
-        (*x, *y) = (*y, *x);
-    }
-    mem::swap(height, width);
-}
-
 #[derive(Clone)]
-enum Pixel {
+pub(crate) enum Pixel {
     White,
     Black { hit_count: u32 },
 }
 
-struct Tile {
-    id: u64,
-    raw: Vec<String>,
-    edges: [String; 4],
+/// A tile of the picture, possibly rotated and flipped into place by `solve`.
+#[derive(Clone)]
+pub struct Tile {
+    pub id: u64,
+    pixels: Grid<bool>,
+    edges: [Vec<bool>; 4],
     coordinates: Option<(i32, i32)>,
 }
 
@@ -176,83 +146,54 @@ impl Tile {
     fn rotate_flip(&mut self, rotation: u8, flip: bool) {
         // flips are around a vertical axis
         if flip {
-            for line in self.raw.iter_mut() {
-                *line = line.chars().rev().collect();
-            }
-            self.edges.swap(1, 3);
-            for edge in self.edges.iter_mut() {
-                *edge = edge.chars().rev().collect();
-            }
+            self.pixels = self.pixels.flip();
         }
-        if rotation != 0 {
-            self.edges.rotate_right(rotation as usize);
-            if rotation == 2 {
-                self.raw = self
-                    .raw
-                    .iter()
-                    .rev()
-                    .map(|line| line.chars().rev().collect())
-                    .collect();
-            } else if rotation == 1 {
-                let mut new_raw = vec![String::new(); self.raw.len()];
-                for line in self.raw.iter().rev() {
-                    for (new_line, ch) in line.chars().enumerate() {
-                        new_raw[new_line].push(ch);
-                    }
-                }
-                self.raw = new_raw;
-            } else {
-                assert_eq!(rotation, 3);
-                let mut new_raw = vec![String::new(); self.raw.len()];
-                for line in self.raw.iter() {
-                    for (new_line, ch) in line.chars().rev().enumerate() {
-                        new_raw[new_line].push(ch);
-                    }
-                }
-                self.raw = new_raw;
-            }
+        for _ in 0..rotation {
+            self.pixels = self.pixels.rotate();
         }
+        self.edges = edges(&self.pixels);
     }
 }
 
-impl From<&str> for Tile {
-    fn from(input: &str) -> Self {
-        let mut lines = input.lines();
-        let header = lines.next().unwrap();
-        let id = &header["Tile ".len()..];
-        let id = id[..id.len() - 1].parse().unwrap();
-        let raw: Vec<String> = lines.map(str::to_string).collect();
-        let edges = [
-            raw.first().unwrap().to_string(),
-            raw.iter()
-                .map(|line| line.chars().last().unwrap())
-                .collect(),
-            raw.last().unwrap().chars().rev().collect(),
-            raw.iter()
-                .rev()
-                .map(|line| line.chars().next().unwrap())
-                .collect(),
-        ];
-        Self {
-            id,
-            raw,
-            edges,
-            coordinates: None,
-        }
+/// The edges clockwise from the top, each read clockwise.
+fn edges(pixels: &Grid<bool>) -> [Vec<bool>; 4] {
+    let last = pixels.width() - 1;
+    [
+        pixels.row(0).to_vec(),
+        pixels.column(last).copied().collect(),
+        pixels.row(last).iter().rev().copied().collect(),
+        pixels.column(0).rev().copied().collect(),
+    ]
+}
+
+impl Tile {
+    fn parser<'a>() -> impl Parser<'a, Self> {
+        let header = line((tag("Tile "), int(), tag(":")));
+        (header, grid(bool::from_char)).try_map(|((_, id, _), pixels)| {
+            if pixels.width() < 3 || pixels.width() != pixels.height() {
+                return Err("tiles have to be square and at least 3 pixels wide");
+            }
+            Ok(Self {
+                id,
+                edges: edges(&pixels),
+                pixels,
+                coordinates: None,
+            })
+        })
     }
 }
 
 impl Display for Tile {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for line in &self.raw {
-            writeln!(f, "{}", line)?;
-        }
-        Ok(())
+        write!(f, "{}", self.pixels)
     }
 }
 
-fn solve(mut initial_tiles: Vec<Tile>) -> Vec<Tile> {
-    let mut all_edges = FxHashMap::<String, (u64, u8, bool)>::default();
+fn solve(mut initial_tiles: Vec<Tile>) -> Result<Vec<Tile>> {
+    if initial_tiles.is_empty() {
+        return Err(ParseError::input(20, "no tiles"));
+    }
+    let mut all_edges = FxHashMap::<Vec<bool>, (u64, u8, bool)>::default();
     let mut tiles = FxHashMap::<u64, Tile>::default();
     let mut debug_coordinates = FxHashSet::<(i32, i32)>::default();
     debug_coordinates.insert((0, 0));
@@ -299,7 +240,13 @@ fn solve(mut initial_tiles: Vec<Tile>) -> Vec<Tile> {
                 );
                 tile
             } else if !tiles.is_empty() {
-                continue;
+                return Err(ParseError::input(
+                    20,
+                    format!(
+                        "tile {} doesn't fit to any of the other tiles",
+                        initial_tiles[0].id
+                    ),
+                ));
             } else {
                 let mut tile = initial_tiles.remove(initial_tiles.len() - 1);
                 tile.coordinates = Some((0, 0));
@@ -310,23 +257,26 @@ fn solve(mut initial_tiles: Vec<Tile>) -> Vec<Tile> {
             tile.edges
                 .iter()
                 .enumerate()
-                .map(|(rotation, edge)| (edge.to_owned(), (tile.id, rotation as u8, false))),
+                .map(|(rotation, edge)| (edge.clone(), (tile.id, rotation as u8, false))),
         );
         all_edges.extend(tile.edges.iter().enumerate().map(|(rotation, edge)| {
             (
-                edge.chars().rev().collect(),
+                edge.iter().rev().copied().collect(),
                 (tile.id, rotation as u8, true),
             )
         }));
-        assert!(tiles.insert(tile.id, tile).is_none());
+        let id = tile.id;
+        if tiles.insert(id, tile).is_some() {
+            return Err(ParseError::input(20, format!("duplicate tile {}", id)));
+        }
         if initial_tiles.is_empty() {
             break;
         }
     }
-    tiles.into_values().collect()
+    Ok(tiles.into_values().collect())
 }
 
-fn min_max_coordinates<'a, T>(tiles: T) -> [i32; 4]
+fn min_max_coordinates<'a, T>(tiles: T) -> Result<[i32; 4]>
 where
     T: Iterator<Item = &'a Tile>,
 {
@@ -349,12 +299,14 @@ where
             y_max = coordinates.1;
         }
     }
-    assert_eq!(x_max - x_min, y_max - y_min);
-    [x_min, x_max, y_min, y_max]
+    if x_max - x_min != y_max - y_min {
+        return Err(ParseError::input(20, "the tiles don't form a square"));
+    }
+    Ok([x_min, x_max, y_min, y_max])
 }
 
-fn corners_product(tiles: &[Tile]) -> u64 {
-    let [x_min, x_max, y_min, y_max] = min_max_coordinates(tiles.iter());
+fn corners_product(tiles: &[Tile]) -> Result<u64> {
+    let [x_min, x_max, y_min, y_max] = min_max_coordinates(tiles.iter())?;
     let corners: Vec<u64> = tiles
         .iter()
         .filter(|tile| {
@@ -371,8 +323,13 @@ fn corners_product(tiles: &[Tile]) -> u64 {
         })
         .map(|tile| tile.id)
         .collect();
-    assert_eq!(corners.len(), 4);
-    corners.iter().product()
+    if corners.len() != 4 {
+        return Err(ParseError::input(
+            20,
+            "the tiles don't form a complete square",
+        ));
+    }
+    Ok(corners.iter().product())
 }
 
 #[test]
@@ -484,17 +441,18 @@ Tile 3079:
 ..#.###...
 ..#.......
 ..#.###...";
-    assert_eq!(part1(input), 20899048083289);
-    assert_eq!(part2(input), 273);
+    let input = parse(input).unwrap();
+    assert_eq!(part1(&input), Ok(20899048083289));
+    assert_eq!(part2(&input), Ok(273));
 }
 
 #[test]
 fn real1() {
     let input = std::fs::read_to_string("input/2020/day20.txt").unwrap();
-    assert_eq!(part1(input.trim()), 45079100979683);
+    assert_eq!(part1(&parse(&input).unwrap()), Ok(45079100979683));
 }
 #[test]
 fn real2() {
     let input = std::fs::read_to_string("input/2020/day20.txt").unwrap();
-    assert_eq!(part2(input.trim()), 1946);
+    assert_eq!(part2(&parse(&input).unwrap()), Ok(1946));
 }
@@ -1,30 +1,53 @@
 use fxhash::{FxHashMap, FxHashSet};
 
-#[aoc(day21, part1)]
-fn part1(input: &str) -> u64 {
-    let menu = parse(input);
-    let ingredients_to_allergens = solve(&menu);
+use crate::{
+    error::{ParseError, Result, Source},
+    normalize,
+    parser::{lines, optional, separated, tag, take_while1, Parser},
+    trace::trace,
+};
+
+pub struct Input {
+    pub foods: Vec<Food>,
+}
+
+/// A line of the menu: the ingredients of a food and some of the allergens it contains.
+pub struct Food {
+    pub ingredients: Vec<String>,
+    pub allergens: Vec<String>,
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day21))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(21, input);
+    Ok(Input {
+        foods: Source::new(21, input).run(lines(food()))?,
+    })
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day21, part1))]
+pub fn part1(input: &Input) -> Result<u64> {
+    let ingredients_to_allergens = solve(&input.foods)?;
     let mut count = 0;
-    for (ingredients, _allergens) in menu {
-        for ingredient in ingredients {
-            if !ingredients_to_allergens.contains_key(ingredient) {
+    for food in &input.foods {
+        for ingredient in &food.ingredients {
+            if !ingredients_to_allergens.contains_key(ingredient.as_str()) {
                 count += 1;
             }
         }
     }
-    count
+    Ok(count)
 }
-#[aoc(day21, part2)]
-fn part2(input: &str) -> String {
-    let menu = parse(input);
-    let ingredients_to_allergens = solve(&menu);
+#[cfg_attr(feature = "aoc-runner", aoc(day21, part2))]
+pub fn part2(input: &Input) -> Result<String> {
+    let ingredients_to_allergens = solve(&input.foods)?;
     let mut ingredients: Vec<_> = ingredients_to_allergens.into_iter().collect();
     ingredients.sort_by_key(|(_ingredient, allergen)| *allergen);
-    ingredients
+    Ok(ingredients
         .into_iter()
         .map(|(ingredient, _allergen)| ingredient)
         .intersperse(",")
-        .collect()
+        .collect())
 }
 
 struct Puzzle<'a> {
@@ -48,6 +71,7 @@ impl<'a> Puzzle<'a> {
             }
         }
         for (allergen, ingredient) in solved_allergens {
+            trace!(21, "deduce", { "allergen": allergen, "ingredient": ingredient });
             self.solved_allergen(allergen, ingredient);
         }
     }
@@ -65,61 +89,74 @@ impl<'a> Puzzle<'a> {
                 }
             }
         }
-        for (solved_allergen, source) in solved_allergens {
-            self.solved_allergen(solved_allergen, source);
+        for (allergen, ingredient) in solved_allergens {
+            // the other candidates were ruled out by `solved_allergen`
+            trace!(21, "deduce", {
+                "allergen": allergen,
+                "ingredient": ingredient,
+                "after": solved_allergen,
+            });
+            self.solved_allergen(allergen, ingredient);
         }
     }
 }
 
-fn solve<'a>(input: &[(Vec<&'a str>, Vec<&'a str>)]) -> FxHashMap<&'a str, &'a str> {
+fn solve(foods: &[Food]) -> Result<FxHashMap<&str, &str>> {
     let mut allergens_to_ingredients = FxHashMap::<&str, Vec<&str>>::default();
 
     let mut line_ingredients = FxHashSet::default();
-    for line in input {
+    for food in foods {
         line_ingredients.clear();
-        for i in &line.0 {
-            line_ingredients.insert(i);
+        for i in &food.ingredients {
+            line_ingredients.insert(i.as_str());
         }
-        for allergen in &line.1 {
-            if let Some(ingredients) = allergens_to_ingredients.get_mut(allergen) {
+        for allergen in &food.allergens {
+            if let Some(ingredients) = allergens_to_ingredients.get_mut(allergen.as_str()) {
                 ingredients.retain(|ingredient| line_ingredients.contains(ingredient));
             } else {
-                allergens_to_ingredients.insert(allergen, line.0.clone());
+                allergens_to_ingredients.insert(
+                    allergen,
+                    food.ingredients.iter().map(String::as_str).collect(),
+                );
             }
         }
     }
 
+    if let Some((allergen, _)) = allergens_to_ingredients
+        .iter()
+        .find(|(_, ingredients)| ingredients.is_empty())
+    {
+        return Err(ParseError::input(
+            21,
+            format!("no ingredient can contain {}", allergen),
+        ));
+    }
+
     let mut puzzle = Puzzle::new(allergens_to_ingredients);
     puzzle.solve();
 
     let mut result = FxHashMap::default();
     for allergen in puzzle.allergens_to_ingredients {
-        assert_eq!(allergen.1.len(), 1);
+        if allergen.1.len() != 1 {
+            return Err(ParseError::input(
+                21,
+                format!("the ingredient containing {} is ambiguous", allergen.0),
+            ));
+        }
         result.insert(allergen.1[0], allergen.0);
     }
-    result
-}
-
-fn parse(input: &str) -> Vec<(Vec<&str>, Vec<&str>)> {
-    input.lines().map(parse_line).collect()
+    Ok(result)
 }
 
 #[inline]
-fn parse_line(line: &str) -> (Vec<&str>, Vec<&str>) {
-    let mut ingredients = Vec::with_capacity(100);
-    let mut allergens = Vec::with_capacity(5);
-    let mut tokens = line.split(' ');
-    while let Some(token) = tokens.next() {
-        if token.starts_with('(') {
-            break;
-        }
-        ingredients.push(token);
-    }
-    for token in tokens {
-        let token = token.trim_end_matches(&[')', ','][..]);
-        allergens.push(token);
-    }
-    (ingredients, allergens)
+fn food<'a>() -> impl Parser<'a, Food> {
+    let name = || take_while1("a word", char::is_alphanumeric).map(str::to_string);
+    let allergens = (tag(" (contains "), separated(name(), tag(", ")), tag(")"))
+        .map(|(_, allergens, _)| allergens);
+    (separated(name(), tag(" ")), optional(allergens)).map(|(ingredients, allergens)| Food {
+        ingredients,
+        allergens: allergens.unwrap_or_default(),
+    })
 }
 
 #[test]
@@ -128,16 +165,18 @@ fn e1() {
 trh fvjkl sbzzf mxmxvkd (contains dairy)
 sqjhc fvjkl (contains soy)
 sqjhc mxmxvkd sbzzf (contains fish)";
-    assert_eq!(part1(input), 5);
-    assert_eq!(part2(input), "mxmxvkd,sqjhc,fvjkl");
+    let input = parse(input).unwrap();
+    assert_eq!(part1(&input), Ok(5));
+    assert_eq!(part2(&input), Ok("mxmxvkd,sqjhc,fvjkl".to_string()));
 }
 #[test]
 fn real() {
     let input = std::fs::read_to_string("input/2020/day21.txt").unwrap();
+    let input = parse(&input).unwrap();
 
-    assert_eq!(part1(&input), 2786);
+    assert_eq!(part1(&input), Ok(2786));
     assert_eq!(
         part2(&input),
-        "prxmdlz,ncjv,knprxg,lxjtns,vzzz,clg,cxfz,qdfpq"
+        Ok("prxmdlz,ncjv,knprxg,lxjtns,vzzz,clg,cxfz,qdfpq".to_string())
     );
 }
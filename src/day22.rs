@@ -2,63 +2,152 @@ use std::collections::VecDeque;
 
 use fxhash::FxHashSet;
 
-#[aoc(day22, part1)]
-fn part1(input: &str) -> usize {
-    let mut decks = parse(input);
-    let winning_deck = loop {
-        step(&mut decks);
-        if decks[0].is_empty() {
-            break &decks[1];
-        }
-        if decks[1].is_empty() {
-            break &decks[0];
+use crate::{
+    checkpoint::{Checkpoints, Decoder, Encoder, State},
+    error::{Result, Source},
+    normalize,
+    parser::{block, int, line, lines, preceded, spanned, tag},
+    trace::trace,
+};
+
+pub struct Input {
+    pub decks: [VecDeque<usize>; 2],
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day22))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(22, input);
+    let src = Source::new(22, input);
+    let deck = |player| block(preceded(line(tag(player)), lines(spanned(int()))));
+    let (first, second) = src.run((deck("Player 1:"), deck("Player 2:")))?;
+    let mut cards = FxHashSet::default();
+    for &(text, card) in first.iter().chain(&second) {
+        // the rules don't say what happens on a draw, so every card has to be unique
+        if !cards.insert(card) {
+            return Err(src.error(text, format!("card {} is in the decks twice", card)));
         }
-    };
-    winning_deck
-        .iter()
-        .rev()
-        .enumerate()
-        .map(|(idx, val)| val * (idx as usize + 1))
-        .sum()
+    }
+    let decks = [first, second].map(|deck| deck.into_iter().map(|(_, card)| card).collect());
+    if decks.iter().any(VecDeque::is_empty) {
+        return Err(src.error_input("both players need at least one card"));
+    }
+    Ok(Input { decks })
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day22, part1))]
+pub fn part1(input: &Input) -> Result<usize> {
+    Combat::new(input).play(None)
 }
 
-#[aoc(day22, part2)]
-fn part2(input: &str) -> usize {
-    let decks = parse(input);
-    let mut game = Game2::new(decks.clone());
-    let winning_deck = match game.play() {
-        Player::One => &game.current_positions[0],
-        Player::Two => &game.current_positions[1],
+#[cfg_attr(feature = "aoc-runner", aoc(day22, part2))]
+pub fn part2(input: &Input) -> Result<usize> {
+    RecursiveCombat::new(input).play(None)
+}
+
+/// Solves `part`, continuing the game in the checkpoint if there is one.
+pub fn resume(input: &Input, part: u8, checkpoints: &mut Checkpoints) -> Result<usize> {
+    let answer = if part == 1 {
+        let game = checkpoints.load()?;
+        game.unwrap_or_else(|| Combat::new(input))
+            .play(Some(checkpoints))?
+    } else {
+        let game = checkpoints.load()?;
+        game.unwrap_or_else(|| RecursiveCombat::new(input))
+            .play(Some(checkpoints))?
     };
-    winning_deck
-        .iter()
+    checkpoints.finish()?;
+    Ok(answer)
+}
+
+fn score(deck: &VecDeque<usize>) -> usize {
+    deck.iter()
         .rev()
         .enumerate()
-        .map(|(idx, val)| val * (idx as usize + 1))
+        .map(|(idx, val)| val * (idx + 1))
         .sum()
 }
 
-fn parse(input: &str) -> [VecDeque<usize>; 2] {
-    let mut decks = [VecDeque::new(), VecDeque::new()];
-    let mut deck = 0;
-    for line in input.lines() {
-        if line.is_empty() {
-            deck += 1;
-            continue;
+/// A game of Combat after some rounds.
+struct Combat {
+    decks: [VecDeque<usize>; 2],
+    round: usize,
+}
+
+impl Combat {
+    fn new(input: &Input) -> Self {
+        Self {
+            decks: input.decks.clone(),
+            round: 0,
+        }
+    }
+
+    /// The score of the winner.
+    fn play(mut self, mut checkpoints: Option<&mut Checkpoints>) -> Result<usize> {
+        loop {
+            if let Some(checkpoints) = checkpoints.as_deref_mut() {
+                checkpoints.save_if_due(&self)?;
+            }
+            self.round += 1;
+            step(&mut self.decks, self.round);
+            if self.decks[0].is_empty() {
+                return Ok(score(&self.decks[1]));
+            }
+            if self.decks[1].is_empty() {
+                return Ok(score(&self.decks[0]));
+            }
+        }
+    }
+}
+
+impl State for Combat {
+    fn encode(&self, out: &mut Encoder) {
+        out.usize(self.round);
+        encode_decks(out, &self.decks);
+    }
+
+    fn decode(input: &mut Decoder) -> Result<Self> {
+        let round = input.usize()?;
+        let decks = decode_decks(input)?;
+        // the game would be over with an empty deck
+        if decks.iter().any(VecDeque::is_empty) || !unique(decks.iter().flatten()) {
+            return Err(input.corrupt());
         }
-        if line.starts_with('P') {
-            // Player n:
-            assert!(decks[deck].is_empty());
-            continue;
+        Ok(Self { round, decks })
+    }
+}
+
+fn encode_decks(out: &mut Encoder, decks: &[VecDeque<usize>; 2]) {
+    for deck in decks {
+        out.usize(deck.len());
+        for &card in deck {
+            out.usize(card);
         }
-        decks[deck].push_back(line.parse().unwrap());
     }
-    decks
 }
 
-fn step(decks: &mut [VecDeque<usize>; 2]) {
+fn decode_decks(input: &mut Decoder) -> Result<[VecDeque<usize>; 2]> {
+    let mut deck = || {
+        let len = input.len(8)?;
+        (0..len).map(|_| input.usize()).collect::<Result<_>>()
+    };
+    Ok([deck()?, deck()?])
+}
+
+/// Whether no card is there twice, which the rules don't allow.
+fn unique<'a>(cards: impl IntoIterator<Item = &'a usize>) -> bool {
+    let mut seen = FxHashSet::default();
+    cards.into_iter().all(|&card| seen.insert(card))
+}
+
+fn step(decks: &mut [VecDeque<usize>; 2], round: usize) {
     let first = decks[0].pop_front().unwrap();
     let second = decks[1].pop_front().unwrap();
+    trace!(22, "round", {
+        "game": 1,
+        "round": round,
+        "cards": [first, second],
+        "winner": if first > second { 1 } else { 2 },
+    });
     if first > second {
         decks[0].push_back(first);
         decks[0].push_back(second);
@@ -72,38 +161,185 @@ fn step(decks: &mut [VecDeque<usize>; 2]) {
 
 type RoundState = [VecDeque<usize>; 2];
 
+#[derive(Clone, Copy)]
 enum Player {
     One,
     Two,
 }
 
+impl Player {
+    fn number(self) -> u8 {
+        match self {
+            Player::One => 1,
+            Player::Two => 2,
+        }
+    }
+}
+
+/// A game of Recursive Combat with the sub-games that are being played, innermost last.
+struct RecursiveCombat {
+    games: Vec<Game2>,
+    /// The number of games started so far.
+    started: usize,
+}
+
+impl RecursiveCombat {
+    fn new(input: &Input) -> Self {
+        Self {
+            games: vec![Game2::new(1, input.decks.clone())],
+            started: 1,
+        }
+    }
+
+    /// The score of the winner of the first game.
+    fn play(mut self, mut checkpoints: Option<&mut Checkpoints>) -> Result<usize> {
+        for step in 0usize.. {
+            if step.is_multiple_of(1024) {
+                if let Some(checkpoints) = checkpoints.as_deref_mut() {
+                    checkpoints.save_if_due(&self)?;
+                }
+            }
+            if let Some(winner) = self.step() {
+                let game = &self.games[0];
+                return Ok(match winner {
+                    Player::One => score(&game.current_positions[0]),
+                    Player::Two => score(&game.current_positions[1]),
+                });
+            }
+        }
+        unreachable!()
+    }
+
+    /// Plays a round of the innermost game and returns the winner of the first game once
+    /// it is over.
+    fn step(&mut self) -> Option<Player> {
+        let game = self.games.last_mut().unwrap();
+        let mut winner = match game.draw() {
+            Draw::Over(winner) => Some(winner),
+            Draw::SubGame(decks) => {
+                self.started += 1;
+                trace!(22, "sub_game", {
+                    "game": self.started,
+                    "parent": game.id,
+                    "cards": game.drawn,
+                });
+                self.games.push(Game2::new(self.started, decks));
+                return None;
+            }
+            Draw::Round(winner) => game.finish_round(winner),
+        };
+        // a sub-game decides the round of its parent, which might end the parent too
+        while let Some(game_winner) = winner {
+            let game = self.games.last().unwrap();
+            trace!(22, "game_over", { "game": game.id, "winner": game_winner.number() });
+            if self.games.len() == 1 {
+                return Some(game_winner);
+            }
+            self.games.pop();
+            winner = self.games.last_mut().unwrap().finish_round(game_winner);
+        }
+        None
+    }
+}
+
+impl State for RecursiveCombat {
+    fn encode(&self, out: &mut Encoder) {
+        out.usize(self.started);
+        out.usize(self.games.len());
+        for game in &self.games {
+            out.usize(game.id);
+            out.usize(game.round);
+            let [first, second] = game.drawn.unwrap_or([0, 0]);
+            out.usize(first);
+            out.usize(second);
+            encode_decks(out, &game.current_positions);
+            out.usize(game.encountered_positions.len());
+            for decks in &game.encountered_positions {
+                encode_decks(out, decks);
+            }
+        }
+    }
+
+    fn decode(input: &mut Decoder) -> Result<Self> {
+        let started = input.usize()?;
+        let len = input.len(64)?;
+        let mut games = Vec::with_capacity(len);
+        for _ in 0..len {
+            let mut game = Game2::new(input.usize()?, Default::default());
+            game.round = input.usize()?;
+            // no card is 0, so that means no cards were drawn
+            game.drawn = match [input.usize()?, input.usize()?] {
+                [0, 0] => None,
+                cards => Some(cards),
+            };
+            game.current_positions = decode_decks(input)?;
+            let len = input.len(16)?;
+            for _ in 0..len {
+                game.encountered_positions.insert(decode_decks(input)?);
+            }
+            games.push(game);
+        }
+        // every game but the innermost waits for a sub-game to decide its round
+        let valid = !games.is_empty()
+            && games.iter().enumerate().all(|(idx, game)| {
+                let innermost = idx == games.len() - 1;
+                let decks = &game.current_positions;
+                game.drawn.is_none() == innermost
+                    && !(innermost && decks.iter().any(VecDeque::is_empty))
+                    && unique(decks.iter().flatten().chain(game.drawn.iter().flatten()))
+            });
+        if !valid {
+            return Err(input.corrupt());
+        }
+        Ok(Self { games, started })
+    }
+}
+
+/// What happens after the players drew their cards.
+enum Draw {
+    /// The game is over because it repeated a previous round.
+    Over(Player),
+    /// The winner of the round is the winner of a sub-game with these decks.
+    SubGame(RoundState),
+    Round(Player),
+}
+
 struct Game2 {
+    /// Games are numbered in the order they start, beginning with 1.
+    id: usize,
+    round: usize,
     encountered_positions: FxHashSet<RoundState>,
     current_positions: RoundState,
+    /// The cards of the round that is being played.
+    drawn: Option<[usize; 2]>,
 }
 
 impl Game2 {
-    fn new(current_positions: RoundState) -> Self {
+    fn new(id: usize, current_positions: RoundState) -> Self {
         Self {
+            id,
+            round: 0,
             current_positions,
             encountered_positions: FxHashSet::default(),
+            drawn: None,
         }
     }
 
-    fn step(&mut self) -> Option<Player> {
+    fn draw(&mut self) -> Draw {
         if self.encountered_positions.contains(&self.current_positions) {
-            return Some(Player::One);
+            trace!(22, "repeat", { "game": self.id, "round": self.round });
+            return Draw::Over(Player::One);
         }
+        self.round += 1;
         self.encountered_positions
             .insert(self.current_positions.clone());
 
         let first = self.current_positions[0].pop_front().unwrap();
         let second = self.current_positions[1].pop_front().unwrap();
+        self.drawn = Some([first, second]);
 
-        let winner = if self.current_positions[0].len() >= first
-            && self.current_positions[1].len() >= second
-        {
-            Game2::new([
+        if self.current_positions[0].len() >= first && self.current_positions[1].len() >= second {
+            Draw::SubGame([
                 self.current_positions[0]
                     .iter()
                     .take(first)
@@ -115,13 +351,23 @@ impl Game2 {
                     .copied()
                     .collect(),
             ])
-            .play()
         } else if first > second {
-            Player::One
+            Draw::Round(Player::One)
         } else {
             assert_ne!(first, second);
-            Player::Two
-        };
+            Draw::Round(Player::Two)
+        }
+    }
+
+    /// Gives the drawn cards to `winner` and returns the winner of the game if it's over.
+    fn finish_round(&mut self, winner: Player) -> Option<Player> {
+        let [first, second] = self.drawn.take().unwrap();
+        trace!(22, "round", {
+            "game": self.id,
+            "round": self.round,
+            "cards": [first, second],
+            "winner": winner.number(),
+        });
 
         match winner {
             Player::One => {
@@ -142,14 +388,6 @@ impl Game2 {
 
         None
     }
-
-    fn play(&mut self) -> Player {
-        loop {
-            if let Some(winner) = self.step() {
-                return winner;
-            }
-        }
-    }
 }
 
 #[test]
@@ -168,7 +406,7 @@ Player 2:
 7
 10
 ";
-    assert_eq!(part1(input), 306);
+    assert_eq!(part1(&parse(input).unwrap()), Ok(306));
 }
 
 #[test]
@@ -187,7 +425,7 @@ Player 2:
 7
 10
 ";
-    assert_eq!(part2(input), 291);
+    assert_eq!(part2(&parse(input).unwrap()), Ok(291));
 }
 
 #[test]
@@ -201,5 +439,58 @@ Player 2:
 29
 14
 ";
-    assert_eq!(part2(input), 105);
+    assert_eq!(part2(&parse(input).unwrap()), Ok(105));
+}
+
+#[test]
+fn resume_from_checkpoint() {
+    use std::{env, process, time::Duration};
+
+    let text = "Player 1:\n9\n2\n6\n3\n1\n\nPlayer 2:\n5\n8\n4\n7\n10";
+    let input = parse(text).unwrap();
+    let path = env::temp_dir().join(format!("aoc2020-day22-{}.checkpoint", process::id()));
+    let mut checkpoints = Checkpoints::new(&path, 22, 2, Duration::ZERO);
+    checkpoints.set_input(text);
+    let mut game = RecursiveCombat::new(&input);
+    for _ in 0..10 {
+        assert!(game.step().is_none());
+    }
+    // in the middle of the first sub-game
+    assert_eq!(game.games.len(), 2);
+    checkpoints.save(&game).unwrap();
+    let other_part = Checkpoints::new(&path, 22, 1, Duration::ZERO);
+    assert!(other_part.load::<Combat>().is_err());
+    // another input must not continue from this checkpoint
+    let mut other_input = Checkpoints::new(&path, 22, 2, Duration::ZERO);
+    let resumed = crate::checkpoint::find(22).unwrap().run(
+        "Player 1:\n1\n2\n6\n3\n9\n\nPlayer 2:\n5\n8\n4\n7\n10",
+        2,
+        &mut other_input,
+    );
+    assert_eq!(
+        resumed.unwrap_err().message,
+        format!("{} is a checkpoint of a different input", path.display())
+    );
+    assert_eq!(resume(&input, 2, &mut checkpoints), Ok(291));
+    assert!(!path.exists());
+
+    // states that can't come from a game
+    let mut checkpoints = Checkpoints::new(&path, 22, 1, Duration::ZERO);
+    let mut game = Combat::new(&input);
+    game.decks[0].clear();
+    checkpoints.save(&game).unwrap();
+    assert!(checkpoints.load::<Combat>().is_err());
+    let mut checkpoints = Checkpoints::new(&path, 22, 2, Duration::ZERO);
+    let mut game = RecursiveCombat::new(&input);
+    game.games[0].current_positions[1][0] = 9;
+    checkpoints.save(&game).unwrap();
+    assert_eq!(
+        checkpoints.load::<RecursiveCombat>().err().unwrap().message,
+        "the checkpoint is corrupt"
+    );
+    let mut game = RecursiveCombat::new(&input);
+    game.games[0].drawn = Some([11, 12]);
+    checkpoints.save(&game).unwrap();
+    assert!(checkpoints.load::<RecursiveCombat>().is_err());
+    checkpoints.finish().unwrap();
 }
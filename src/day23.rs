@@ -0,0 +1,145 @@
+use crate::{
+    error::{Result, Source},
+    normalize,
+    parser::{any_char, line, repeat, spanned, Parser},
+};
+
+pub struct Input {
+    /// The cup labels in clockwise order, starting with the current cup.
+    pub cups: Vec<u32>,
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day23))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(23, input);
+    let src = Source::new(23, input);
+    let label = any_char().try_map(|char| match char.to_digit(10) {
+        Some(label) if label > 0 => Ok(label),
+        _ => Err("expected a cup label from 1 to 9"),
+    });
+    let (labels, cups) = src.run(line(spanned(repeat(label))))?;
+    let mut sorted = cups.clone();
+    sorted.sort_unstable();
+    if sorted.is_empty()
+        || sorted
+            .iter()
+            .zip(1..)
+            .any(|(&label, expected)| label != expected)
+    {
+        return Err(src.error(
+            labels,
+            "the cups have to be labeled 1 to n, each label used once",
+        ));
+    }
+    if cups.len() < 4 {
+        // the current cup, the three picked up cups and a destination
+        return Err(src.error(labels, "there have to be at least 4 cups"));
+    }
+    Ok(Input { cups })
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day23, part1))]
+pub fn part1(input: &Input) -> Result<String> {
+    let cups = &input.cups;
+    let mut next = successors(cups, cups.len());
+    play(&mut next, cups[0], 100);
+    Ok(labels_after_one(&next))
+}
+#[cfg_attr(feature = "aoc-runner", aoc(day23, part2))]
+pub fn part2(input: &Input) -> Result<u64> {
+    let cups = &input.cups;
+    let mut next = successors(cups, 1_000_000);
+    play(&mut next, cups[0], 10_000_000);
+    let first = next[1];
+    let second = next[first as usize];
+    Ok(first as u64 * second as u64)
+}
+
+/// Builds the circle as a flat linked list: `next[label]` is the label of the cup
+/// clockwise of `label`. Index 0 is unused. Cups after the ones from the input are
+/// labeled in ascending order up to `total`.
+fn successors(cups: &[u32], total: usize) -> Vec<u32> {
+    let mut next = vec![0; total + 1];
+    let mut labels = cups
+        .iter()
+        .copied()
+        .chain((cups.len() as u32 + 1)..=total as u32);
+    let first = labels.next().unwrap();
+    let mut previous = first;
+    for label in labels {
+        next[previous as usize] = label;
+        previous = label;
+    }
+    next[previous as usize] = first;
+    next
+}
+
+fn play(next: &mut [u32], mut current: u32, moves: usize) {
+    let max = (next.len() - 1) as u32;
+    for _ in 0..moves {
+        let a = next[current as usize];
+        let b = next[a as usize];
+        let c = next[b as usize];
+        next[current as usize] = next[c as usize];
+
+        let mut destination = current;
+        loop {
+            destination = if destination == 1 {
+                max
+            } else {
+                destination - 1
+            };
+            if destination != a && destination != b && destination != c {
+                break;
+            }
+        }
+        next[c as usize] = next[destination as usize];
+        next[destination as usize] = a;
+
+        current = next[current as usize];
+    }
+}
+
+fn labels_after_one(next: &[u32]) -> String {
+    let mut labels = String::with_capacity(next.len());
+    let mut cup = next[1];
+    while cup != 1 {
+        labels.push(char::from_digit(cup, 10).unwrap());
+        cup = next[cup as usize];
+    }
+    labels
+}
+
+#[test]
+fn e1() {
+    let input = parse("389125467").unwrap();
+    let mut next = successors(&input.cups, input.cups.len());
+    play(&mut next, input.cups[0], 10);
+    assert_eq!(labels_after_one(&next), "92658374");
+    assert_eq!(part1(&input), Ok("67384529".to_string()));
+}
+#[test]
+fn e2() {
+    assert_eq!(part2(&parse("389125467").unwrap()), Ok(149245887792));
+}
+
+#[test]
+fn too_few_cups() {
+    assert_eq!(
+        parse("132").err().map(|e| e.to_string()),
+        Some("day 23, line 1, column 1: there have to be at least 4 cups".to_string())
+    );
+    assert!(parse("1").is_err());
+    assert!(parse("2143").is_ok());
+}
+
+#[test]
+fn real1() {
+    let input = std::fs::read_to_string("input/2020/day23.txt").unwrap();
+    assert_eq!(part1(&parse(&input).unwrap()), Ok("89573246".to_string()));
+}
+#[test]
+fn real2() {
+    let input = std::fs::read_to_string("input/2020/day23.txt").unwrap();
+    assert_eq!(part2(&parse(&input).unwrap()), Ok(2029056128));
+}
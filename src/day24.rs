@@ -0,0 +1,103 @@
+use fxhash::FxHashSet;
+
+use crate::{
+    automaton::{Coordinates, Hex, Space},
+    error::{Result, Source},
+    normalize,
+    parser::{lines, one_of, repeat, Parser},
+};
+
+/// The tile each line of the input ends up on, in axial coordinates.
+pub struct Input {
+    pub tiles: Vec<Coordinates<2>>,
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day24))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(24, input);
+    Ok(Input {
+        tiles: Source::new(24, input).run(lines(walk()))?,
+    })
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day24, part1))]
+pub fn part1(input: &Input) -> Result<usize> {
+    Ok(flipped_tiles(input).cells.len())
+}
+#[cfg_attr(feature = "aoc-runner", aoc(day24, part2))]
+pub fn part2(input: &Input) -> Result<usize> {
+    let mut floor = flipped_tiles(input);
+    for _ in 0..100 {
+        floor = floor.step(flip_rule);
+    }
+    Ok(floor.cells.len())
+}
+
+fn flip_rule(black: bool, black_neighbors: usize) -> bool {
+    if black {
+        // black tiles with zero or more than 2 black neighbors are flipped to white
+        black_neighbors == 1 || black_neighbors == 2
+    } else {
+        // white tiles with exactly 2 black neighbors are flipped to black
+        black_neighbors == 2
+    }
+}
+
+fn flipped_tiles(input: &Input) -> Space<Hex> {
+    let mut black: FxHashSet<Coordinates<2>> = FxHashSet::default();
+    for &tile in &input.tiles {
+        if !black.insert(tile) {
+            black.remove(&tile);
+        }
+    }
+    Space::new(black)
+}
+
+/// The tile at the end of a walk from the reference tile.
+fn walk<'a>() -> impl Parser<'a, Coordinates<2>> {
+    static DIRECTIONS: &[(&str, Coordinates<2>)] = &[
+        ("e", Hex::EAST),
+        ("se", Hex::SOUTH_EAST),
+        ("sw", Hex::SOUTH_WEST),
+        ("w", Hex::WEST),
+        ("nw", Hex::NORTH_WEST),
+        ("ne", Hex::NORTH_EAST),
+    ];
+    repeat(one_of(DIRECTIONS)).map(|steps| {
+        steps
+            .iter()
+            .fold([0, 0], |[q, r], [dq, dr]| [q + dq, r + dr])
+    })
+}
+
+#[test]
+fn e0() {
+    assert_eq!(Source::new(24, "nwwswee").run(walk()), Ok([0, 0]));
+    assert_eq!(Source::new(24, "esew").run(walk()), Ok(Hex::SOUTH_EAST));
+}
+#[test]
+fn e1() {
+    let input = "sesenwnenenewseeswwswswwnenewsewsw
+neeenesenwnwwswnenewnwwsewnenwseswesw
+seswneswswsenwwnwse
+nwnwneseeswswnenewneswwnewseswneseene
+swweswneswnenwsewnwneneseenw
+eesenwseswswnenwswnwnwsewwnwsene
+sewnenenenesenwsewnenwwwse
+wenwwweseeeweswwwnwwe
+wsweesenenewnwwnwsenewsenwwsesesenwne
+neeswseenwwswnwswswnw
+nenwswwsewswnenenewsenwsenwnesesenew
+enewnwewneswsewnwswenweswnenwsenwsw
+sweneswneswneneenwnewenewwneswswnese
+swwesenesewenwneswnwwneseswwne
+enesenwswwswneneswsenwnewswseenwsese
+wnwnesenesenenwwnenwsewesewsesesew
+nenewswnwewswnenesenwnesewesw
+eneswnwswnwsenenwnwnwwseeswneewsenese
+neswnwewnwnwseenwseesewsenwsweewe
+wseweeenwnesenwwwswnew";
+    let input = parse(input).unwrap();
+    assert_eq!(part1(&input), Ok(10));
+    assert_eq!(part2(&input), Ok(2208));
+}
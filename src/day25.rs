@@ -0,0 +1,59 @@
+use crate::{
+    error::{ParseError, Result, Source},
+    normalize,
+    number_theory::{discrete_log, modpow},
+    parser::{int, line, Parser},
+};
+
+const MODULUS: u64 = 20201227;
+const SUBJECT_NUMBER: u64 = 7;
+
+pub struct Input {
+    pub card_public_key: u64,
+    pub door_public_key: u64,
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day25))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(25, input);
+    // the keys are remainders, anything bigger would have to be reduced first
+    let key = || {
+        int().try_map(|key| match key {
+            MODULUS.. => Err("public keys have to be smaller than 20201227"),
+            key => Ok(key),
+        })
+    };
+    let (card_public_key, door_public_key) =
+        Source::new(25, input).run((line(key()), line(key())))?;
+    Ok(Input {
+        card_public_key,
+        door_public_key,
+    })
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day25, part1))]
+pub fn part1(input: &Input) -> Result<u64> {
+    let card_key = input.card_public_key;
+    let card_loop_size = discrete_log(SUBJECT_NUMBER, card_key, MODULUS).ok_or_else(|| {
+        ParseError::input(
+            25,
+            format!("{} is not a power of {}", card_key, SUBJECT_NUMBER),
+        )
+    })?;
+    Ok(modpow(input.door_public_key, card_loop_size, MODULUS))
+}
+
+#[test]
+fn e1() {
+    let input = "5764801
+17807724";
+    assert_eq!(part1(&parse(input).unwrap()), Ok(14897079));
+}
+
+#[test]
+fn key_too_large() {
+    assert_eq!(
+        parse("5764801\n20201227").err().map(|e| e.to_string()),
+        Some("day 25, line 2, column 1: public keys have to be smaller than 20201227".to_string())
+    );
+}
@@ -1,21 +1,41 @@
-#[aoc(day3, part1)]
-fn part1(input: &str) -> u32 {
-    let map = Map::new(input);
+use std::fmt;
 
-    trees_for_slope(&map, 3, 1)
+use bitvec::prelude::BitVec;
+use fxhash::FxHashMap;
+
+use crate::{
+    error::{Result, Source},
+    grid::{Cell, Grid},
+    normalize,
+    number_theory::gcd,
+};
+
+/// The trees on the slope. The pattern repeats to the right.
+pub type Map = Grid<bool>;
+
+pub type Input = Map;
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day3))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(3, input);
+    Map::parse(&Source::new(3, input))
 }
-#[aoc(day3, part2)]
-fn part2(input: &str) -> u32 {
-    let map = Map::new(input);
-    [
-        trees_for_slope(&map, 1, 1),
-        trees_for_slope(&map, 3, 1),
-        trees_for_slope(&map, 5, 1),
-        trees_for_slope(&map, 7, 1),
-        trees_for_slope(&map, 1, 2),
+
+#[cfg_attr(feature = "aoc-runner", aoc(day3, part1))]
+pub fn part1(map: &Input) -> Result<u32> {
+    Ok(trees_for_slope(map, 3, 1))
+}
+#[cfg_attr(feature = "aoc-runner", aoc(day3, part2))]
+pub fn part2(map: &Input) -> Result<u32> {
+    Ok([
+        trees_for_slope(map, 1, 1),
+        trees_for_slope(map, 3, 1),
+        trees_for_slope(map, 5, 1),
+        trees_for_slope(map, 7, 1),
+        trees_for_slope(map, 1, 2),
     ]
     .iter()
-    .product()
+    .product())
 }
 
 fn trees_for_slope(map: &Map, right: usize, down: usize) -> u32 {
@@ -25,45 +45,158 @@ fn trees_for_slope(map: &Map, right: usize, down: usize) -> u32 {
     loop {
         x += right;
         y += down;
-        if y >= map.height {
+        if y >= map.height() {
             break trees;
         }
-        if map.get(x, y) {
+        if *map.get_wrapping(x, y) {
             trees += 1;
         }
     }
 }
 
-struct Map {
+/// How far a toboggan goes to the right and down with every step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Slope {
+    right: usize,
+    down: usize,
+}
+
+impl Slope {
+    /// `None` if the slope doesn't go down, because then the toboggan never arrives.
+    pub fn new(right: usize, down: usize) -> Option<Self> {
+        (down > 0).then_some(Self { right, down })
+    }
+
+    pub fn right(&self) -> usize {
+        self.right
+    }
+
+    pub fn down(&self) -> usize {
+        self.down
+    }
+}
+
+impl fmt::Display for Slope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "right {}, down {}", self.right, self.down)
+    }
+}
+
+/// The trees of every row of a map as a bitset, to count the trees of many slopes.
+pub struct Trees {
+    rows: Vec<BitVec>,
     width: usize,
-    height: usize,
-    grid: Vec<bool>,
 }
 
-impl Map {
-    fn new(str: &str) -> Self {
-        let mut height = 0;
-        let mut grid = Vec::with_capacity(str.len());
-        for line in str.lines() {
-            height += 1;
-            for char in line.chars() {
-                match char {
-                    '.' => grid.push(false),
-                    '#' => grid.push(true),
-                    _ => unreachable!(),
-                }
-            }
-        }
-        let width = grid.len() / height;
+impl Trees {
+    pub fn new(map: &Map) -> Self {
         Self {
-            width,
-            height,
-            grid,
+            rows: map
+                .rows()
+                .map(|row| row.iter().copied().collect())
+                .collect(),
+            width: map.width(),
         }
     }
 
-    fn get(&self, mut x: usize, y: usize) -> bool {
-        x %= self.width;
-        self.grid[y * self.width + x]
+    /// The trees on the way from the top left to the bottom, like `trees_for_slope`.
+    pub fn count(&self, slope: Slope) -> u32 {
+        (slope.down..self.rows.len())
+            .step_by(slope.down)
+            .zip(1..)
+            .filter(|&(y, step)| self.rows[y][step * slope.right % self.width])
+            .count() as u32
     }
 }
+
+/// The slopes with the fewest and the most trees, with their number of trees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Extremes {
+    pub fewest: (Slope, u32),
+    pub most: (Slope, u32),
+}
+
+/// Tries every slope `right / down` in lowest terms with `right` from 0 and `down` from 1
+/// up to `bound`. On ties, the slope that comes first by `down` and then `right` wins.
+pub fn extreme_slopes(map: &Map, bound: usize) -> Option<Extremes> {
+    let trees = Trees::new(map);
+    // slopes that only differ by whole widths to the right take the same path
+    let mut counts = FxHashMap::default();
+    let mut extremes: Option<Extremes> = None;
+    for down in 1..=bound {
+        for right in (0..=bound).filter(|&right| gcd(right as u64, down as u64) == 1) {
+            let slope = Slope { right, down };
+            let trees = *counts
+                .entry((right % map.width().max(1), down))
+                .or_insert_with(|| trees.count(slope));
+            let extremes = extremes.get_or_insert(Extremes {
+                fewest: (slope, trees),
+                most: (slope, trees),
+            });
+            if trees < extremes.fewest.1 {
+                extremes.fewest = (slope, trees);
+            }
+            if trees > extremes.most.1 {
+                extremes.most = (slope, trees);
+            }
+        }
+    }
+    extremes
+}
+
+/// The map with the path of `slope` like in the puzzle: repeated to the right until
+/// the path reaches the bottom, with `O` where it crosses open ground and `X` where
+/// it hits a tree.
+pub fn render(map: &Map, slope: Slope) -> String {
+    let steps = map.height().saturating_sub(1) / slope.down;
+    let repeats = (steps * slope.right / map.width().max(1)) + 1;
+    let mut rows: Vec<Vec<char>> = map
+        .rows()
+        .map(|row| {
+            let row: Vec<char> = row.iter().map(Cell::to_char).collect();
+            row.repeat(repeats)
+        })
+        .collect();
+    for step in 1..=steps {
+        let cell = &mut rows[step * slope.down][step * slope.right];
+        *cell = if *cell == '#' { 'X' } else { 'O' };
+    }
+    rows.into_iter()
+        .map(|row| row.into_iter().collect::<String>())
+        .intersperse("\n".to_string())
+        .collect()
+}
+
+#[test]
+fn slopes() {
+    let map = parse(
+        "..##.......
+#...#...#..
+.#....#..#.
+..#.#...#.#
+.#...##..#.
+..#.##.....
+.#.#.#....#
+.#........#
+#.##...#...
+#...##....#
+.#..#...#.#",
+    )
+    .unwrap();
+    let trees = Trees::new(&map);
+    let slope = |right, down| Slope::new(right, down).unwrap();
+    assert_eq!(trees.count(slope(3, 1)), 7);
+    assert_eq!(trees.count(slope(1, 2)), 2);
+    let extremes = extreme_slopes(&map, 3).unwrap();
+    assert_eq!(extremes.most, (slope(3, 1), 7));
+    assert_eq!(extremes.fewest, (slope(1, 3), 0));
+    assert_eq!(extreme_slopes(&map, 0), None);
+    assert_eq!(Slope::new(1, 0), None);
+
+    let path = render(&map, slope(3, 1));
+    let lines: Vec<&str> = path.lines().collect();
+    assert_eq!(lines[1], "#..O#...#..#...#...#..#...#...#..");
+    assert_eq!(lines[2].len(), 33);
+    assert_eq!(path.matches('X').count(), 7);
+    assert_eq!(path.matches('O').count(), 3);
+}
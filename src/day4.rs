@@ -1,91 +1,386 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, ops::RangeInclusive};
 
-const REQUIRED_FIELDS: &[&str] = &[
-    "byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid", /*"cid"*/
-];
+use fxhash::FxHashMap;
 
-const VALID_E_COLORS: &[&str] = &["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
+use regex::Regex;
+use serde_json::Value;
 
-#[aoc(day4, part1)]
-fn part1(input: &str) -> usize {
-    input
-        .split("\n\n")
-        .map(|pass| parse_pass(pass))
-        .filter(|pass| REQUIRED_FIELDS.iter().all(|f| pass.contains_key(f)))
-        .count()
-}
-#[aoc(day4, part2)]
-fn part2(input: &str) -> usize {
-    input
-        .split("\n\n")
-        .map(|pass| parse_pass(pass))
-        .filter(|pass| REQUIRED_FIELDS.iter().all(|f| pass.contains_key(f)) && validate(pass))
-        .count()
+use crate::{
+    error::{Result, Source},
+    normalize,
+    parser::{blocks, field, optional, separated, spanned, take_while1, word, Parser},
+};
+
+/// The fields of a passport, by key.
+pub type Passport = HashMap<String, String>;
+
+pub struct Input {
+    pub passports: Vec<Passport>,
+    /// The first and last 1-based line of every passport.
+    pub lines: Vec<RangeInclusive<usize>>,
 }
 
-fn parse_pass(pass: &str) -> HashMap<&str, &str> {
-    pass.split_whitespace()
-        .map(|p| {
-            let mut s = p.split(':');
-            (s.next().unwrap(), s.next().unwrap())
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day4))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(4, input);
+    let key = take_while1("a key", char::is_alphanumeric);
+    let whitespace = take_while1("whitespace", char::is_whitespace);
+    // values can be empty, like `cid:`
+    let value = optional(word()).map(Option::unwrap_or_default);
+    let passport = separated(field(key, ":", value), whitespace).map(|fields| {
+        fields
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    });
+    let src = Source::new(4, input);
+    let (lines, passports) = src
+        .run(blocks(spanned(passport)))?
+        .into_iter()
+        .map(|(text, passport)| {
+            let first = src.line(text);
+            (first..=first + text.matches('\n').count(), passport)
         })
-        .collect()
+        .unzip();
+    Ok(Input { passports, lines })
 }
 
-fn validate(pass: &HashMap<&str, &str>) -> bool {
-    pass["byr"]
-        .parse::<u32>()
-        .map_or(false, |p| (1920..=2002).contains(&p))
-        && pass["eyr"]
-            .parse::<u32>()
-            .map_or(false, |p| (2020..=2030).contains(&p))
-        && pass["iyr"]
-            .parse::<u32>()
-            .map_or(false, |p| (2010..=2020).contains(&p))
-        && valid_height(pass["hgt"])
-        && valid_color(pass["hcl"])
-        && VALID_E_COLORS.contains(&pass["ecl"])
-        && valid_pid(pass["pid"])
-}
-
-fn valid_height(h: &str) -> bool {
-    let n = &h[..h.len() - 2].parse();
-    let n = if let Ok(n) = n {
-        n
-    } else {
-        return false;
-    };
-    match &h[h.len() - 2..] {
-        "cm" => (150..=193).contains(n),
-        "in" => (59..=76).contains(n),
-        _ => false,
+#[cfg_attr(feature = "aoc-runner", aoc(day4, part1))]
+pub fn part1(input: &Input) -> Result<usize> {
+    let schema = Schema::passport();
+    Ok(input
+        .passports
+        .iter()
+        .filter(|pass| schema.has_required_fields(pass))
+        .count())
+}
+#[cfg_attr(feature = "aoc-runner", aoc(day4, part2))]
+pub fn part2(input: &Input) -> Result<usize> {
+    let schema = Schema::passport();
+    Ok(input
+        .passports
+        .iter()
+        .filter(|pass| schema.is_valid(pass))
+        .count())
+}
+
+/// How the value of a field is checked.
+pub enum Validator {
+    /// Any value.
+    Any,
+    /// A number in the range.
+    Int(RangeInclusive<i64>),
+    /// A number directly followed by one of the units, in the range of that unit.
+    Units(Vec<(String, RangeInclusive<i64>)>),
+    /// `#` followed by six lowercase hex digits.
+    HexColor,
+    /// One of the values.
+    OneOf(Vec<String>),
+    /// Exactly this many digits.
+    Digits(usize),
+    /// The value has to match the regex.
+    Matches(Regex),
+}
+
+impl Validator {
+    /// Why `value` is invalid, if it is.
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        let valid = match self {
+            Validator::Any => true,
+            Validator::Int(range) => value.parse().is_ok_and(|n| range.contains(&n)),
+            Validator::Units(units) => units.iter().any(|(unit, range)| {
+                value
+                    .strip_suffix(unit.as_str())
+                    .and_then(|n| n.parse().ok())
+                    .is_some_and(|n| range.contains(&n))
+            }),
+            Validator::HexColor => value.strip_prefix('#').is_some_and(|hex| {
+                hex.len() == 6 && hex.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f'))
+            }),
+            Validator::OneOf(values) => values.iter().any(|v| v == value),
+            Validator::Digits(len) => {
+                value.len() == *len && value.chars().all(|c| c.is_ascii_digit())
+            }
+            Validator::Matches(regex) => regex.is_match(value),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("`{}` is not {}", value, self))
+        }
     }
 }
 
-fn valid_color(color: &str) -> bool {
-    let mut chars = color.chars();
-    if chars.next().unwrap() != '#' {
-        return false;
+impl fmt::Display for Validator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let range = |range: &RangeInclusive<i64>| format!("{} to {}", range.start(), range.end());
+        match self {
+            Validator::Any => write!(f, "anything"),
+            Validator::Int(r) => write!(f, "a number from {}", range(r)),
+            Validator::Units(units) => {
+                let units: Vec<String> = units
+                    .iter()
+                    .map(|(unit, r)| format!("{} {}", range(r), unit))
+                    .collect();
+                write!(f, "{}", units.join(" or "))
+            }
+            Validator::HexColor => write!(f, "a hex color"),
+            Validator::OneOf(values) => write!(f, "one of {}", values.join(", ")),
+            Validator::Digits(len) => write!(f, "{} digits", len),
+            Validator::Matches(regex) => write!(f, "matching `{}`", regex),
+        }
     }
-    let mut count = 0;
-    for char in chars {
-        match char {
-            '0'..='9' | 'a'..='f' => count += 1,
-            _ => return false,
+}
+
+/// A field of a record and how it is checked.
+pub struct FieldRule {
+    pub name: String,
+    pub required: bool,
+    pub validator: Validator,
+}
+
+/// The fields a record has to have, and what their values have to look like.
+/// Fields that are not in the schema are allowed and not checked.
+pub struct Schema {
+    pub fields: Vec<FieldRule>,
+}
+
+impl Schema {
+    /// The rules of the passport scanner in part 2. `cid` is optional.
+    pub fn passport() -> Self {
+        let field = |name: &str, required, validator| FieldRule {
+            name: name.to_string(),
+            required,
+            validator,
+        };
+        let strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect();
+        Schema {
+            fields: vec![
+                field("byr", true, Validator::Int(1920..=2002)),
+                field("iyr", true, Validator::Int(2010..=2020)),
+                field("eyr", true, Validator::Int(2020..=2030)),
+                field(
+                    "hgt",
+                    true,
+                    Validator::Units(vec![
+                        ("cm".to_string(), 150..=193),
+                        ("in".to_string(), 59..=76),
+                    ]),
+                ),
+                field("hcl", true, Validator::HexColor),
+                field(
+                    "ecl",
+                    true,
+                    Validator::OneOf(strings(&["amb", "blu", "brn", "gry", "grn", "hzl", "oth"])),
+                ),
+                field("pid", true, Validator::Digits(9)),
+                field("cid", false, Validator::Any),
+            ],
         }
     }
-    count == 6
-}
-fn valid_pid(pid: &str) -> bool {
-    let chars = pid.chars();
-    let mut count = 0;
-    for char in chars {
-        match char {
-            '0'..='9' => count += 1,
-            _ => return false,
+
+    /// Reads a schema from JSON: a list of fields like
+    /// `{"name": "hgt", "required": true, "rule": {"units": {"cm": [150, 193]}}}`.
+    /// The rule is `"any"` (the default), `{"int": [min, max]}`, `{"units": {...}}`,
+    /// `"hex-color"`, `{"one-of": [...]}`, `{"digits": n}` or `{"matches": "<regex>"}`.
+    /// `required` defaults to `true`.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let json: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let fields = json
+            .as_array()
+            .ok_or("the schema has to be a list of fields")?;
+        Ok(Schema {
+            fields: fields.iter().map(field_rule).collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn has_required_fields(&self, record: &Passport) -> bool {
+        self.fields
+            .iter()
+            .all(|field| !field.required || record.contains_key(&field.name))
+    }
+
+    /// Every field of `record` that breaks the schema, in the order of the schema.
+    pub fn check(&self, record: &Passport) -> Vec<FieldError> {
+        self.fields
+            .iter()
+            .filter_map(|field| {
+                let problem = match record.get(&field.name) {
+                    Some(value) => {
+                        field.validator.check(value).err()?;
+                        Problem::Invalid {
+                            value: value.clone(),
+                            rule: field.validator.to_string(),
+                        }
+                    }
+                    None if field.required => Problem::Missing,
+                    None => return None,
+                };
+                Some(FieldError {
+                    field: field.name.clone(),
+                    problem,
+                })
+            })
+            .collect()
+    }
+
+    /// Whether `record` has the required fields and all of its fields are valid.
+    pub fn is_valid(&self, record: &Passport) -> bool {
+        self.fields
+            .iter()
+            .all(|field| match record.get(&field.name) {
+                Some(value) => field.validator.check(value).is_ok(),
+                None => !field.required,
+            })
+    }
+}
+
+/// Why a field breaks the schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// The field is required, but the record doesn't have it.
+    Missing,
+    /// The value doesn't follow the rule, e.g. `a number from 1920 to 2002`.
+    Invalid { value: String, rule: String },
+}
+
+/// A field of a record that breaks the schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: String,
+    pub problem: Problem,
+}
+
+impl FieldError {
+    /// What went wrong, without the value, to group records that fail the same way.
+    pub fn reason(&self) -> String {
+        match &self.problem {
+            Problem::Missing => format!("`{}` is missing", self.field),
+            Problem::Invalid { rule, .. } => format!("`{}` is not {}", self.field, rule),
         }
     }
-    count == 9
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.problem {
+            Problem::Missing => write!(f, "`{}` is missing", self.field),
+            Problem::Invalid { value, rule } => {
+                write!(f, "`{}` `{}` is not {}", self.field, value, rule)
+            }
+        }
+    }
+}
+
+/// A record that breaks the schema.
+pub struct Rejection {
+    /// The first and last 1-based line of the record in the input.
+    pub lines: RangeInclusive<usize>,
+    pub errors: Vec<FieldError>,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.lines.start() == self.lines.end() {
+            write!(f, "line {}: ", self.lines.start())?;
+        } else {
+            write!(f, "lines {}-{}: ", self.lines.start(), self.lines.end())?;
+        }
+        let errors: Vec<String> = self.errors.iter().map(FieldError::to_string).collect();
+        write!(f, "{}", errors.join(", "))
+    }
+}
+
+/// Every record in `input` that breaks `schema`, with every field that is wrong.
+pub fn audit(input: &Input, schema: &Schema) -> Vec<Rejection> {
+    input
+        .passports
+        .iter()
+        .zip(&input.lines)
+        .filter_map(|(record, lines)| {
+            let errors = schema.check(record);
+            if errors.is_empty() {
+                None
+            } else {
+                Some(Rejection {
+                    lines: lines.clone(),
+                    errors,
+                })
+            }
+        })
+        .collect()
+}
+
+/// How many of the rejected records fail for every reason, the most common first.
+pub fn summary(rejections: &[Rejection]) -> Vec<(String, usize)> {
+    let mut counts: FxHashMap<String, usize> = FxHashMap::default();
+    for error in rejections.iter().flat_map(|rejection| &rejection.errors) {
+        *counts.entry(error.reason()).or_default() += 1;
+    }
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+    counts
+}
+
+fn field_rule(json: &Value) -> Result<FieldRule, String> {
+    let name = json["name"].as_str().ok_or("every field needs a `name`")?;
+    let invalid = |what: &str| format!("invalid {} of field `{}`", what, name);
+    let required = match &json["required"] {
+        Value::Null => true,
+        required => required.as_bool().ok_or_else(|| invalid("`required`"))?,
+    };
+    let range = |json: &Value| -> Option<RangeInclusive<i64>> {
+        match json.as_array()?.as_slice() {
+            [min, max] => Some(min.as_i64()?..=max.as_i64()?),
+            _ => None,
+        }
+    };
+    let validator = match &json["rule"] {
+        Value::Null => Validator::Any,
+        Value::String(rule) if rule == "any" => Validator::Any,
+        Value::String(rule) if rule == "hex-color" => Validator::HexColor,
+        Value::Object(rule) if rule.len() == 1 => {
+            let (kind, value) = rule.iter().next().unwrap();
+            match kind.as_str() {
+                "int" => Validator::Int(range(value).ok_or_else(|| invalid("range"))?),
+                "units" => Validator::Units(
+                    value
+                        .as_object()
+                        .ok_or_else(|| invalid("units"))?
+                        .iter()
+                        .map(|(unit, r)| Some((unit.clone(), range(r)?)))
+                        .collect::<Option<_>>()
+                        .ok_or_else(|| invalid("units"))?,
+                ),
+                "one-of" => Validator::OneOf(
+                    value
+                        .as_array()
+                        .ok_or_else(|| invalid("values"))?
+                        .iter()
+                        .map(|v| v.as_str().map(str::to_string))
+                        .collect::<Option<_>>()
+                        .ok_or_else(|| invalid("values"))?,
+                ),
+                "digits" => Validator::Digits(
+                    value.as_u64().ok_or_else(|| invalid("number of digits"))? as usize,
+                ),
+                "matches" => {
+                    let regex = value.as_str().ok_or_else(|| invalid("regex"))?;
+                    Validator::Matches(
+                        Regex::new(regex)
+                            .map_err(|e| format!("invalid regex `{}`: {}", regex, e))?,
+                    )
+                }
+                _ => return Err(format!("unknown rule `{}` of field `{}`", kind, name)),
+            }
+        }
+        _ => return Err(invalid("rule")),
+    };
+    Ok(FieldRule {
+        name: name.to_string(),
+        required,
+        validator,
+    })
 }
 
 #[test]
@@ -103,10 +398,11 @@ ecl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277
 hgt:59cm ecl:zzz
 eyr:2038 hcl:74454a iyr:2023
 pid:3556412378 byr:2007";
-    assert!(invalid
-        .split("\n\n")
-        .map(|pass| parse_pass(pass))
-        .all(|pass| !validate(&pass)))
+    assert!(parse(invalid)
+        .unwrap()
+        .passports
+        .iter()
+        .all(|pass| !Schema::passport().is_valid(pass)))
 }
 #[test]
 fn valid() {
@@ -122,8 +418,84 @@ pid:545766238 ecl:hzl
 eyr:2022
 
 iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719";
-    assert!(invalid
-        .split("\n\n")
-        .map(|pass| parse_pass(pass))
-        .all(|pass| validate(&pass)))
+    assert!(parse(invalid)
+        .unwrap()
+        .passports
+        .iter()
+        .all(|pass| Schema::passport().is_valid(pass)))
+}
+#[test]
+fn short_values() {
+    let height = Validator::Units(vec![("cm".to_string(), 150..=193)]);
+    assert!(height.check("7").is_err());
+    assert!(height.check("").is_err());
+    assert!(height.check("cm").is_err());
+    assert!(Validator::HexColor.check("").is_err());
+    assert_eq!(
+        Validator::Digits(9).check("0123").unwrap_err(),
+        "`0123` is not 9 digits"
+    );
+}
+#[test]
+fn schema_file() {
+    let schema = Schema::from_json(include_str!("../schemas/passport.json")).unwrap();
+    let input = parse(&crate::generators::generate(4, 0, 200).unwrap()).unwrap();
+    let passport = Schema::passport();
+    for pass in &input.passports {
+        assert_eq!(schema.is_valid(pass), passport.is_valid(pass));
+        assert_eq!(
+            schema.has_required_fields(pass),
+            passport.has_required_fields(pass)
+        );
+    }
+
+    let schema = Schema::from_json(
+        r#"[{"name": "id", "rule": {"matches": "^[A-Z]{2}[0-9]+$"}},
+            {"name": "note", "required": false}]"#,
+    )
+    .unwrap();
+    let input = parse("id:AB12 note:x\n\nid:12\n\nnote:y").unwrap();
+    let valid: Vec<bool> = input.passports.iter().map(|p| schema.is_valid(p)).collect();
+    assert_eq!(valid, [true, false, false]);
+    assert!(Schema::from_json(r#"[{"name": "id", "rule": {"int": [1]}}]"#).is_err());
+    assert!(Schema::from_json(r#"[{"name": "id", "rule": "hex"}]"#).is_err());
+}
+#[test]
+fn report() {
+    let input = parse(
+        "eyr:1972 cid:100
+hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926
+
+pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980 hcl:#623a2f
+
+hgt:7 ecl:zzz
+byr:1926",
+    )
+    .unwrap();
+    assert_eq!(input.lines, [1..=2, 4..=4, 6..=7]);
+    let rejections = audit(&input, &Schema::passport());
+    let rejections: Vec<String> = rejections.iter().map(|r| r.to_string()).collect();
+    assert_eq!(
+        rejections,
+        [
+            "lines 1-2: `eyr` `1972` is not a number from 2020 to 2030, \
+             `hgt` `170` is not 150 to 193 cm or 59 to 76 in, `pid` `186cm` is not 9 digits",
+            "lines 6-7: `iyr` is missing, `eyr` is missing, \
+             `hgt` `7` is not 150 to 193 cm or 59 to 76 in, `hcl` is missing, \
+             `ecl` `zzz` is not one of amb, blu, brn, gry, grn, hzl, oth, `pid` is missing",
+        ]
+    );
+    let counts = summary(&audit(&input, &Schema::passport()));
+    assert_eq!(
+        counts[0],
+        ("`hgt` is not 150 to 193 cm or 59 to 76 in".to_string(), 2)
+    );
+    assert_eq!(counts.len(), 8);
+}
+#[test]
+fn real_input() {
+    let input = std::fs::read_to_string("input/2020/day4.txt").unwrap();
+    assert_eq!(part2(&parse(&input).unwrap()), Ok(184));
+    let crlf = input.replace('\n', "\r\n");
+    assert_eq!(part2(&parse(&crlf).unwrap()), Ok(184));
 }
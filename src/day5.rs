@@ -1,59 +1,92 @@
+use std::io::BufRead;
+
 use bitvec::prelude::*;
 
-#[aoc(day5, part1)]
-fn part1(input: &str) -> usize {
-    input.lines().map(|line| get_id(line)).max().unwrap()
+use crate::{
+    error::{ParseError, Result, Source},
+    normalize,
+    parser::{lines, Failure, Parser},
+    stream::Lines,
+};
+
+pub struct Input {
+    pub seat_ids: Vec<usize>,
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day5))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(5, input);
+    Ok(Input {
+        seat_ids: Source::new(5, input).run(lines(seat_id()))?,
+    })
 }
-#[aoc(day5, part2)]
-fn part2(input: &str) -> usize {
+
+#[cfg_attr(feature = "aoc-runner", aoc(day5, part1))]
+pub fn part1(input: &Input) -> Result<usize> {
+    input
+        .seat_ids
+        .iter()
+        .copied()
+        .max()
+        .ok_or_else(|| ParseError::input(5, "no boarding passes"))
+}
+#[cfg_attr(feature = "aoc-runner", aoc(day5, part2))]
+pub fn part2(input: &Input) -> Result<usize> {
     let mut seats = bitarr![0; 128*8];
-    for seat in input.lines().map(|line| get_id(line)) {
+    for &seat in &input.seat_ids {
         seats.set(seat, true)
     }
-    for r in 0..128 {
-        for c in 0..8 {
-            let id = r * 8 + c;
-            if !seats[id] && seats[id + 1] && seats[id - 1] {
-                return id;
-            }
+    free_seat(&seats)
+}
+
+/// Both parts, reading the boarding passes one at a time.
+pub fn stream(input: impl BufRead) -> Result<(usize, usize)> {
+    let mut lines = Lines::new(5, input);
+    let mut seats = bitarr![0; 128*8];
+    let mut highest = None;
+    while let Some(seat) = lines.parse(seat_id())? {
+        seats.set(seat, true);
+        highest = highest.max(Some(seat));
+    }
+    let highest = highest.ok_or_else(|| ParseError::input(5, "no boarding passes"))?;
+    Ok((highest, free_seat(&seats)?))
+}
+
+fn free_seat(seats: &BitSlice) -> Result<usize> {
+    for id in 1..(128 * 8 - 1) {
+        if !seats[id] && seats[id + 1] && seats[id - 1] {
+            return Ok(id);
         }
     }
-    panic!();
+    Err(ParseError::input(5, "no free seat between two taken ones"))
 }
 
-fn get_id(seat: &str) -> usize {
-    let (row, column) = seat.split_at(7);
-    get_row(row) * 8 + get_column(column)
+fn seat_id<'a>() -> impl Parser<'a, usize> {
+    (bits(7, 'F', 'B'), bits(3, 'L', 'R')).map(|(row, column)| row * 8 + column)
 }
 
-fn get_row(row: &str) -> usize {
-    let mut r = 0;
-    for (n, char) in row.chars().rev().enumerate() {
-        let bin = match char {
-            'F' => 0,
-            'B' => 1,
-            _ => panic!(),
-        };
-        r |= bin << n;
-    }
-    r
-}
-
-fn get_column(column: &str) -> usize {
-    let mut c = 0;
-    for (n, char) in column.chars().rev().enumerate() {
-        let bin = match char {
-            'L' => 0,
-            'R' => 1,
-            _ => panic!(),
-        };
-        c |= bin << n;
+/// `count` characters, read as a binary number with `zero` for 0 and `one` for 1.
+fn bits<'a>(count: usize, zero: char, one: char) -> impl Parser<'a, usize> {
+    move |mut input: &'a str| {
+        let mut value = 0;
+        for _ in 0..count {
+            let bit = match input.chars().next() {
+                Some(char) if char == zero => 0,
+                Some(char) if char == one => 1,
+                _ => {
+                    let message = format!("expected `{}` or `{}`", zero, one);
+                    return Err(Failure::new(input, message));
+                }
+            };
+            value = value << 1 | bit;
+            input = &input[1..];
+        }
+        Ok((input, value))
     }
-    c
 }
 
 #[test]
 fn ex() {
     let a = "BFFFBBFRRR";
-    assert_eq!(get_id(a), 567);
+    assert_eq!(Source::new(5, a).run(seat_id()), Ok(567));
 }
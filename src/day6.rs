@@ -1,28 +1,89 @@
 extern crate fxhash;
 
+use std::io::BufRead;
+
 use fxhash::FxHashSet;
 
-#[aoc(day6, part1)]
-fn part1(input: &str) -> usize {
-    input.split("\n\n").map(|g| count_group(g)).sum()
+use crate::{
+    error::{Result, Source},
+    normalize,
+    parser::{blocks, lines, optional, word, Parser},
+    stream::Lines,
+};
+
+/// The answers of every person in a group, one string per person.
+pub type Group = Vec<String>;
+
+pub struct Input {
+    pub groups: Vec<Group>,
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day6))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(6, input);
+    let person = word().map(str::to_string);
+    Ok(Input {
+        groups: Source::new(6, input).run(blocks(lines(person)))?,
+    })
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day6, part1))]
+pub fn part1(input: &Input) -> Result<usize> {
+    Ok(input.groups.iter().map(|g| count_group(g)).sum())
 }
-#[aoc(day6, part2)]
-fn part2(input: &str) -> usize {
-    input.split("\n\n").map(|g| count_group2(g)).sum()
+#[cfg_attr(feature = "aoc-runner", aoc(day6, part2))]
+pub fn part2(input: &Input) -> Result<usize> {
+    Ok(input.groups.iter().map(|g| count_group2(g)).sum())
+}
+
+/// Both parts, reading the answers one person at a time.
+pub fn stream(input: impl BufRead) -> Result<(usize, usize)> {
+    let mut lines = Lines::new(6, input);
+    let (mut anyone_total, mut everyone_total) = (0, 0);
+    // the answers of anyone and of everyone in the current group
+    let mut group: Option<(FxHashSet<char>, FxHashSet<char>)> = None;
+    loop {
+        // `Some(None)` for the blank lines between groups
+        let person = lines.parse(optional(word()))?;
+        if let Some(Some(person)) = person {
+            let answers: FxHashSet<char> = person.chars().collect();
+            match &mut group {
+                Some((anyone, everyone)) => {
+                    anyone.extend(&answers);
+                    everyone.retain(|c| answers.contains(c));
+                }
+                None => group = Some((answers.clone(), answers)),
+            }
+            continue;
+        }
+        match group.take() {
+            Some((anyone, everyone)) => {
+                anyone_total += anyone.len();
+                everyone_total += everyone.len();
+            }
+            None => return Err(lines.error("expected the answers of a person")),
+        }
+        if person.is_none() {
+            return Ok((anyone_total, everyone_total));
+        }
+    }
 }
 
-fn count_group(group: &str) -> usize {
+fn count_group(group: &[String]) -> usize {
     let mut answers = FxHashSet::default();
-    for line in group.lines() {
+    for line in group {
         for char in line.chars() {
             answers.insert(char);
         }
     }
     answers.len()
 }
-fn count_group2(group: &str) -> usize {
-    let mut lines = group.lines();
-    let mut answers: FxHashSet<char> = lines.next().unwrap().chars().collect();
+fn count_group2(group: &[String]) -> usize {
+    let mut lines = group.iter();
+    let mut answers: FxHashSet<char> = match lines.next() {
+        Some(line) => line.chars().collect(),
+        None => return 0,
+    };
     for line in lines {
         let new_answers: FxHashSet<char> = line.chars().collect();
         answers.retain(|c| new_answers.contains(c));
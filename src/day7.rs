@@ -1,16 +1,35 @@
 use std::collections::{HashMap, HashSet};
 
-use lazy_static::lazy_static;
 use multimap::MultiMap;
-use regex::Regex;
 
-#[aoc(day7, part1)]
-fn part1(input: &str) -> usize {
-    let bags = input.lines().map(|l| parse(l));
+use crate::{
+    error::{ParseError, Result, Source},
+    normalize,
+    parser::{alt, int, lines, optional, separated, tag, until, Parser},
+};
+
+pub struct Input {
+    /// The rules, by the color of the outer bag.
+    pub bags: HashMap<String, Bag>,
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day7))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(7, input);
+    Ok(Input {
+        bags: Source::new(7, input)
+            .run(lines(rule()))?
+            .into_iter()
+            .collect(),
+    })
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day7, part1))]
+pub fn part1(input: &Input) -> Result<usize> {
     let mut can_contain = MultiMap::new();
-    for (name, bag) in bags {
-        for (_, child) in bag.children {
-            can_contain.insert(child, name.clone());
+    for (name, bag) in &input.bags {
+        for (_, child) in &bag.children {
+            can_contain.insert(child.as_str(), name.as_str());
         }
     }
     let mut checked = HashSet::new();
@@ -31,45 +50,49 @@ fn part1(input: &str) -> usize {
         }
         current.clear();
     }
-    checked.len()
+    Ok(checked.len())
 }
 
-#[aoc(day7, part2)]
-fn part2(input: &str) -> usize {
-    let bags: HashMap<String, Bag> = input.lines().map(|l| parse(l)).collect();
-    bags.get("shiny gold")
-        .unwrap()
-        .calculate_total_children(&bags)
+#[cfg_attr(feature = "aoc-runner", aoc(day7, part2))]
+pub fn part2(input: &Input) -> Result<usize> {
+    input
+        .bags
+        .get("shiny gold")
+        .ok_or_else(|| ParseError::input(7, "no rule for shiny gold bags"))?
+        .calculate_total_children(&input.bags, 0)
 }
 
-fn parse(line: &str) -> (String, Bag) {
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r"(.*?) bags contain").unwrap();
-        static ref RE2: Regex = Regex::new(r"(?:(\d+) (.*?) bags?)").unwrap();
-    }
-    (
-        RE.captures(line).unwrap()[1].to_owned(),
-        Bag {
-            children: RE2
-                .captures_iter(line)
-                .map(|capture| ((&capture[1]).parse().unwrap(), capture[2].to_owned()))
-                .collect(),
-        },
-    )
+fn rule<'a>() -> impl Parser<'a, (String, Bag)> {
+    let color = || until(" bag").map(str::to_string);
+    let child = (int(), tag(" "), color(), tag(" bag"), optional(tag("s")))
+        .map(|(count, _, color, _, _)| (count, color));
+    let children = alt(
+        tag("no other bags").map(|_| vec![]),
+        separated(child, tag(", ")),
+    );
+    (color(), tag(" bags contain "), children, tag("."))
+        .map(|(name, _, children, _)| (name, Bag { children }))
 }
 
-struct Bag {
-    children: Vec<(usize, String)>,
+/// The bags that have to be inside of a bag, along with their count.
+pub struct Bag {
+    pub children: Vec<(usize, String)>,
 }
 
 impl Bag {
-    fn calculate_total_children(&self, bags: &HashMap<String, Bag>) -> usize {
+    fn calculate_total_children(&self, bags: &HashMap<String, Bag>, depth: usize) -> Result<usize> {
+        if depth > bags.len() {
+            return Err(ParseError::input(7, "the bag rules contain a cycle"));
+        }
         let mut children = 0;
         for (n, name) in &self.children {
-            children += n + n * bags[name].calculate_total_children(bags);
+            let bag = bags
+                .get(name)
+                .ok_or_else(|| ParseError::input(7, format!("no rule for {} bags", name)))?;
+            children += n + n * bag.calculate_total_children(bags, depth + 1)?;
         }
         //self.total_children = Some(children);
-        children
+        Ok(children)
     }
 }
 
@@ -84,5 +107,7 @@ dark olive bags contain 3 faded blue bags, 4 dotted black bags.
 vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.
 faded blue bags contain no other bags.
 dotted black bags contain no other bags.";
-    part1(input);
+    let input = parse(input).unwrap();
+    assert_eq!(part1(&input), Ok(4));
+    assert_eq!(part2(&input), Ok(32));
 }
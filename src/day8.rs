@@ -1,11 +1,50 @@
-#[aoc(day8, part1)]
-fn part1(input: &str) -> i32 {
-    let mut vm = Vm::new(input.lines().map(|line| parse(line)).collect());
-    vm.run_until_loop()
+use std::{fmt, io::BufRead};
+
+use crate::{
+    error::{ParseError, Result, Source},
+    normalize,
+    parser::{int, lines, one_of, tag, Parser},
+    stream::Lines,
+    trace::trace,
+};
+
+pub struct Input {
+    pub program: Vec<Op>,
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day8))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(8, input);
+    Ok(Input {
+        program: Source::new(8, input).run(lines(op()))?,
+    })
+}
+
+/// Like `parse`, but loads the program one instruction at a time without keeping its text.
+pub fn read(input: impl BufRead) -> Result<Input> {
+    let mut lines = Lines::new(8, input);
+    let mut program = vec![];
+    while let Some(op) = lines.parse(op())? {
+        program.push(op);
+    }
+    Ok(Input { program })
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day8, part1))]
+pub fn part1(input: &Input) -> Result<i32> {
+    let mut vm = Vm::new(&input.program);
+    if vm.has_loop() {
+        Ok(vm.acc)
+    } else {
+        Err(ParseError::input(
+            8,
+            "the program terminates without looping",
+        ))
+    }
 }
-#[aoc(day8, part2)]
-fn part2(input: &str) -> i32 {
-    let mut vm = Vm::new(input.lines().map(|line| parse(line)).collect());
+#[cfg_attr(feature = "aoc-runner", aoc(day8, part2))]
+pub fn part2(input: &Input) -> Result<i32> {
+    let mut vm = Vm::new(&input.program);
     for i in 0..vm.instructions.len() {
         let prev_val = vm.instructions[i].op;
         vm.instructions[i].op = match prev_val {
@@ -13,13 +52,19 @@ fn part2(input: &str) -> i32 {
             Op::Jmp(val) => Op::Noop(val),
             v => v,
         };
+        if vm.instructions[i].op != prev_val {
+            trace!(8, "patch", { "ip": i, "op": vm.instructions[i].op.to_string() });
+        }
         if !vm.has_loop() {
-            return vm.acc;
+            return Ok(vm.acc);
         }
         vm.instructions[i].op = prev_val;
         vm.reset();
     }
-    panic!()
+    Err(ParseError::input(
+        8,
+        "no single nop/jmp swap makes the program terminate",
+    ))
 }
 
 struct Instruction {
@@ -27,41 +72,52 @@ struct Instruction {
     op: Op,
 }
 
-#[derive(Clone, Copy)]
-enum Op {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
     Noop(i32),
     Acc(i32),
     Jmp(i32),
 }
 
-fn parse(line: &str) -> Instruction {
-    let arg = line[4..].parse().unwrap();
-    let op = match &line[..=2] {
-        "acc" => Op::Acc(arg),
-        "jmp" => Op::Jmp(arg),
-        "nop" => Op::Noop(arg),
-        _ => unreachable!(),
-    };
-    Instruction { visited: false, op }
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::Noop(arg) => write!(f, "nop {:+}", arg),
+            Op::Acc(arg) => write!(f, "acc {:+}", arg),
+            Op::Jmp(arg) => write!(f, "jmp {:+}", arg),
+        }
+    }
+}
+
+fn op<'a>() -> impl Parser<'a, Op> {
+    type Operation = fn(i32) -> Op;
+    static OPERATIONS: &[(&str, Operation)] =
+        &[("acc", Op::Acc), ("jmp", Op::Jmp), ("nop", Op::Noop)];
+    (one_of(OPERATIONS), tag(" "), int()).map(|(op, _, argument)| op(argument))
 }
 
-struct Vm {
+/// The handheld game console.
+pub struct Vm {
     instructions: Vec<Instruction>,
-    acc: i32,
-    ip: usize,
+    pub acc: i32,
+    pub ip: usize,
 }
 
 impl Vm {
-    fn new(instructions: Vec<Instruction>) -> Self {
+    pub fn new(program: &[Op]) -> Self {
         Self {
-            instructions,
+            instructions: program
+                .iter()
+                .map(|&op| Instruction { visited: false, op })
+                .collect(),
             acc: 0,
             ip: 0,
         }
     }
 
     fn exec_at_ip(&mut self) {
-        let instruction = &mut self.instructions[self.ip];
+        let ip = self.ip;
+        let instruction = &mut self.instructions[ip];
         let mut inc = 1;
         match instruction.op {
             Op::Noop(_) => {}
@@ -72,16 +128,10 @@ impl Vm {
         }
         self.ip = (self.ip as i32 + inc) as usize;
         instruction.visited = true;
+        trace!(8, "exec", { "ip": ip, "op": instruction.op.to_string(), "acc": self.acc });
     }
 
-    fn run_until_loop(&mut self) -> i32 {
-        while !self.instructions[self.ip].visited {
-            self.exec_at_ip();
-        }
-        self.acc
-    }
-
-    fn reset(&mut self) {
+    pub fn reset(&mut self) {
         self.ip = 0;
         self.acc = 0;
         for instr in &mut self.instructions {
@@ -89,7 +139,9 @@ impl Vm {
         }
     }
 
-    fn has_loop(&mut self) -> bool {
+    /// Runs the program until it either terminates by leaving the program or is about
+    /// to execute an instruction for the second time.
+    pub fn has_loop(&mut self) -> bool {
         loop {
             if self.ip >= self.instructions.len() {
                 break false;
@@ -1,18 +1,33 @@
-#[aoc(day9, part1)]
-fn part1(input: &str) -> u64 {
-    let values: Vec<u64> = input.lines().map(|s| s.parse().unwrap()).collect();
-    for idx in 25..values.len() {
-        if !sums(&values[((idx - 25) as usize)..(idx as usize)], values[idx]) {
-            return values[idx];
-        }
-    }
-    unreachable!()
+use std::{collections::VecDeque, io::BufRead};
+
+use crate::{
+    error::{ParseError, Result, Source},
+    normalize,
+    parser::{int, lines},
+    stream::Lines,
+};
+
+pub struct Input {
+    pub numbers: Vec<u64>,
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc_generator(day9))]
+pub fn parse(input: &str) -> Result<Input> {
+    let input = &*normalize::for_day(9, input);
+    Ok(Input {
+        numbers: Source::new(9, input).run(lines(int()))?,
+    })
+}
+
+#[cfg_attr(feature = "aoc-runner", aoc(day9, part1))]
+pub fn part1(input: &Input) -> Result<u64> {
+    first_invalid(&input.numbers)
 }
 
-#[aoc(day9, part2)]
-fn part2(input: &str) -> u64 {
-    let wanted = part1(input);
-    let values: Vec<u64> = input.lines().map(|s| s.parse().unwrap()).collect();
+#[cfg_attr(feature = "aoc-runner", aoc(day9, part2))]
+pub fn part2(input: &Input) -> Result<u64> {
+    let values = &input.numbers;
+    let wanted = first_invalid(values)?;
     for start_idx in 0..values.len() {
         for end_idx in (start_idx + 1)..values.len() {
             let window = &values[start_idx..=end_idx];
@@ -21,11 +36,74 @@ fn part2(input: &str) -> u64 {
                 break;
             }
             if sum == wanted {
-                return window.iter().min().unwrap() + window.iter().max().unwrap();
+                return Ok(window.iter().min().unwrap() + window.iter().max().unwrap());
             }
         }
     }
-    unreachable!()
+    Err(ParseError::input(
+        9,
+        format!("no contiguous range sums to {}", wanted),
+    ))
+}
+
+/// Both parts, reading the numbers one at a time. Until the first invalid number turns
+/// up, any of the numbers before it could be part of the range that sums to it, so they
+/// are all kept. After that, only the current candidate range is.
+pub fn stream(input: impl BufRead) -> Result<(u64, u64)> {
+    let mut lines = Lines::new(9, input);
+    let mut numbers = vec![];
+    let wanted = loop {
+        let number = lines.parse(int())?.ok_or_else(all_valid)?;
+        let len = numbers.len();
+        if len >= 25 && !sums(&numbers[len - 25..], number) {
+            numbers.push(number);
+            break number;
+        }
+        numbers.push(number);
+    };
+
+    // the range starting at the front of `range`, as far as it is below `wanted`
+    let mut range = VecDeque::new();
+    let mut sum = 0;
+    let mut numbers = numbers.into_iter();
+    loop {
+        if sum == wanted && range.len() >= 2 {
+            let min = range.iter().min().unwrap();
+            let max = range.iter().max().unwrap();
+            return Ok((wanted, min + max));
+        }
+        if sum > wanted {
+            sum -= range.pop_front().unwrap();
+            continue;
+        }
+        let number = match numbers.next() {
+            Some(number) => number,
+            None => match lines.parse(int())? {
+                Some(number) => number,
+                None => {
+                    return Err(ParseError::input(
+                        9,
+                        format!("no contiguous range sums to {}", wanted),
+                    ))
+                }
+            },
+        };
+        range.push_back(number);
+        sum += number;
+    }
+}
+
+fn first_invalid(values: &[u64]) -> Result<u64> {
+    for idx in 25..values.len() {
+        if !sums(&values[(idx - 25)..idx], values[idx]) {
+            return Ok(values[idx]);
+        }
+    }
+    Err(all_valid())
+}
+
+fn all_valid() -> ParseError {
+    ParseError::input(9, "every number is the sum of two of the 25 before it")
 }
 
 fn sums(slice: &[u64], val: u64) -> bool {
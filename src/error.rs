@@ -0,0 +1,125 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use crate::parser::{self, Parser};
+
+/// An error in a puzzle input, with the position where it was detected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: u8,
+    /// 1-based line number, or 0 if the error is about the input as a whole.
+    pub line: usize,
+    /// 1-based column, counted in chars.
+    pub column: usize,
+    pub message: String,
+}
+
+pub type Result<T, E = ParseError> = std::result::Result<T, E>;
+
+impl ParseError {
+    pub fn new(day: u8, line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            day,
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    /// An error about the input as a whole, e.g. when it has no solution.
+    pub fn input(day: u8, message: impl Into<String>) -> Self {
+        Self::new(day, 0, 0, message)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "day {}: {}", self.day, self.message)
+        } else {
+            write!(
+                f,
+                "day {}, line {}, column {}: {}",
+                self.day, self.line, self.column, self.message
+            )
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// The complete input of a day. Every `&str` handed to the methods below has to be
+/// a subslice of `text`, so that errors can be reported at the position of that fragment.
+#[derive(Clone, Copy)]
+pub(crate) struct Source<'a> {
+    day: u8,
+    text: &'a str,
+}
+
+impl<'a> Source<'a> {
+    pub(crate) fn new(day: u8, text: &'a str) -> Self {
+        Self { day, text }
+    }
+
+    /// An error located at the start of `fragment`.
+    pub(crate) fn error(&self, fragment: &str, message: impl Into<String>) -> ParseError {
+        let start = self.text.as_ptr() as usize;
+        let offset = (fragment.as_ptr() as usize).wrapping_sub(start);
+        if offset > self.text.len() {
+            // not part of the input, so we can't tell where it is
+            return self.error_input(message);
+        }
+        let before = &self.text[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+        let column = before[line_start..].chars().count() + 1;
+        ParseError::new(self.day, line, column, message)
+    }
+
+    /// The 1-based line that `fragment` starts on, or 0 if it isn't part of the input.
+    pub(crate) fn line(&self, fragment: &str) -> usize {
+        let offset = (fragment.as_ptr() as usize).wrapping_sub(self.text.as_ptr() as usize);
+        if offset > self.text.len() {
+            return 0;
+        }
+        self.text[..offset].matches('\n').count() + 1
+    }
+
+    /// An error about the input as a whole, e.g. when it has no solution.
+    pub(crate) fn error_input(&self, message: impl Into<String>) -> ParseError {
+        ParseError::input(self.day, message)
+    }
+
+    pub(crate) fn parse<T>(&self, fragment: &str) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        fragment
+            .parse()
+            .map_err(|e| self.error(fragment, format!("invalid number `{}`: {}", fragment, e)))
+    }
+
+    /// Runs `parser` on the whole input, which it has to consume completely.
+    pub(crate) fn run<T>(&self, parser: impl Parser<'a, T>) -> Result<T> {
+        parser::all(parser, self.text).map_err(|failure| self.error(failure.at, failure.message))
+    }
+}
+
+#[test]
+fn positions() {
+    let text = "abc\ndéf\n";
+    let src = Source::new(1, text);
+    assert_eq!(
+        src.error(&text[1..], "").to_string(),
+        "day 1, line 1, column 2: "
+    );
+    assert_eq!(src.error(&text[7..], "x").column, 3);
+    assert_eq!(src.error(&text[7..], "x").line, 2);
+    assert_eq!(src.error("elsewhere", "x").line, 0);
+    assert_eq!(src.line(&text[5..]), 2);
+    assert_eq!(src.line("elsewhere"), 0);
+    assert_eq!(
+        src.parse::<u8>(&text[4..5]).unwrap_err().to_string(),
+        "day 1, line 2, column 1: invalid number `d`: invalid digit found in string"
+    );
+}
@@ -0,0 +1,1104 @@
+//! Random puzzle inputs, for stress tests and benchmarks at sizes beyond the real inputs.
+//!
+//! Every generator is deterministic for a given `Rng` seed and produces an input that
+//! both parts of its day can solve, without a trailing newline.
+
+use std::{collections::BTreeSet, fmt::Write, ops::RangeInclusive};
+
+use fxhash::{FxHashMap, FxHashSet};
+
+/// SplitMix64. Not suitable for anything but test data.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `range`, which must not be empty.
+    pub fn range(&mut self, range: RangeInclusive<i64>) -> i64 {
+        let (low, high) = range.into_inner();
+        let len = (high - low) as u64 + 1;
+        low + (self.next_u64() % len) as i64
+    }
+
+    /// A number in `0..n`, with `n > 0`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// `true` with a probability of `p`.
+    pub fn chance(&mut self, p: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+
+    /// A random lowercase word.
+    pub fn word(&mut self, len: RangeInclusive<i64>) -> String {
+        let len = self.range(len);
+        (0..len)
+            .map(|_| (b'a' + self.below(26) as u8) as char)
+            .collect()
+    }
+}
+
+/// An input for `day` from `seed`. `size` scales the input, usually it is the
+/// number of lines or records; days with a fixed size ignore it. Returns `None`
+/// for days that have no generator.
+pub fn generate(day: u8, seed: u64, size: usize) -> Option<String> {
+    let rng = &mut Rng::new(seed);
+    let size = size.max(1);
+    Some(match day {
+        1 => day1(rng, size),
+        2 => day2(rng, size),
+        3 => day3(rng, 31, size),
+        4 => day4(rng, size),
+        5 => day5(rng, size.min(1000)),
+        6 => day6(rng, size),
+        7 => day7(rng, size),
+        8 => day8(rng, size),
+        9 => day9(rng, size),
+        10 => day10(rng, size),
+        11 => day11(rng, size, size),
+        12 => day12(rng, size),
+        13 => day13(rng, size),
+        14 => day14(rng, size, 9),
+        15 => day15(rng, size.min(20)),
+        16 => day16(rng, size.min(40), size),
+        17 => day17(rng, size),
+        18 => day18(rng, size),
+        19 => day19(rng, size),
+        20 => day20(rng, size.min(12)),
+        21 => day21(rng, size.min(20), size),
+        // Recursive Combat gets too slow with bigger decks than the real input's
+        22 => day22(rng, size.min(25)),
+        23 => day23(rng),
+        24 => day24(rng, size),
+        25 => day25(rng),
+        _ => return None,
+    })
+}
+
+fn join_lines(lines: impl IntoIterator<Item = impl ToString>) -> String {
+    lines
+        .into_iter()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Expense report entries with exactly one pair and one triple summing to 2020.
+pub fn day1(rng: &mut Rng, entries: usize) -> String {
+    loop {
+        // The planted entries are at least 600 and every other entry is at least 1500,
+        // so only sums of planted entries can be 2020.
+        let a = rng.range(600..=1420);
+        let b = rng.range(600..=710);
+        let c = rng.range(600..=710);
+        let planted = [a, 2020 - a, b, c, 2020 - b - c];
+        let distinct: FxHashSet<i64> = planted.iter().copied().collect();
+        let pairs = (0..5)
+            .flat_map(|i| (i + 1..5).map(move |j| (i, j)))
+            .filter(|&(i, j)| planted[i] + planted[j] == 2020)
+            .count();
+        let triples = (0..5)
+            .flat_map(|i| (i + 1..5).flat_map(move |j| (j + 1..5).map(move |k| (i, j, k))))
+            .filter(|&(i, j, k)| planted[i] + planted[j] + planted[k] == 2020)
+            .count();
+        if distinct.len() < 5 || pairs != 1 || triples != 1 {
+            continue;
+        }
+        let mut values = planted.to_vec();
+        while values.len() < entries {
+            values.push(rng.range(1500..=2019));
+        }
+        rng.shuffle(&mut values);
+        return join_lines(values);
+    }
+}
+
+/// Password policies, roughly half of them satisfied.
+pub fn day2(rng: &mut Rng, passwords: usize) -> String {
+    join_lines((0..passwords).map(|_| {
+        let letter = (b'a' + rng.below(6) as u8) as char;
+        let mut pass: Vec<char> = rng.word(4..=16).chars().collect();
+        let len = pass.len() as i64;
+        let from = rng.range(1..=len);
+        let to = rng.range(from..=len);
+        for _ in 0..rng.range(0..=to) {
+            let idx = rng.below(pass.len());
+            pass[idx] = letter;
+        }
+        format!(
+            "{}-{} {}: {}",
+            from,
+            to,
+            letter,
+            pass.iter().collect::<String>()
+        )
+    }))
+}
+
+/// A map of open squares and trees.
+pub fn day3(rng: &mut Rng, width: usize, height: usize) -> String {
+    join_lines((0..height).map(|_| {
+        (0..width)
+            .map(|_| if rng.chance(0.25) { '#' } else { '.' })
+            .collect::<String>()
+    }))
+}
+
+/// Passports with missing fields and valid and invalid values.
+pub fn day4(rng: &mut Rng, passports: usize) -> String {
+    let passports: Vec<String> = (0..passports)
+        .map(|_| {
+            let height = if rng.chance(0.5) {
+                format!("{}cm", rng.range(150..=193))
+            } else {
+                format!("{}in", rng.range(59..=76))
+            };
+            let eye_color = rng.choose(&["amb", "blu", "brn", "gry", "grn", "hzl", "oth"]);
+            // the name, a valid value and an invalid value of every field
+            let candidates = [
+                (
+                    "byr",
+                    rng.range(1920..=2002).to_string(),
+                    rng.range(1900..=1919).to_string(),
+                ),
+                (
+                    "iyr",
+                    rng.range(2010..=2020).to_string(),
+                    rng.range(2021..=2030).to_string(),
+                ),
+                (
+                    "eyr",
+                    rng.range(2020..=2030).to_string(),
+                    rng.range(2000..=2019).to_string(),
+                ),
+                ("hgt", height, rng.range(50..=200).to_string()),
+                (
+                    "hcl",
+                    format!("#{:06x}", rng.next_u64() & 0xff_ffff),
+                    format!("{:06x}", rng.next_u64() & 0xff_ffff),
+                ),
+                ("ecl", eye_color.to_string(), rng.word(3..=3)),
+                (
+                    "pid",
+                    format!("{:09}", rng.range(0..=999_999_999)),
+                    rng.range(0..=99_999_999).to_string(),
+                ),
+                ("cid", rng.range(1..=999).to_string(), String::new()),
+            ];
+            let valid = rng.chance(0.5);
+            let mut fields = vec![];
+            for (name, good, bad) in candidates {
+                if rng.chance(0.95) {
+                    let value = if valid || rng.chance(0.8) { good } else { bad };
+                    fields.push(format!("{}:{}", name, value));
+                }
+            }
+            rng.shuffle(&mut fields);
+            let mut passport = String::new();
+            for (idx, field) in fields.iter().enumerate() {
+                if idx > 0 {
+                    passport.push(if rng.chance(0.3) { '\n' } else { ' ' });
+                }
+                passport.push_str(field);
+            }
+            passport
+        })
+        .collect();
+    passports.join("\n\n")
+}
+
+/// Boarding passes for `seats` consecutive seats, one of them missing.
+pub fn day5(rng: &mut Rng, seats: usize) -> String {
+    let seats = seats.clamp(3, 1000) as i64;
+    let first = rng.range(1..=1023 - seats);
+    let missing = rng.range(first + 1..=first + seats - 2);
+    let mut ids: Vec<i64> = (first..first + seats).filter(|&id| id != missing).collect();
+    rng.shuffle(&mut ids);
+    join_lines(ids.into_iter().map(|id| {
+        (0..10)
+            .rev()
+            .map(|bit| {
+                let set = (id >> bit) & 1 == 1;
+                match (bit >= 3, set) {
+                    (true, false) => 'F',
+                    (true, true) => 'B',
+                    (false, false) => 'L',
+                    (false, true) => 'R',
+                }
+            })
+            .collect::<String>()
+    }))
+}
+
+/// Groups of customs declaration answers.
+pub fn day6(rng: &mut Rng, groups: usize) -> String {
+    let groups: Vec<String> = (0..groups)
+        .map(|_| {
+            let common = rng.word(0..=5);
+            join_lines((0..rng.range(1..=5)).map(|_| {
+                let mut answers: BTreeSet<char> = common.chars().collect();
+                answers.extend(rng.word(0..=8).chars());
+                if answers.is_empty() {
+                    answers.insert('a');
+                }
+                let mut answers: Vec<char> = answers.into_iter().collect();
+                rng.shuffle(&mut answers);
+                answers.into_iter().collect::<String>()
+            }))
+        })
+        .collect();
+    groups.join("\n\n")
+}
+
+/// Acyclic bag rules around a shiny gold bag. Bags only contain bags from deeper
+/// levels, which keeps the number of bags inside shiny gold small enough to count.
+pub fn day7(rng: &mut Rng, bags: usize) -> String {
+    const ADJECTIVES: &[&str] = &[
+        "light", "dark", "bright", "muted", "shiny", "faded", "dotted", "vibrant", "pale", "dim",
+        "clear", "drab", "dull", "mirrored", "plaid", "posh", "striped", "wavy",
+    ];
+    const COLORS: &[&str] = &[
+        "red",
+        "orange",
+        "white",
+        "yellow",
+        "gold",
+        "olive",
+        "plum",
+        "blue",
+        "black",
+        "aqua",
+        "beige",
+        "bronze",
+        "brown",
+        "coral",
+        "crimson",
+        "cyan",
+        "fuchsia",
+        "gray",
+        "green",
+        "indigo",
+        "lavender",
+        "lime",
+        "magenta",
+        "maroon",
+        "purple",
+        "salmon",
+        "silver",
+        "tan",
+        "teal",
+        "tomato",
+        "turquoise",
+        "violet",
+    ];
+    const LEVELS: usize = 6;
+    let bags = bags.clamp(2, ADJECTIVES.len() * COLORS.len());
+    let mut names = vec!["shiny gold".to_string()];
+    let mut used: FxHashSet<String> = names.iter().cloned().collect();
+    while names.len() < bags {
+        let name = format!("{} {}", rng.choose(ADJECTIVES), rng.choose(COLORS));
+        if used.insert(name.clone()) {
+            names.push(name);
+        }
+    }
+    // shiny gold is in the middle, so that it has bags around and inside it
+    let levels: Vec<usize> = (0..bags)
+        .map(|idx| {
+            if idx == 0 {
+                LEVELS / 2
+            } else {
+                rng.below(LEVELS)
+            }
+        })
+        .collect();
+    let mut rules: Vec<String> = (0..bags)
+        .map(|outer| {
+            let deeper: Vec<usize> = (0..bags).filter(|&b| levels[b] > levels[outer]).collect();
+            let mut children = vec![];
+            if !deeper.is_empty() {
+                for _ in 0..rng.range(0..=3) {
+                    let child = *rng.choose(&deeper);
+                    if !children.iter().any(|&(_, c)| c == child) {
+                        children.push((rng.range(1..=5), child));
+                    }
+                }
+            }
+            if children.is_empty() {
+                return format!("{} bags contain no other bags.", names[outer]);
+            }
+            let children: Vec<String> = children
+                .iter()
+                .map(|&(count, child)| {
+                    let plural = if count == 1 { "" } else { "s" };
+                    format!("{} {} bag{}", count, names[child], plural)
+                })
+                .collect();
+            format!("{} bags contain {}.", names[outer], children.join(", "))
+        })
+        .collect();
+    rng.shuffle(&mut rules);
+    join_lines(rules)
+}
+
+/// A boot code program that loops because of exactly one corrupted instruction.
+/// Flipping any other `jmp` or `nop` doesn't make it terminate.
+pub fn day8(rng: &mut Rng, instructions: usize) -> String {
+    use crate::day8::{Op, Vm};
+
+    let len = instructions.max(4) as i64;
+    loop {
+        // Only forward jumps, so the fixed program terminates.
+        let mut program: Vec<Op> = (0..len)
+            .map(|idx| match rng.below(3) {
+                0 => Op::Acc(rng.range(-50..=50) as i32),
+                1 => Op::Noop(rng.range(-idx..=len - idx) as i32),
+                _ => Op::Jmp(rng.range(1..=(len - idx).min(8)) as i32),
+            })
+            .collect();
+        let mut path = vec![];
+        let mut ip = 0;
+        while ip < len {
+            path.push(ip);
+            ip += match program[ip as usize] {
+                Op::Jmp(offset) => offset as i64,
+                _ => 1,
+            };
+        }
+        // A backward jump to an instruction on the path loops forever.
+        if path.len() < 2 {
+            continue;
+        }
+        let corrupted = path[rng.range(1..=path.len() as i64 - 1) as usize];
+        let target = *rng.choose(&path[..path.iter().position(|&p| p == corrupted).unwrap()]);
+        program[corrupted as usize] = Op::Jmp((target - corrupted) as i32);
+
+        let terminates = |program: &[Op]| !Vm::new(program).has_loop();
+        let fixes = (0..program.len())
+            .filter(|&idx| {
+                let mut flipped = program.clone();
+                flipped[idx] = match program[idx] {
+                    Op::Jmp(offset) => Op::Noop(offset),
+                    Op::Noop(offset) => Op::Jmp(offset),
+                    Op::Acc(_) => return false,
+                };
+                terminates(&flipped)
+            })
+            .count();
+        if fixes != 1 {
+            continue;
+        }
+        return join_lines(program);
+    }
+}
+
+/// XMAS data with a preamble of 25 and one number that isn't the sum of two of the
+/// 25 before it, but of a contiguous range of earlier numbers.
+pub fn day9(rng: &mut Rng, numbers: usize) -> String {
+    const PREAMBLE: usize = 25;
+    let numbers = numbers.max(PREAMBLE + 10);
+    loop {
+        let mut values: Vec<u64> = vec![];
+        while values.len() < PREAMBLE {
+            let value = rng.range(1..=50) as u64;
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        }
+        let invalid_at = rng.range(PREAMBLE as i64 + 3..=numbers as i64 - 1) as usize;
+        while values.len() < numbers {
+            let window = &values[values.len() - PREAMBLE..];
+            if values.len() == invalid_at {
+                let start = rng.below(values.len() - 2);
+                let end =
+                    rng.range(start as i64 + 2..=(values.len() as i64).min(start as i64 + 10));
+                let sum: u64 = values[start..end as usize].iter().sum();
+                if window.iter().any(|a| window.iter().any(|b| a + b == sum)) {
+                    break;
+                }
+                values.push(sum);
+                continue;
+            }
+            // Summing small numbers keeps the numbers from growing too fast.
+            let mut smallest = window.to_vec();
+            smallest.sort_unstable();
+            smallest.dedup();
+            let a = smallest[rng.below(smallest.len().min(4))];
+            let b = smallest[rng.below(smallest.len().min(4))];
+            if a != b {
+                values.push(a + b);
+            }
+        }
+        if values.len() == numbers {
+            return join_lines(values);
+        }
+    }
+}
+
+/// Adapters with differences of 1 and 3 jolts, in runs like those in the real
+/// inputs, so that the number of arrangements fits into a `u64`.
+pub fn day10(rng: &mut Rng, adapters: usize) -> String {
+    let mut jolts = vec![];
+    let mut jolt = 0;
+    let mut arrangements: u128 = 1;
+    while jolts.len() < adapters {
+        let run = (rng.range(0..=4) as usize).min(adapters - jolts.len());
+        // the arrangements of a run of n steps of 1 are the tribonacci numbers
+        let factor = [1, 1, 2, 4, 7][run];
+        let run = if arrangements * factor > u64::MAX as u128 / 7 {
+            run.min(1)
+        } else {
+            arrangements *= factor;
+            run
+        };
+        for _ in 0..run {
+            jolt += 1;
+            jolts.push(jolt);
+        }
+        if jolts.len() < adapters {
+            jolt += 3;
+            jolts.push(jolt);
+        }
+    }
+    rng.shuffle(&mut jolts);
+    join_lines(jolts)
+}
+
+/// A seat layout of empty seats and floor in which nobody moves anymore after a while,
+/// with the rules of both parts.
+pub fn day11(rng: &mut Rng, width: usize, height: usize) -> String {
+    use crate::{day11, grid::Grid};
+
+    let mut layout = Grid::from_fn(width, height, |_, _| {
+        if rng.chance(0.8) {
+            day11::Position::Seat
+        } else {
+            day11::Position::Floor
+        }
+    });
+    // Some layouts never settle. Seats that still move after the waves from the
+    // edges should have reached everything become floor, until both parts settle.
+    // Every round removes at least one seat, so this ends.
+    let generations = 2 * (width + height) + 10;
+    loop {
+        let unsettled: Vec<_> = [1, 2]
+            .into_iter()
+            .flat_map(|part| day11::unsettled(&layout, part, generations))
+            .collect();
+        if unsettled.is_empty() {
+            return layout.to_string().trim_end().to_string();
+        }
+        for pos in unsettled {
+            layout[pos] = day11::Position::Floor;
+        }
+    }
+}
+
+/// Navigation instructions.
+pub fn day12(rng: &mut Rng, instructions: usize) -> String {
+    join_lines((0..instructions).map(|_| {
+        let action = *rng.choose(&['N', 'S', 'E', 'W', 'L', 'R', 'F', 'F']);
+        let value = match action {
+            'L' | 'R' => *rng.choose(&[90, 180, 270]),
+            _ => rng.range(1..=100),
+        };
+        format!("{}{}", action, value)
+    }))
+}
+
+/// A bus schedule with `slots` entries. The bus ids are distinct primes, so that the
+/// contest in part 2 has a solution.
+pub fn day13(rng: &mut Rng, slots: usize) -> String {
+    let primes: Vec<u64> = (7..1000u64)
+        .filter(|&n| (2..n).take_while(|d| d * d <= n).all(|d| n % d != 0))
+        .collect();
+    let slots = slots.max(2);
+    let mut buses = vec![None; slots];
+    let mut product: u64 = 1;
+    let mut used = FxHashSet::default();
+    let mut positions: Vec<usize> = (1..slots).collect();
+    rng.shuffle(&mut positions);
+    positions.insert(0, 0);
+    for position in positions {
+        let prime = *rng.choose(&primes);
+        // keeps the answer of part 2 far away from overflowing
+        if used.contains(&prime) || product.saturating_mul(prime) > 1 << 55 {
+            continue;
+        }
+        if position == 0 || rng.chance(0.3) {
+            used.insert(prime);
+            product *= prime;
+            buses[position] = Some(prime);
+        }
+    }
+    let schedule: Vec<String> = buses
+        .iter()
+        .map(|bus| bus.map_or("x".to_string(), |id| id.to_string()))
+        .collect();
+    format!("{}\n{}", rng.range(1..=10_000_000), schedule.join(","))
+}
+
+/// A docking program with masks that have at most `floating` `X`s.
+pub fn day14(rng: &mut Rng, writes: usize, floating: usize) -> String {
+    let mut lines = vec![];
+    let mut count = 0;
+    while count < writes {
+        let mut mask: Vec<char> = (0..36)
+            .map(|_| if rng.chance(0.5) { '0' } else { '1' })
+            .collect();
+        for _ in 0..rng.range(0..=floating as i64) {
+            let idx = rng.below(36);
+            mask[idx] = 'X';
+        }
+        lines.push(format!("mask = {}", mask.iter().collect::<String>()));
+        for _ in 0..rng.range(1..=5) {
+            lines.push(format!(
+                "mem[{}] = {}",
+                rng.range(0..=65535),
+                rng.range(0..=(1 << 36) - 1)
+            ));
+            count += 1;
+        }
+    }
+    join_lines(lines)
+}
+
+/// Distinct starting numbers for the memory game.
+pub fn day15(rng: &mut Rng, numbers: usize) -> String {
+    let mut starting: Vec<i64> = vec![];
+    while starting.len() < numbers.max(1) {
+        let n = rng.range(0..=30);
+        if !starting.contains(&n) {
+            starting.push(n);
+        }
+    }
+    starting
+        .iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Ticket notes where the field order can be found by elimination.
+///
+/// Field `k` accepts values from 1 to 100 * (k + 1), and the column of field `k` has a
+/// value above 100 * k. So column `k` fits fields `k` and above, and the last field
+/// has only one column it fits.
+pub fn day16(rng: &mut Rng, fields: usize, tickets: usize) -> String {
+    let fields = fields.max(1);
+    let mut names = vec![];
+    let mut used = FxHashSet::default();
+    while names.len() < fields {
+        let prefix = if names.len() < 6 { "departure " } else { "" };
+        let name = format!("{}{}", prefix, rng.word(3..=8));
+        if used.insert(name.clone()) {
+            names.push(name);
+        }
+    }
+    rng.shuffle(&mut names);
+    let limit = |field: usize| 100 * (field as i64 + 1);
+    let mut rules: Vec<String> = (0..fields)
+        .map(|field| {
+            let split = rng.range(1..=limit(field) - 1);
+            format!(
+                "{}: 1-{} or {}-{}",
+                names[field],
+                split,
+                split + 1,
+                limit(field)
+            )
+        })
+        .collect();
+    rng.shuffle(&mut rules);
+    // columns[c] is the field in column c
+    let mut columns: Vec<usize> = (0..fields).collect();
+    rng.shuffle(&mut columns);
+
+    let mut nearby: Vec<Vec<i64>> = (0..tickets.max(1))
+        .map(|_| {
+            columns
+                .iter()
+                .map(|&field| rng.range(1..=limit(field)))
+                .collect()
+        })
+        .collect();
+    for (column, &field) in columns.iter().enumerate() {
+        let ticket = rng.below(nearby.len());
+        nearby[ticket][column] = rng.range(limit(field) - 99..=limit(field));
+    }
+    // invalid tickets are ignored in part 2
+    let invalid = limit(fields) + 1..=limit(fields) + 100;
+    for _ in 0..tickets / 4 {
+        let mut ticket = nearby[rng.below(nearby.len())].clone();
+        ticket[rng.below(fields)] = rng.range(invalid.clone());
+        nearby.insert(rng.below(nearby.len() + 1), ticket);
+    }
+    let ticket = |values: &[i64]| {
+        values
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    // small enough that the product of the departure fields fits into an `i64`
+    let mine: Vec<i64> = (0..fields).map(|_| rng.range(1..=1000)).collect();
+    format!(
+        "{}\n\nyour ticket:\n{}\n\nnearby tickets:\n{}",
+        join_lines(rules),
+        ticket(&mine),
+        join_lines(nearby.iter().map(|t| ticket(t)))
+    )
+}
+
+/// An initial slice of active and inactive cubes.
+pub fn day17(rng: &mut Rng, size: usize) -> String {
+    join_lines((0..size).map(|_| {
+        (0..size)
+            .map(|_| if rng.chance(0.4) { '#' } else { '.' })
+            .collect::<String>()
+    }))
+}
+
+/// Homework expressions with single digits and nested parentheses.
+pub fn day18(rng: &mut Rng, expressions: usize) -> String {
+    fn expression(rng: &mut Rng, depth: usize) -> String {
+        let mut out = String::new();
+        for idx in 0..rng.range(2..=4) {
+            if idx > 0 {
+                out.push_str(if rng.chance(0.5) { " + " } else { " * " });
+            }
+            if depth < 2 && rng.chance(0.25) {
+                write!(out, "({})", expression(rng, depth + 1)).unwrap();
+            } else {
+                write!(out, "{}", rng.range(1..=9)).unwrap();
+            }
+        }
+        out
+    }
+    join_lines((0..expressions).map(|_| expression(rng, 0)))
+}
+
+/// Message rules in the shape of the real input: `0: 8 11`, `8: 42` and `11: 42 31`,
+/// where 42 and 31 match disjoint sets of words of the same length.
+pub fn day19(rng: &mut Rng, messages: usize) -> String {
+    #[derive(Clone)]
+    enum Rule {
+        Letter(char),
+        Seq(Vec<usize>),
+        Or(Vec<usize>, Vec<usize>),
+    }
+    // rules[idx] for every rule number; the numbers are assigned up front
+    let mut free: Vec<usize> = (1..60).filter(|n| ![8, 11, 31, 42].contains(n)).collect();
+    rng.shuffle(&mut free);
+    let mut rules: FxHashMap<usize, Rule> = FxHashMap::default();
+    let (a, b) = (free.pop().unwrap(), free.pop().unwrap());
+    rules.insert(a, Rule::Letter('a'));
+    rules.insert(b, Rule::Letter('b'));
+    // every level matches words twice as long as the level before it
+    let mut level = vec![a, b];
+    for _ in 0..2 {
+        let mut next = vec![];
+        for _ in 0..4 {
+            let mut pair = || vec![*rng.choose(&level), *rng.choose(&level)];
+            let rule = Rule::Or(pair(), pair());
+            let idx = free.pop().unwrap();
+            rules.insert(idx, rule);
+            next.push(idx);
+        }
+        level = next;
+    }
+    let first = *rng.choose(&level);
+    let second = *rng.choose(&level);
+    rules.insert(42, Rule::Seq(vec![a, first]));
+    rules.insert(31, Rule::Seq(vec![b, second]));
+    rules.insert(8, Rule::Seq(vec![42]));
+    rules.insert(11, Rule::Seq(vec![42, 31]));
+    rules.insert(0, Rule::Seq(vec![8, 11]));
+    // rules have to be numbered without gaps
+    for idx in free {
+        rules.insert(idx, Rule::Letter(if rng.chance(0.5) { 'a' } else { 'b' }));
+    }
+
+    fn sample(rng: &mut Rng, rules: &FxHashMap<usize, Rule>, rule: usize, out: &mut String) {
+        let seq = match &rules[&rule] {
+            Rule::Letter(letter) => return out.push(*letter),
+            Rule::Seq(seq) => seq,
+            Rule::Or(first, second) => {
+                if rng.chance(0.5) {
+                    first
+                } else {
+                    second
+                }
+            }
+        };
+        for &rule in seq {
+            sample(rng, rules, rule, out);
+        }
+    }
+    let messages: Vec<String> = (0..messages)
+        .map(|_| {
+            let mut message = String::new();
+            // part 2 matches 42 n times and then 31 m times, with 0 < m < n
+            let thirty_ones = rng.range(1..=3);
+            let forty_twos = rng.range(1..=5);
+            for _ in 0..forty_twos {
+                sample(rng, &rules, 42, &mut message);
+            }
+            for _ in 0..thirty_ones {
+                sample(rng, &rules, 31, &mut message);
+            }
+            if rng.chance(0.2) {
+                let idx = rng.below(message.len());
+                let flipped = if message.as_bytes()[idx] == b'a' {
+                    "b"
+                } else {
+                    "a"
+                };
+                message.replace_range(idx..=idx, flipped);
+            }
+            message
+        })
+        .collect();
+
+    let mut lines: Vec<String> = rules
+        .iter()
+        .map(|(idx, rule)| {
+            let seq = |seq: &[usize]| {
+                seq.iter()
+                    .map(|r| r.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            };
+            match rule {
+                Rule::Letter(letter) => format!("{}: \"{}\"", idx, letter),
+                Rule::Seq(s) => format!("{}: {}", idx, seq(s)),
+                Rule::Or(first, second) => format!("{}: {} | {}", idx, seq(first), seq(second)),
+            }
+        })
+        .collect();
+    lines.sort();
+    format!("{}\n\n{}", join_lines(lines), join_lines(messages))
+}
+
+/// A square picture of `side` * `side` tiles, cut into rotated and flipped tiles.
+/// All tile edges are distinct, so that only matching edges line up. The picture
+/// contains a few sea monsters.
+///
+/// There are only about 500 distinct edges, so `side` can be at most 12.
+pub fn day20(rng: &mut Rng, side: usize) -> String {
+    assert!(side <= 12, "at most 12 tiles per side are supported");
+    const TILE: usize = 10;
+    const MONSTER: [&str; 3] = [
+        "                  # ",
+        "#    ##    ##    ###",
+        " #  #  #  #  #  #   ",
+    ];
+    let side = side.max(2);
+    let inner = TILE - 2;
+    let len = side * inner;
+    let mut picture: Vec<Vec<bool>> = (0..len)
+        .map(|_| (0..len).map(|_| rng.chance(0.3)).collect())
+        .collect();
+    if len >= MONSTER[0].len() {
+        for _ in 0..side {
+            let x = rng.below(len - MONSTER[0].len() + 1);
+            let y = rng.below(len - MONSTER.len() + 1);
+            for (dy, line) in MONSTER.iter().enumerate() {
+                for (dx, char) in line.chars().enumerate() {
+                    if char == '#' {
+                        picture[y + dy][x + dx] = true;
+                    }
+                }
+            }
+        }
+    }
+
+    // The corners of the tiles are shared by up to four tiles, the edges by two.
+    let corners: Vec<Vec<bool>> = (0..=side)
+        .map(|_| (0..=side).map(|_| rng.chance(0.5)).collect())
+        .collect();
+    let mut seen = FxHashSet::default();
+    let mut edge = |rng: &mut Rng, from: bool, to: bool| loop {
+        let mut edge = vec![from];
+        edge.extend((0..TILE - 2).map(|_| rng.chance(0.5)));
+        edge.push(to);
+        let reversed: Vec<bool> = edge.iter().rev().copied().collect();
+        if edge != reversed && !seen.contains(&edge) && !seen.contains(&reversed) {
+            seen.insert(edge.clone());
+            return edge;
+        }
+    };
+    // horizontal[y][x] is the top edge of the tile at (x, y), from left to right
+    let horizontal: Vec<Vec<Vec<bool>>> = (0..=side)
+        .map(|y| {
+            (0..side)
+                .map(|x| edge(rng, corners[y][x], corners[y][x + 1]))
+                .collect()
+        })
+        .collect();
+    // vertical[y][x] is the left edge of the tile at (x, y), from top to bottom
+    let vertical: Vec<Vec<Vec<bool>>> = (0..side)
+        .map(|y| {
+            (0..=side)
+                .map(|x| edge(rng, corners[y][x], corners[y + 1][x]))
+                .collect()
+        })
+        .collect();
+
+    let mut ids = FxHashSet::default();
+    let mut tiles = vec![];
+    for y in 0..side {
+        for x in 0..side {
+            let mut tile = vec![vec![false; TILE]; TILE];
+            for (i, row) in tile.iter_mut().enumerate() {
+                row[0] = vertical[y][x][i];
+                row[TILE - 1] = vertical[y][x + 1][i];
+            }
+            tile[0] = horizontal[y][x].clone();
+            tile[TILE - 1] = horizontal[y + 1][x].clone();
+            for ty in 0..inner {
+                for tx in 0..inner {
+                    tile[ty + 1][tx + 1] = picture[y * inner + ty][x * inner + tx];
+                }
+            }
+            for _ in 0..rng.below(4) {
+                // rotate clockwise
+                tile = (0..TILE)
+                    .map(|row| (0..TILE).map(|col| tile[TILE - 1 - col][row]).collect())
+                    .collect();
+            }
+            if rng.chance(0.5) {
+                tile.iter_mut().for_each(|row| row.reverse());
+            }
+            let id = loop {
+                let id = rng.range(1000..=9999);
+                if ids.insert(id) {
+                    break id;
+                }
+            };
+            let rows = tile.iter().map(|row| {
+                row.iter()
+                    .map(|&black| if black { '#' } else { '.' })
+                    .collect::<String>()
+            });
+            tiles.push(format!("Tile {}:\n{}", id, join_lines(rows)));
+        }
+    }
+    rng.shuffle(&mut tiles);
+    tiles.join("\n\n")
+}
+
+/// Foods with ingredient lists and incomplete allergen lists, from which each of the
+/// `allergens` allergens can be tied to one ingredient by elimination.
+pub fn day21(rng: &mut Rng, allergens: usize, foods: usize) -> String {
+    const ALLERGENS: &[&str] = &[
+        "celery",
+        "dairy",
+        "eggs",
+        "fish",
+        "gluten",
+        "lupin",
+        "mustard",
+        "nuts",
+        "peanuts",
+        "sesame",
+        "shellfish",
+        "soy",
+        "sulphites",
+        "wheat",
+    ];
+    let mut names: Vec<String> = ALLERGENS.iter().map(|a| a.to_string()).collect();
+    rng.shuffle(&mut names);
+    while names.len() < allergens {
+        names.push(rng.word(5..=9));
+    }
+    names.truncate(allergens.max(1));
+    names.sort();
+    names.dedup();
+    let allergens = names;
+
+    let mut used = FxHashSet::default();
+    let mut ingredients = vec![];
+    while ingredients.len() < allergens.len() * 4 + 10 {
+        let ingredient = rng.word(3..=8);
+        if used.insert(ingredient.clone()) {
+            ingredients.push(ingredient);
+        }
+    }
+    // ingredient `i` contains allergen `i`
+    let mut menu: Vec<(Vec<usize>, Vec<usize>)> = vec![];
+    let solved = |menu: &[(Vec<usize>, Vec<usize>)]| {
+        let mut candidates: Vec<Option<FxHashSet<usize>>> = vec![None; allergens.len()];
+        for (food, listed) in menu {
+            for &allergen in listed {
+                let food: FxHashSet<usize> = food.iter().copied().collect();
+                candidates[allergen] = Some(match &candidates[allergen] {
+                    Some(c) => c.intersection(&food).copied().collect(),
+                    None => food,
+                });
+            }
+        }
+        let mut candidates: Vec<FxHashSet<usize>> = candidates
+            .into_iter()
+            .map(Option::unwrap_or_default)
+            .collect();
+        let mut done = FxHashSet::default();
+        while let Some(idx) =
+            (0..candidates.len()).find(|&idx| candidates[idx].len() == 1 && !done.contains(&idx))
+        {
+            done.insert(idx);
+            let ingredient = *candidates[idx].iter().next().unwrap();
+            for (other, c) in candidates.iter_mut().enumerate() {
+                if other != idx {
+                    c.remove(&ingredient);
+                }
+            }
+        }
+        done.len() == allergens.len()
+    };
+    while menu.len() < foods || !solved(&menu) {
+        let listed: Vec<usize> = (0..rng.range(1..=3))
+            .map(|_| rng.below(allergens.len()))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let mut food: BTreeSet<usize> = listed.iter().copied().collect();
+        for _ in 0..rng.range(3..=12) {
+            food.insert(rng.below(ingredients.len()));
+        }
+        let mut food: Vec<usize> = food.into_iter().collect();
+        rng.shuffle(&mut food);
+        menu.push((food, listed));
+    }
+    join_lines(menu.iter().map(|(food, listed)| {
+        let food: Vec<&str> = food.iter().map(|&i| ingredients[i].as_str()).collect();
+        let listed: Vec<&str> = listed.iter().map(|&a| allergens[a].as_str()).collect();
+        format!("{} (contains {})", food.join(" "), listed.join(", "))
+    }))
+}
+
+/// Two decks of `cards` cards each, with distinct values.
+pub fn day22(rng: &mut Rng, cards: usize) -> String {
+    let cards = cards.max(1);
+    let mut deck: Vec<usize> = (1..=cards * 2).collect();
+    rng.shuffle(&mut deck);
+    let (first, second) = deck.split_at(cards);
+    format!(
+        "Player 1:\n{}\n\nPlayer 2:\n{}",
+        join_lines(first),
+        join_lines(second)
+    )
+}
+
+/// The labels 1 to 9 in a random order.
+pub fn day23(rng: &mut Rng) -> String {
+    let mut cups: Vec<usize> = (1..=9).collect();
+    rng.shuffle(&mut cups);
+    cups.iter().map(|c| c.to_string()).collect()
+}
+
+/// Paths to the tiles to flip.
+pub fn day24(rng: &mut Rng, tiles: usize) -> String {
+    join_lines((0..tiles).map(|_| {
+        (0..rng.range(10..=25))
+            .map(|_| *rng.choose(&["e", "se", "sw", "w", "nw", "ne"]))
+            .collect::<String>()
+    }))
+}
+
+/// The public keys of the card and the door.
+pub fn day25(rng: &mut Rng) -> String {
+    use crate::number_theory::modpow;
+
+    let key = |rng: &mut Rng| modpow(7, rng.range(1..=20201226) as u64, 20201227);
+    format!("{}\n{}", key(rng), key(rng))
+}
+
+#[test]
+fn solvable() {
+    use crate::solution;
+
+    for entry in solution::registry() {
+        for seed in 0..3 {
+            let input = generate(entry.day, seed, 10).unwrap();
+            let parsed = entry
+                .parse(&input)
+                .unwrap_or_else(|e| panic!("seed {}: {}\n{}", seed, e, input));
+            for &part in entry.parts {
+                // these take the same time for every input, and the real inputs cover them
+                if matches!((entry.day, part), (15, 2) | (23, 2)) {
+                    continue;
+                }
+                entry
+                    .solve(&parsed, part)
+                    .unwrap_or_else(|e| panic!("seed {}: {}\n{}", seed, e, input));
+            }
+        }
+    }
+}
+
+#[test]
+fn deterministic() {
+    assert_eq!(generate(20, 7, 3), generate(20, 7, 3));
+    assert_ne!(generate(20, 7, 3), generate(20, 8, 3));
+    let mut rng = Rng::new(1);
+    assert!((0..1000).all(|_| (3..=5).contains(&rng.range(3..=5))));
+}
+#[test]
+fn default_size_is_fast() {
+    use std::time::Duration;
+
+    use crate::{
+        runner::{self, Config, Job, Outcome},
+        solution,
+    };
+
+    // the simulations that don't end for every input
+    let jobs = [11, 22]
+        .into_iter()
+        .flat_map(|day| {
+            let entry = solution::find(2020, day).unwrap();
+            (0..4).map(move |seed| Job {
+                entry,
+                parts: entry.parts.to_vec(),
+                input: generate(day, seed, 100).unwrap(),
+            })
+        })
+        .collect();
+    let config = Config {
+        timeout: Duration::from_secs(30),
+        ..Config::default()
+    };
+    for report in runner::run(jobs, &config) {
+        assert!(
+            matches!(report.outcome, Outcome::Solved(_)),
+            "day {} part {}: {}",
+            report.day,
+            report.part,
+            report.outcome
+        );
+    }
+}
@@ -0,0 +1,305 @@
+//! A rectangular grid of cells, like the character maps of days 3, 11 and 20.
+
+use std::{
+    fmt,
+    ops::{Index, IndexMut},
+};
+
+use crate::{
+    error::{Result, Source},
+    parser::{Failure, Parser},
+};
+
+/// The offsets to the four orthogonal neighbors, clockwise from the top.
+pub const DIRECTIONS4: [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+/// The offsets to the orthogonal and diagonal neighbors, clockwise from the top.
+pub const DIRECTIONS8: [(isize, isize); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+/// A cell that is written as a single character.
+pub trait Cell: Sized {
+    fn from_char(char: char) -> Option<Self>;
+    fn to_char(&self) -> char;
+}
+
+/// `#` and `.`, e.g. trees and open ground.
+impl Cell for bool {
+    fn from_char(char: char) -> Option<Self> {
+        match char {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        }
+    }
+
+    fn to_char(&self) -> char {
+        if *self {
+            '#'
+        } else {
+            '.'
+        }
+    }
+}
+
+/// Cells stored row by row. Positions are `(x, y)` with `(0, 0)` at the top left.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Panics if there aren't `width * height` cells.
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Self {
+        assert_eq!(cells.len(), width * height, "wrong number of cells");
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    pub fn from_fn(width: usize, height: usize, mut cell: impl FnMut(usize, usize) -> T) -> Self {
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| cell(x, y))
+            .collect();
+        Self::new(width, height, cells)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if x < self.width && y < self.height {
+            Some(&self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if x < self.width && y < self.height {
+            Some(&mut self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// The cell at a position, as if the grid repeated endlessly in both directions.
+    pub fn get_wrapping(&self, x: usize, y: usize) -> &T {
+        &self[(x % self.width, y % self.height)]
+    }
+
+    /// The position one step from `(x, y)` in `direction`, if it is in the grid.
+    pub fn step(
+        &self,
+        (x, y): (usize, usize),
+        direction: (isize, isize),
+    ) -> Option<(usize, usize)> {
+        let x = x.checked_add_signed(direction.0)?;
+        let y = y.checked_add_signed(direction.1)?;
+        (x < self.width && y < self.height).then_some((x, y))
+    }
+
+    /// The orthogonal neighbors of `(x, y)` that are in the grid.
+    pub fn neighbors4(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        DIRECTIONS4
+            .iter()
+            .filter_map(move |&direction| self.step((x, y), direction))
+    }
+
+    /// The orthogonal and diagonal neighbors of `(x, y)` that are in the grid.
+    pub fn neighbors8(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        DIRECTIONS8
+            .iter()
+            .filter_map(move |&direction| self.step((x, y), direction))
+    }
+
+    /// The cells seen when looking from `(x, y)` in `direction`, up to the edge of the grid.
+    /// The cell at `(x, y)` itself is not included.
+    pub fn ray(
+        &self,
+        x: usize,
+        y: usize,
+        direction: (isize, isize),
+    ) -> impl Iterator<Item = &T> + '_ {
+        let mut position = (x, y);
+        std::iter::from_fn(move || {
+            position = self.step(position, direction)?;
+            Some(&self[position])
+        })
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = &T> + '_ {
+        self.cells.iter()
+    }
+
+    /// All positions, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> + 'static {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+        self.cells.chunks(self.width)
+    }
+
+    pub fn column(&self, x: usize) -> impl DoubleEndedIterator<Item = &T> + '_ {
+        self.cells[x..].iter().step_by(self.width)
+    }
+
+    pub fn map<U>(&self, mut f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid::new(
+            self.width,
+            self.height,
+            self.cells.iter().map(&mut f).collect(),
+        )
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn filled(width: usize, height: usize, value: T) -> Self {
+        Self::new(width, height, vec![value; width * height])
+    }
+
+    /// The grid turned a quarter clockwise.
+    pub fn rotate(&self) -> Self {
+        Self::from_fn(self.height, self.width, |x, y| {
+            self[(y, self.height - 1 - x)].clone()
+        })
+    }
+
+    /// The grid mirrored around a vertical axis.
+    pub fn flip(&self) -> Self {
+        Self::from_fn(self.width, self.height, |x, y| {
+            self[(self.width - 1 - x, y)].clone()
+        })
+    }
+
+    /// The eight rotations and reflections of the grid, starting with the grid itself
+    /// and its three rotations.
+    pub fn orientations(&self) -> Vec<Self> {
+        let mut orientations = vec![self.clone()];
+        for _ in 0..3 {
+            orientations.push(orientations.last().unwrap().rotate());
+        }
+        for idx in 0..4 {
+            orientations.push(orientations[idx].flip());
+        }
+        orientations
+    }
+
+    /// A copy of the `width * height` cells starting at `(x, y)`. Panics if they aren't
+    /// all in the grid.
+    pub fn sub_grid(&self, x: usize, y: usize, width: usize, height: usize) -> Self {
+        assert!(x + width <= self.width && y + height <= self.height);
+        Self::from_fn(width, height, |dx, dy| self[(x + dx, y + dy)].clone())
+    }
+}
+
+impl<T: Cell> Grid<T> {
+    /// Reads the whole input as a grid written with `Cell::to_char`.
+    pub(crate) fn parse(src: &Source) -> Result<Self> {
+        src.run(grid(T::from_char))
+    }
+}
+
+/// A non-empty grid with a line per row, converting every character with `cell`. It takes
+/// all of the rest of the input, so it usually runs on a `block` or on the whole input.
+pub(crate) fn grid<'a, T>(cell: impl Fn(char) -> Option<T>) -> impl Parser<'a, Grid<T>> {
+    move |input: &'a str| {
+        let mut cells = Vec::with_capacity(input.len());
+        let mut width = None;
+        let mut height = 0;
+        for line in input.lines() {
+            for (idx, char) in line.char_indices() {
+                match cell(char) {
+                    Some(cell) => cells.push(cell),
+                    None => {
+                        return Err(Failure::new(&line[idx..], format!("unexpected `{}`", char)))
+                    }
+                }
+            }
+            let line_width = line.chars().count();
+            if *width.get_or_insert(line_width) != line_width {
+                return Err(Failure::new(line, "all rows must have the same width"));
+            }
+            height += 1;
+        }
+        match width {
+            Some(width) if width > 0 => {
+                Ok((&input[input.len()..], Grid::new(width, height, cells)))
+            }
+            _ => Err(Failure::new(input, "expected a grid")),
+        }
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        assert!(x < self.width, "x = {} is outside the grid", x);
+        &self.cells[y * self.width + x]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        assert!(x < self.width, "x = {} is outside the grid", x);
+        &mut self.cells[y * self.width + x]
+    }
+}
+
+impl<T: Cell> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            let row: String = row.iter().map(Cell::to_char).collect();
+            writeln!(f, "{}", row)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn transforms() {
+    let text = "#..\n.##";
+    let grid = Grid::<bool>::parse(&Source::new(0, text)).unwrap();
+    assert_eq!((grid.width(), grid.height()), (3, 2));
+    assert_eq!(grid.to_string(), "#..\n.##\n");
+    assert!(*grid.get_wrapping(4, 3));
+    assert_eq!(grid.get(3, 0), None);
+    assert_eq!(grid.rotate().to_string(), ".#\n#.\n#.\n");
+    assert_eq!(grid.flip().to_string(), "..#\n##.\n");
+    assert_eq!(grid.rotate().rotate().rotate().rotate(), grid);
+    assert_eq!(grid.sub_grid(1, 0, 2, 2).to_string(), "..\n##\n");
+    assert_eq!(grid.neighbors4(0, 0).collect::<Vec<_>>(), [(1, 0), (0, 1)]);
+    assert_eq!(grid.neighbors8(1, 1).count(), 5);
+    assert_eq!(grid.ray(0, 1, (1, 0)).filter(|&&cell| cell).count(), 2);
+    assert_eq!(grid.column(1).collect::<Vec<_>>(), [&false, &true]);
+
+    let text = "#.\n#";
+    let error = Grid::<bool>::parse(&Source::new(0, text)).unwrap_err();
+    assert_eq!((error.line, error.column), (2, 1));
+    let error = Grid::<bool>::parse(&Source::new(0, "")).unwrap_err();
+    assert_eq!(error.message, "expected a grid");
+}
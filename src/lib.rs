@@ -3,32 +3,65 @@
 #![feature(adt_const_params)]
 #![feature(iter_intersperse)]
 
+//! Solutions for Advent of Code 2020.
+//!
+//! Every day lives in its own module with a parsed `Input` type, a `parse` function and
+//! `part1`/`part2` solvers. With the `aoc-runner` feature (enabled by default) the
+//! solvers are also registered with `cargo aoc`.
+
+#[cfg(feature = "aoc-runner")]
 extern crate aoc_runner;
 
+#[cfg(feature = "aoc-runner")]
 #[macro_use]
 extern crate aoc_runner_derive;
 
-mod day1;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day19;
-mod day2;
-mod day20;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
-mod day21;
-mod day22;
+pub mod error;
+
+pub mod allocations;
+mod automaton;
+pub mod bench;
+pub mod checkpoint;
+pub mod day1;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day2;
+pub mod day20;
+pub mod day21;
+pub mod day22;
+pub mod day23;
+pub mod day24;
+pub mod day25;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+pub mod generators;
+pub mod grid;
+pub mod normalize;
+mod number_theory;
+mod parser;
+pub mod property;
+pub mod reference;
+pub mod runner;
+pub mod solution;
+pub mod stream;
+pub mod trace;
+pub mod visualize;
+
+pub use error::{ParseError, Result};
+pub use solution::{Answer, Solution};
 
+#[cfg(feature = "aoc-runner")]
 aoc_lib! { year = 2020 }
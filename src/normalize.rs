@@ -0,0 +1,84 @@
+//! Cleaning up inputs before they are parsed, so that files saved by any editor on any
+//! system give the same answers.
+//!
+//! The `parse` of every day starts with `for_day`, which applies the `NORMALIZE` of the
+//! day's `Solution`: everything unless the day opts out. So the typed API of the days,
+//! `cargo aoc` and the registry all see the same input.
+
+use std::borrow::Cow;
+
+use crate::solution;
+
+/// Which fixes `normalize` applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Normalize {
+    /// Removes a byte order mark at the start.
+    pub bom: bool,
+    /// Turns `\r\n` into `\n`.
+    pub line_endings: bool,
+    /// Removes the lines at the end that are empty or only contain whitespace, and the
+    /// line break of the last line.
+    pub trailing_blank_lines: bool,
+}
+
+impl Normalize {
+    pub const ALL: Normalize = Normalize {
+        bom: true,
+        line_endings: true,
+        trailing_blank_lines: true,
+    };
+
+    pub const NONE: Normalize = Normalize {
+        bom: false,
+        line_endings: false,
+        trailing_blank_lines: false,
+    };
+}
+
+/// `input` with the fixes in `normalize` applied. Only allocates if it has to change
+/// line endings.
+pub fn normalize(input: &str, normalize: Normalize) -> Cow<'_, str> {
+    let mut input = input;
+    if normalize.bom {
+        input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    }
+    if normalize.trailing_blank_lines {
+        // the end of the last line with something on it
+        input = match input.rfind(|c: char| !c.is_whitespace()) {
+            Some(idx) => {
+                let rest = &input[idx..];
+                let end = rest.find(['\r', '\n']).unwrap_or(rest.len());
+                &input[..idx + end]
+            }
+            None => "",
+        };
+    }
+    if normalize.line_endings && input.contains("\r\n") {
+        Cow::Owned(input.replace("\r\n", "\n"))
+    } else {
+        Cow::Borrowed(input)
+    }
+}
+
+/// `input` the way `day` wants it.
+pub(crate) fn for_day(day: u8, input: &str) -> Cow<'_, str> {
+    let fixes = solution::find(2020, day).map_or(Normalize::ALL, |entry| entry.normalize);
+    normalize(input, fixes)
+}
+
+#[test]
+fn normalized() {
+    let input = "\u{feff}a\r\n\r\nb  \r\n \r\n\n";
+    assert_eq!(normalize(input, Normalize::ALL), "a\n\nb  ");
+    assert!(matches!(
+        normalize("a\nb\n\n", Normalize::ALL),
+        Cow::Borrowed("a\nb")
+    ));
+    assert_eq!(normalize(" \n\t\n", Normalize::ALL), "");
+    assert_eq!(normalize(input, Normalize::NONE), input);
+    let line_endings = Normalize {
+        line_endings: true,
+        ..Normalize::NONE
+    };
+    assert_eq!(normalize(input, line_endings), "\u{feff}a\n\nb  \n \n\n");
+}
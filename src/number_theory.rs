@@ -0,0 +1,144 @@
+use fxhash::FxHashMap;
+
+/// Computes `base^exponent mod modulus` by repeated squaring.
+pub(crate) fn modpow(base: u64, mut exponent: u64, modulus: u64) -> u64 {
+    let modulus = modulus as u128;
+    let mut base = base as u128 % modulus;
+    let mut result = 1 % modulus;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result * base % modulus;
+        }
+        base = base * base % modulus;
+        exponent >>= 1;
+    }
+    result as u64
+}
+
+/// The greatest common divisor of `a` and `b`, with `gcd(0, 0) == 0`.
+pub(crate) fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Returns `x` with `a * x ≡ 1 (mod modulus)`, if `a` and `modulus` are coprime.
+pub(crate) fn modinv(a: i64, modulus: i64) -> Option<i64> {
+    let (mut old_r, mut r) = (a.rem_euclid(modulus) as i128, modulus as i128);
+    let (mut old_s, mut s) = (1i128, 0i128);
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_s, s) = (s, old_s - quotient * s);
+    }
+    if old_r != 1 {
+        return None;
+    }
+    Some(old_s.rem_euclid(modulus as i128) as i64)
+}
+
+/// Why `chinese_remainder` has no answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CrtError {
+    /// Two of the moduli have a common divisor.
+    NotCoprime,
+    /// The answer doesn't fit into an `i64`.
+    Overflow,
+}
+
+/// Finds the smallest non-negative `x` with `x ≡ residues[i] (mod moduli[i])` for all `i`.
+/// The moduli have to be positive and pairwise coprime.
+pub(crate) fn chinese_remainder(residues: &[i64], moduli: &[i64]) -> Result<i64, CrtError> {
+    assert_eq!(residues.len(), moduli.len());
+    let mut x = 0i128;
+    let mut product = 1i128;
+    for (&residue, &modulus) in residues.iter().zip(moduli) {
+        let inverse =
+            modinv((product % modulus as i128) as i64, modulus).ok_or(CrtError::NotCoprime)?;
+        let delta =
+            (residue as i128 - x).rem_euclid(modulus as i128) * inverse as i128 % modulus as i128;
+        // x stays below the product, so it can't overflow if the product doesn't
+        let next = product
+            .checked_mul(modulus as i128)
+            .ok_or(CrtError::Overflow)?;
+        x += product * delta;
+        product = next;
+    }
+    i64::try_from(x.rem_euclid(product)).map_err(|_| CrtError::Overflow)
+}
+
+/// Finds the smallest `x` with `base^x ≡ target (mod modulus)` using baby-step giant-step.
+/// `base` has to be invertible modulo `modulus`.
+pub(crate) fn discrete_log(base: u64, target: u64, modulus: u64) -> Option<u64> {
+    let step = (modulus as f64).sqrt().ceil() as u64;
+    let mut baby_steps = FxHashMap::default();
+    let mut value = 1 % modulus;
+    for j in 0..step {
+        baby_steps.entry(value).or_insert(j);
+        value = (value as u128 * base as u128 % modulus as u128) as u64;
+    }
+    // multiply by base^-step for every giant step
+    let factor = modpow(modinv(base as i64, modulus as i64)? as u64, step, modulus);
+    let mut gamma = target % modulus;
+    for i in 0..step {
+        if let Some(&j) = baby_steps.get(&gamma) {
+            return Some(i * step + j);
+        }
+        gamma = (gamma as u128 * factor as u128 % modulus as u128) as u64;
+    }
+    None
+}
+
+#[test]
+fn pow() {
+    assert_eq!(modpow(7, 8, 20201227), 5764801);
+    assert_eq!(modpow(3, 0, 1), 0);
+    assert_eq!(modpow(2, 64, u64::MAX), 1);
+}
+#[test]
+fn divisor() {
+    assert_eq!(gcd(12, 18), 6);
+    assert_eq!(gcd(0, 5), 5);
+    assert_eq!(gcd(0, 0), 0);
+}
+#[test]
+fn inverse() {
+    assert_eq!(modinv(3, 7), Some(5));
+    assert_eq!(modinv(-3, 7), Some(2));
+    assert_eq!(modinv(4, 8), None);
+}
+#[test]
+fn crt() {
+    assert_eq!(chinese_remainder(&[2, 3, 2], &[3, 5, 7]), Ok(23));
+    assert_eq!(chinese_remainder(&[0, -1], &[17, 13]), Ok(51));
+    assert_eq!(
+        chinese_remainder(&[1, 2], &[4, 6]),
+        Err(CrtError::NotCoprime)
+    );
+    let primes = [
+        1_000_000_007,
+        998_244_353,
+        1_000_000_009,
+        999_999_937,
+        999_999_929,
+    ];
+    assert_eq!(
+        chinese_remainder(&[-1; 2], &primes[..2]),
+        Ok(1_000_000_007 * 998_244_353 - 1)
+    );
+    assert_eq!(
+        chinese_remainder(&[-1; 3], &primes[..3]),
+        Err(CrtError::Overflow)
+    );
+    assert_eq!(
+        chinese_remainder(&[-1; 5], &primes),
+        Err(CrtError::Overflow)
+    );
+}
+#[test]
+fn log() {
+    assert_eq!(discrete_log(7, 5764801, 20201227), Some(8));
+    assert_eq!(discrete_log(7, 17807724, 20201227), Some(11));
+    assert_eq!(discrete_log(2, 3, 7), None);
+}
@@ -0,0 +1,362 @@
+//! Small parser combinators for the puzzle inputs.
+//!
+//! A parser takes the rest of the input and returns what is left after it together with
+//! the parsed value, or a `Failure` where it got stuck. The rest is always a subslice of
+//! the whole input, so `Source::run` can report failures with their line and column.
+
+use std::{fmt, str::FromStr};
+
+pub(crate) struct Failure<'a> {
+    /// The input where the parser got stuck.
+    pub(crate) at: &'a str,
+    pub(crate) message: String,
+}
+
+impl<'a> Failure<'a> {
+    pub(crate) fn new(at: &'a str, message: impl Into<String>) -> Self {
+        Self {
+            at,
+            message: message.into(),
+        }
+    }
+
+    /// A failure for leftover input that no parser wanted.
+    fn unexpected(at: &'a str) -> Self {
+        match at.chars().next() {
+            Some('\n') => Self::new(at, "unexpected line break"),
+            Some(char) => Self::new(at, format!("unexpected `{}`", char)),
+            None => Self::new(at, "unexpected end of input"),
+        }
+    }
+}
+
+pub(crate) type PResult<'a, T> = Result<(&'a str, T), Failure<'a>>;
+
+pub(crate) trait Parser<'a, T> {
+    fn parse(&self, input: &'a str) -> PResult<'a, T>;
+
+    fn map<U>(self, f: impl Fn(T) -> U) -> impl Parser<'a, U>
+    where
+        Self: Sized,
+    {
+        move |input: &'a str| {
+            let (rest, value) = self.parse(input)?;
+            Ok((rest, f(value)))
+        }
+    }
+
+    /// Like `map`, but `f` can reject the value. The failure is reported where the value
+    /// starts.
+    fn try_map<U, E: Into<String>>(self, f: impl Fn(T) -> Result<U, E>) -> impl Parser<'a, U>
+    where
+        Self: Sized,
+    {
+        move |input: &'a str| {
+            let (rest, value) = self.parse(input)?;
+            match f(value) {
+                Ok(value) => Ok((rest, value)),
+                Err(message) => Err(Failure::new(input, message)),
+            }
+        }
+    }
+}
+
+impl<'a, T, F> Parser<'a, T> for F
+where
+    F: Fn(&'a str) -> PResult<'a, T>,
+{
+    fn parse(&self, input: &'a str) -> PResult<'a, T> {
+        self(input)
+    }
+}
+
+/// Tuples of parsers run one after another and return a tuple of the values.
+macro_rules! sequence {
+    ($($parser:ident $value:ident),+) => {
+        impl<'a, $($parser, $value),+> Parser<'a, ($($value,)+)> for ($($parser,)+)
+        where
+            $($parser: Parser<'a, $value>),+
+        {
+            #[allow(non_snake_case)] // the parsers and values are named after their types
+            fn parse(&self, input: &'a str) -> PResult<'a, ($($value,)+)> {
+                let ($($parser,)+) = self;
+                let rest = input;
+                $(let (rest, $value) = $parser.parse(rest)?;)+
+                Ok((rest, ($($value,)+)))
+            }
+        }
+    };
+}
+
+sequence!(PA A, PB B);
+sequence!(PA A, PB B, PC C);
+sequence!(PA A, PB B, PC C, PD D);
+sequence!(PA A, PB B, PC C, PD D, PE E);
+sequence!(PA A, PB B, PC C, PD D, PE E, PF F);
+sequence!(PA A, PB B, PC C, PD D, PE E, PF F, PG G);
+
+/// Exactly `tag`.
+pub(crate) fn tag<'a>(tag: &'static str) -> impl Parser<'a, &'a str> {
+    move |input: &'a str| match input.strip_prefix(tag) {
+        Some(rest) => Ok((rest, &input[..tag.len()])),
+        None => Err(Failure::new(
+            input,
+            format!("expected `{}`", tag.escape_default()),
+        )),
+    }
+}
+
+/// The first of `options` that the input starts with, e.g. `[("acc", Op::Acc), ...]`.
+pub(crate) fn one_of<'a, T: Clone>(options: &'static [(&'static str, T)]) -> impl Parser<'a, T> {
+    move |input: &'a str| {
+        for (tag, value) in options {
+            if let Some(rest) = input.strip_prefix(tag) {
+                return Ok((rest, value.clone()));
+            }
+        }
+        let mut tags: Vec<String> = options
+            .iter()
+            .map(|(tag, _)| format!("`{}`", tag))
+            .collect();
+        let last = tags.pop().unwrap_or_default();
+        let message = if tags.is_empty() {
+            format!("expected {}", last)
+        } else {
+            format!("expected one of {} or {}", tags.join(", "), last)
+        };
+        Err(Failure::new(input, message))
+    }
+}
+
+/// A decimal integer with an optional sign.
+pub(crate) fn int<'a, T>() -> impl Parser<'a, T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    move |input: &'a str| {
+        let sign = input.starts_with(['+', '-']) as usize;
+        let digits = input[sign..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(input.len() - sign);
+        if digits == 0 {
+            return Err(Failure::new(input, "expected a number"));
+        }
+        let (number, rest) = input.split_at(sign + digits);
+        match number.parse() {
+            Ok(value) => Ok((rest, value)),
+            Err(e) => Err(Failure::new(
+                input,
+                format!("invalid number `{}`: {}", number, e),
+            )),
+        }
+    }
+}
+
+/// Any single character.
+pub(crate) fn any_char<'a>() -> impl Parser<'a, char> {
+    move |input: &'a str| match input.chars().next() {
+        Some(char) => Ok((&input[char.len_utf8()..], char)),
+        None => Err(Failure::new(input, "expected a character")),
+    }
+}
+
+/// One or more characters that match `predicate`. `what` describes them for errors.
+pub(crate) fn take_while1<'a>(
+    what: &'static str,
+    predicate: impl Fn(char) -> bool,
+) -> impl Parser<'a, &'a str> {
+    move |input: &'a str| {
+        let len = input.find(|c| !predicate(c)).unwrap_or(input.len());
+        if len == 0 {
+            return Err(Failure::new(input, format!("expected {}", what)));
+        }
+        Ok((&input[len..], &input[..len]))
+    }
+}
+
+/// Everything up to the next whitespace.
+pub(crate) fn word<'a>() -> impl Parser<'a, &'a str> {
+    take_while1("a word", |c| !c.is_whitespace())
+}
+
+/// The non-empty rest of the line before the next `delimiter`, which isn't consumed.
+pub(crate) fn until<'a>(delimiter: &'static str) -> impl Parser<'a, &'a str> {
+    move |input: &'a str| {
+        let line = &input[..input.find('\n').unwrap_or(input.len())];
+        match line.find(delimiter) {
+            Some(0) => Err(Failure::new(input, "expected some text")),
+            Some(len) => Ok((&input[len..], &input[..len])),
+            None => Err(Failure::new(
+                &line[line.len()..],
+                format!("expected `{}`", delimiter.escape_default()),
+            )),
+        }
+    }
+}
+
+/// `key`, `separator` and `value`, like `byr:1937` or `class: 1-3 or 5-7`.
+pub(crate) fn field<'a, K, V>(
+    key: impl Parser<'a, K>,
+    separator: &'static str,
+    value: impl Parser<'a, V>,
+) -> impl Parser<'a, (K, V)> {
+    (key, tag(separator), value).map(|(key, _, value)| (key, value))
+}
+
+/// The value of `parser`, together with the text it consumed.
+pub(crate) fn spanned<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, (&'a str, T)> {
+    move |input: &'a str| {
+        let (rest, value) = parser.parse(input)?;
+        Ok((rest, (&input[..input.len() - rest.len()], value)))
+    }
+}
+
+pub(crate) fn preceded<'a, A, B>(
+    first: impl Parser<'a, A>,
+    second: impl Parser<'a, B>,
+) -> impl Parser<'a, B> {
+    (first, second).map(|(_, second)| second)
+}
+
+/// `first`, or `second` if `first` fails. If both fail, the failure that got further wins.
+pub(crate) fn alt<'a, T>(
+    first: impl Parser<'a, T>,
+    second: impl Parser<'a, T>,
+) -> impl Parser<'a, T> {
+    move |input: &'a str| {
+        first.parse(input).or_else(|first| {
+            second.parse(input).map_err(|second| {
+                if second.at.len() <= first.at.len() {
+                    second
+                } else {
+                    first
+                }
+            })
+        })
+    }
+}
+
+/// `None` if `parser` fails right at the start, failures after that are passed on.
+pub(crate) fn optional<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, Option<T>> {
+    move |input: &'a str| match parser.parse(input) {
+        Ok((rest, value)) => Ok((rest, Some(value))),
+        Err(failure) if failure.at.len() == input.len() => Ok((input, None)),
+        Err(failure) => Err(failure),
+    }
+}
+
+/// One or more `item`s with `separator` in between, like `1,2,3`. If an item after a
+/// separator fails right at its start, the list ends before that separator.
+pub(crate) fn separated<'a, T, S>(
+    item: impl Parser<'a, T>,
+    separator: impl Parser<'a, S>,
+) -> impl Parser<'a, Vec<T>> {
+    move |input: &'a str| {
+        let (mut input, first) = item.parse(input)?;
+        let mut items = vec![first];
+        while let Ok((rest, _)) = separator.parse(input) {
+            match optional(|input| item.parse(input)).parse(rest)? {
+                (rest, Some(value)) => {
+                    items.push(value);
+                    input = rest;
+                }
+                (_, None) => break,
+            }
+        }
+        Ok((input, items))
+    }
+}
+
+/// Runs `parser` on the text up to `delimiter`, which it has to consume completely, and
+/// skips the delimiter.
+fn delimited_by<'a, T>(delimiter: &'static str, parser: impl Parser<'a, T>) -> impl Parser<'a, T> {
+    move |input: &'a str| {
+        let (text, rest) = match input.find(delimiter) {
+            Some(len) => (&input[..len], &input[len + delimiter.len()..]),
+            None => (input, &input[input.len()..]),
+        };
+        match parser.parse(text)? {
+            ("", value) => Ok((rest, value)),
+            (leftover, _) => Err(Failure::unexpected(leftover)),
+        }
+    }
+}
+
+/// `parser` on a whole line.
+pub(crate) fn line<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, T> {
+    delimited_by("\n", parser)
+}
+
+/// `parser` on every line up to the end of the input.
+pub(crate) fn lines<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, Vec<T>> {
+    repeat(line(parser))
+}
+
+/// `parser` on a whole block of lines, which ends at an empty line.
+pub(crate) fn block<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, T> {
+    delimited_by("\n\n", parser)
+}
+
+/// `parser` on every block up to the end of the input.
+pub(crate) fn blocks<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, Vec<T>> {
+    repeat(block(parser))
+}
+
+/// `parser` again and again until the input ends, e.g. on a `line`.
+pub(crate) fn repeat<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, Vec<T>> {
+    move |mut input: &'a str| {
+        let mut items = vec![];
+        while !input.is_empty() {
+            let (rest, item) = parser.parse(input)?;
+            items.push(item);
+            input = rest;
+        }
+        Ok((input, items))
+    }
+}
+
+/// `parser` on all of `input`, which it has to consume completely.
+pub(crate) fn all<'a, T>(parser: impl Parser<'a, T>, input: &'a str) -> Result<T, Failure<'a>> {
+    match parser.parse(input)? {
+        ("", value) => Ok(value),
+        (rest, _) => Err(Failure::unexpected(rest)),
+    }
+}
+
+#[test]
+fn combinators() {
+    let numbers = lines(separated(int::<i32>(), tag(",")));
+    let parsed = all(|input| numbers.parse(input), "1,-2\n3").ok().unwrap();
+    assert_eq!(parsed, [vec![1, -2], vec![3]]);
+    let failure = all(numbers, "1,2\n3,x").err().unwrap();
+    assert_eq!(
+        (failure.at, failure.message.as_str()),
+        (",x", "unexpected `,`")
+    );
+
+    let passport = blocks(separated(
+        field(take_while1("a key", char::is_alphabetic), ":", word()),
+        take_while1("whitespace", char::is_whitespace),
+    ));
+    let parsed = all(passport, "a:1 b:#2\nc:3\n\nd:4").ok().unwrap();
+    assert_eq!(parsed[0], [("a", "1"), ("b", "#2"), ("c", "3")]);
+    assert_eq!(parsed[1], [("d", "4")]);
+
+    let failure = all(int::<u8>(), "300").err().unwrap();
+    assert!(failure.message.starts_with("invalid number `300`"));
+    let op = one_of(&[("acc", 1), ("jmp", 2), ("nop", 3)]);
+    let failure = all(op, "mul").err().unwrap();
+    assert_eq!(failure.message, "expected one of `acc`, `jmp` or `nop`");
+    let bag = (until(" bags"), tag(" bags"), optional(tag(".")));
+    assert_eq!(all(bag, "light red bags").ok().unwrap().0, "light red");
+    let number_or_x = alt(int().map(Some), tag("x").map(|_| None));
+    assert_eq!(
+        all(separated(number_or_x, tag(",")), "x,7").ok().unwrap(),
+        [None, Some(7u8)]
+    );
+    assert_eq!(
+        all(spanned(repeat(tag("ab"))), "abab").ok().unwrap().0,
+        "abab"
+    );
+}
@@ -0,0 +1,197 @@
+//! Differential testing of the solvers against the brute-force `reference` solutions on
+//! generated inputs, shrinking the inputs they disagree on.
+
+use std::{
+    fmt,
+    ops::Range,
+    panic::{self, AssertUnwindSafe},
+};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::{
+    generators,
+    reference::Reference,
+    solution::{self, Answer, Entry},
+};
+
+/// An input on which a solver and its reference disagree.
+#[derive(Debug)]
+pub struct Counterexample {
+    pub day: u8,
+    pub part: u8,
+    /// The seed of the generated input that `input` was shrunk from.
+    pub seed: u64,
+    pub input: String,
+    pub expected: Answer,
+    /// The solver's answer, or its error or panic message.
+    pub actual: Result<Answer, String>,
+}
+
+impl fmt::Display for Counterexample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let actual = match &self.actual {
+            Ok(answer) => answer.to_string(),
+            Err(e) => format!("error: {}", e),
+        };
+        write!(
+            f,
+            "day {} part {} (seed {}): expected {}, got {} for\n{}",
+            self.day, self.part, self.seed, self.expected, actual, self.input
+        )
+    }
+}
+
+/// The expected and actual answer if the solver gets `input` wrong. Inputs that the
+/// solver's parser or the reference rejects don't count.
+fn disagreement(
+    entry: &Entry,
+    reference: &Reference,
+    input: &str,
+) -> Option<(Answer, Result<Answer, String>)> {
+    let expected = (reference.solve)(input)?;
+    let actual = panic::catch_unwind(AssertUnwindSafe(|| {
+        let parsed = entry.parse(input).ok()?;
+        Some(
+            entry
+                .solve(&parsed, reference.part)
+                .map_err(|e| e.to_string()),
+        )
+    }))
+    .unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        Some(Err(format!("panicked: {}", message)))
+    })?;
+    if actual.as_ref() == Ok(&expected) {
+        None
+    } else {
+        Some((expected, actual))
+    }
+}
+
+/// Compares the solver with `reference` on an input generated from every seed, and
+/// returns the first disagreement, shrunk. `size` is capped at `reference.max_size`.
+pub fn check(reference: &Reference, seeds: Range<u64>, size: usize) -> Result<(), Counterexample> {
+    let entry = solution::find(2020, reference.day).expect("references only exist for solved days");
+    let size = size.min(reference.max_size);
+    for seed in seeds {
+        let Some(input) = generators::generate(reference.day, seed, size) else {
+            continue;
+        };
+        if disagreement(entry, reference, &input).is_none() {
+            continue;
+        }
+        let input = shrink(&input, |input| {
+            disagreement(entry, reference, input).is_some()
+        });
+        let (expected, actual) = disagreement(entry, reference, &input).unwrap();
+        return Err(Counterexample {
+            day: reference.day,
+            part: reference.part,
+            seed,
+            input,
+            expected,
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// Makes `input` as small as possible while `fails` keeps returning `true` for it, by
+/// removing blocks, lines and words and by making numbers smaller.
+pub fn shrink(input: &str, fails: impl Fn(&str) -> bool) -> String {
+    let mut current = input.to_string();
+    loop {
+        let mut next = current.clone();
+        for separator in ["\n\n", "\n", " "] {
+            next = remove_pieces(&next, separator, &fails);
+        }
+        next = reduce_numbers(&next, &fails);
+        if next == current {
+            return current;
+        }
+        current = next;
+    }
+}
+
+/// Removes ever smaller runs of the pieces of `input` between `separator`s.
+fn remove_pieces(input: &str, separator: &str, fails: &impl Fn(&str) -> bool) -> String {
+    let mut pieces: Vec<&str> = input.split(separator).collect();
+    let mut chunk = pieces.len() / 2;
+    while chunk > 0 {
+        let mut start = 0;
+        while start + chunk <= pieces.len() {
+            let candidate: Vec<&str> = pieces[..start]
+                .iter()
+                .chain(&pieces[start + chunk..])
+                .copied()
+                .collect();
+            if !candidate.is_empty() && fails(&candidate.join(separator)) {
+                pieces = candidate;
+            } else {
+                start += chunk;
+            }
+        }
+        chunk /= 2;
+    }
+    pieces.join(separator)
+}
+
+/// Replaces every number with the first of 0, 1, half of it and one less that still fails.
+fn reduce_numbers(input: &str, fails: &impl Fn(&str) -> bool) -> String {
+    lazy_static! {
+        static ref NUMBER: Regex = Regex::new(r"\d+").unwrap();
+    }
+    let mut current = input.to_string();
+    let mut start = 0;
+    while let Some(number) = NUMBER.find_at(&current, start) {
+        let (range, n) = (number.range(), number.as_str().parse::<u64>());
+        start = range.end;
+        let Ok(n) = n else {
+            continue;
+        };
+        for smaller in [0, 1, n / 2, n.saturating_sub(1)] {
+            if smaller >= n {
+                continue;
+            }
+            let candidate = format!(
+                "{}{}{}",
+                &current[..range.start],
+                smaller,
+                &current[range.end..]
+            );
+            if fails(&candidate) {
+                start = range.start + smaller.to_string().len();
+                current = candidate;
+                break;
+            }
+        }
+    }
+    current
+}
+
+#[test]
+fn references_agree() {
+    for reference in crate::reference::REFERENCES {
+        if let Err(counterexample) = check(reference, 0..8, reference.max_size) {
+            panic!("{}", counterexample);
+        }
+    }
+}
+
+#[test]
+fn shrinks() {
+    let has_seven = |input: &str| input.lines().any(|line| line == "7");
+    assert_eq!(shrink("1\n2\n7\n8\n\n7", has_seven), "7");
+    let large = |input: &str| {
+        input
+            .split_whitespace()
+            .any(|n| n.parse::<u64>().unwrap() >= 100)
+    };
+    assert_eq!(shrink("5 17\n250\n3", large), "100");
+}
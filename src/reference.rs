@@ -0,0 +1,511 @@
+//! Straightforward brute-force solutions, to check the optimised solvers against.
+//!
+//! They share no code with the solvers, not even the parsers, and return `None` for
+//! inputs they can't handle. Most of them are exponential, so they are only run on
+//! small inputs.
+
+use std::collections::HashMap;
+
+use crate::solution::Answer;
+
+pub struct Reference {
+    pub day: u8,
+    pub part: u8,
+    /// The largest `generators::generate` size that this finishes quickly for.
+    pub max_size: usize,
+    pub solve: fn(&str) -> Option<Answer>,
+}
+
+pub static REFERENCES: &[Reference] = &[
+    Reference {
+        day: 1,
+        part: 1,
+        max_size: 200,
+        solve: day1_part1,
+    },
+    Reference {
+        day: 1,
+        part: 2,
+        max_size: 100,
+        solve: day1_part2,
+    },
+    Reference {
+        day: 10,
+        part: 1,
+        max_size: 200,
+        solve: day10_part1,
+    },
+    Reference {
+        day: 10,
+        part: 2,
+        max_size: 25,
+        solve: day10_part2,
+    },
+    Reference {
+        day: 18,
+        part: 1,
+        max_size: 200,
+        solve: day18_part1,
+    },
+    Reference {
+        day: 18,
+        part: 2,
+        max_size: 200,
+        solve: day18_part2,
+    },
+    Reference {
+        day: 19,
+        part: 1,
+        max_size: 50,
+        solve: day19_part1,
+    },
+    Reference {
+        day: 19,
+        part: 2,
+        max_size: 50,
+        solve: day19_part2,
+    },
+    Reference {
+        day: 20,
+        part: 1,
+        max_size: 4,
+        solve: day20_part1,
+    },
+    Reference {
+        day: 20,
+        part: 2,
+        max_size: 4,
+        solve: day20_part2,
+    },
+];
+
+fn numbers(input: &str) -> Option<Vec<i64>> {
+    input.lines().map(|line| line.parse().ok()).collect()
+}
+
+fn day1_part1(input: &str) -> Option<Answer> {
+    let entries = numbers(input)?;
+    for i in 0..entries.len() {
+        for j in i + 1..entries.len() {
+            if entries[i] + entries[j] == 2020 {
+                return Some(Answer::from(entries[i] * entries[j]));
+            }
+        }
+    }
+    None
+}
+
+fn day1_part2(input: &str) -> Option<Answer> {
+    let entries = numbers(input)?;
+    for i in 0..entries.len() {
+        for j in i + 1..entries.len() {
+            for k in j + 1..entries.len() {
+                if entries[i] + entries[j] + entries[k] == 2020 {
+                    return Some(Answer::from(entries[i] * entries[j] * entries[k]));
+                }
+            }
+        }
+    }
+    None
+}
+
+fn day10_part1(input: &str) -> Option<Answer> {
+    let mut jolts = numbers(input)?;
+    jolts.push(0);
+    jolts.sort_unstable();
+    let mut ones = 0;
+    // the device is always 3 jolts above the last adapter
+    let mut threes = 1;
+    for pair in jolts.windows(2) {
+        match pair[1] - pair[0] {
+            1 => ones += 1,
+            3 => threes += 1,
+            0 | 2 => {}
+            _ => return None,
+        }
+    }
+    Some(Answer::from(ones * threes))
+}
+
+fn day10_part2(input: &str) -> Option<Answer> {
+    // counts every arrangement one by one
+    fn arrangements(jolts: &[i64], from: i64) -> u64 {
+        let last = *jolts.last().unwrap();
+        if from == last {
+            return 1;
+        }
+        jolts
+            .iter()
+            .filter(|&&jolt| jolt > from && jolt <= from + 3)
+            .map(|&jolt| arrangements(jolts, jolt))
+            .sum()
+    }
+    let mut jolts = numbers(input)?;
+    jolts.sort_unstable();
+    jolts.dedup();
+    Some(Answer::from(arrangements(&jolts, 0)))
+}
+
+/// Evaluates an expression of digits, `+`, `*` and parentheses. With `addition_first`,
+/// `+` binds tighter than `*`, otherwise both are evaluated from left to right.
+fn evaluate(tokens: &[char], addition_first: bool) -> Option<i64> {
+    // the values and operators of the enclosing parentheses
+    let mut values: Vec<Option<i64>> = vec![];
+    let mut ops = vec![];
+    let mut stack: Vec<(Vec<Option<i64>>, Vec<char>)> = vec![];
+    for &token in tokens {
+        match token {
+            '(' => stack.push((std::mem::take(&mut values), std::mem::take(&mut ops))),
+            ')' => {
+                let value = reduce(&values, &ops, addition_first)?;
+                let (outer_values, outer_ops) = stack.pop()?;
+                values = outer_values;
+                ops = outer_ops;
+                values.push(Some(value));
+            }
+            '+' | '*' => ops.push(token),
+            digit => values.push(Some(digit.to_digit(10)? as i64)),
+        }
+    }
+    if !stack.is_empty() {
+        return None;
+    }
+    reduce(&values, &ops, addition_first)
+}
+
+fn reduce(values: &[Option<i64>], ops: &[char], addition_first: bool) -> Option<i64> {
+    if values.len() != ops.len() + 1 {
+        return None;
+    }
+    let values: Vec<i64> = values.iter().copied().collect::<Option<_>>()?;
+    if addition_first {
+        // a product of sums
+        let mut product = 1;
+        let mut sum = values[0];
+        for (op, value) in ops.iter().zip(&values[1..]) {
+            if *op == '+' {
+                sum += value;
+            } else {
+                product *= sum;
+                sum = *value;
+            }
+        }
+        Some(product * sum)
+    } else {
+        let mut acc = values[0];
+        for (op, value) in ops.iter().zip(&values[1..]) {
+            acc = if *op == '+' { acc + value } else { acc * value };
+        }
+        Some(acc)
+    }
+}
+
+fn day18(input: &str, addition_first: bool) -> Option<Answer> {
+    let mut sum = 0;
+    for line in input.lines() {
+        let tokens: Vec<char> = line.chars().filter(|c| *c != ' ').collect();
+        sum += evaluate(&tokens, addition_first)?;
+    }
+    Some(Answer::from(sum))
+}
+
+fn day18_part1(input: &str) -> Option<Answer> {
+    day18(input, false)
+}
+
+fn day18_part2(input: &str) -> Option<Answer> {
+    day18(input, true)
+}
+
+enum Token {
+    Letter(u8),
+    Rule(usize),
+}
+
+type Grammar = HashMap<usize, Vec<Vec<Token>>>;
+
+fn parse_grammar(rules: &str) -> Option<Grammar> {
+    let mut grammar = HashMap::new();
+    for line in rules.lines() {
+        let (idx, body) = line.split_once(": ")?;
+        let alternatives = body
+            .split(" | ")
+            .map(|alternative| {
+                alternative
+                    .split(' ')
+                    .map(|token| match token.strip_prefix('"') {
+                        Some(letter) => Some(Token::Letter(*letter.as_bytes().first()?)),
+                        None => token.parse().ok().map(Token::Rule),
+                    })
+                    .collect()
+            })
+            .collect::<Option<_>>()?;
+        grammar.insert(idx.parse().ok()?, alternatives);
+    }
+    Some(grammar)
+}
+
+/// Whether a rule refers back to itself, other than the loops that part 2 adds.
+fn is_cyclic(grammar: &Grammar, loops: &[usize]) -> bool {
+    fn visit(grammar: &Grammar, loops: &[usize], rule: usize, path: &mut Vec<usize>) -> bool {
+        if path.contains(&rule) {
+            return true;
+        }
+        path.push(rule);
+        let cyclic = grammar[&rule].iter().flatten().any(|token| match *token {
+            Token::Rule(next) if next == rule && loops.contains(&rule) => false,
+            Token::Rule(next) => visit(grammar, loops, next, path),
+            Token::Letter(_) => false,
+        });
+        path.pop();
+        cyclic
+    }
+    grammar
+        .keys()
+        .any(|&rule| visit(grammar, loops, rule, &mut vec![]))
+}
+
+/// All positions where a match of `rule` starting at `start` can end.
+fn match_ends(grammar: &Grammar, rule: usize, message: &[u8], start: usize) -> Vec<usize> {
+    let mut ends = vec![];
+    for alternative in &grammar[&rule] {
+        let mut positions = vec![start];
+        for token in alternative {
+            positions = positions
+                .into_iter()
+                .flat_map(|position| match *token {
+                    Token::Letter(letter) => {
+                        if message.get(position) == Some(&letter) {
+                            vec![position + 1]
+                        } else {
+                            vec![]
+                        }
+                    }
+                    Token::Rule(rule) => match_ends(grammar, rule, message, position),
+                })
+                .collect();
+        }
+        ends.extend(positions);
+    }
+    ends
+}
+
+fn day19(input: &str, loops: bool) -> Option<Answer> {
+    let (rules, messages) = input.split_once("\n\n")?;
+    let mut grammar = parse_grammar(rules)?;
+    if loops {
+        use Token::Rule;
+        grammar.insert(8, vec![vec![Rule(42)], vec![Rule(42), Rule(8)]]);
+        grammar.insert(
+            11,
+            vec![vec![Rule(42), Rule(31)], vec![Rule(42), Rule(11), Rule(31)]],
+        );
+    }
+    let rule_exists = |token: &Token| match token {
+        Token::Rule(rule) => grammar.contains_key(rule),
+        Token::Letter(_) => true,
+    };
+    if !grammar.contains_key(&0) || !grammar.values().flatten().flatten().all(rule_exists) {
+        return None;
+    }
+    if is_cyclic(&grammar, if loops { &[8, 11] } else { &[] }) {
+        return None;
+    }
+    let valid = messages
+        .lines()
+        .filter(|message| match_ends(&grammar, 0, message.as_bytes(), 0).contains(&message.len()))
+        .count();
+    Some(Answer::from(valid))
+}
+
+fn day19_part1(input: &str) -> Option<Answer> {
+    day19(input, false)
+}
+
+fn day19_part2(input: &str) -> Option<Answer> {
+    day19(input, true)
+}
+
+type Image = Vec<Vec<bool>>;
+
+fn rotate(image: &Image) -> Image {
+    let len = image.len();
+    (0..len)
+        .map(|y| (0..len).map(|x| image[len - 1 - x][y]).collect())
+        .collect()
+}
+
+fn flip(image: &Image) -> Image {
+    image
+        .iter()
+        .map(|row| row.iter().rev().copied().collect())
+        .collect()
+}
+
+/// The eight rotations and reflections of a square image.
+fn orientations(image: &Image) -> Vec<Image> {
+    let mut orientations = vec![image.clone()];
+    for _ in 0..3 {
+        orientations.push(rotate(orientations.last().unwrap()));
+    }
+    for idx in 0..4 {
+        orientations.push(flip(&orientations[idx]));
+    }
+    orientations
+}
+
+/// The tiles of day 20, placed in a square by trying every tile in every orientation
+/// at every position. Returns the ids and the oriented tiles in row-major order.
+fn assemble(input: &str) -> Option<Vec<(u64, Image)>> {
+    let tiles: Vec<(u64, Vec<Image>)> = input
+        .split("\n\n")
+        .map(|tile| {
+            let (header, pixels) = tile.split_once('\n')?;
+            let id = header
+                .strip_prefix("Tile ")?
+                .strip_suffix(':')?
+                .parse()
+                .ok()?;
+            let is_tile = pixels.lines().count() == 10
+                && pixels
+                    .lines()
+                    .all(|line| line.len() == 10 && line.chars().all(|c| c == '#' || c == '.'));
+            if !is_tile {
+                return None;
+            }
+            let image: Image = pixels
+                .lines()
+                .map(|line| line.chars().map(|c| c == '#').collect())
+                .collect();
+            Some((id, orientations(&image)))
+        })
+        .collect::<Option<_>>()?;
+    // like the puzzle promises, every edge may only fit one other tile
+    let mut edges: Vec<&Vec<bool>> = tiles
+        .iter()
+        .flat_map(|(_, orientations)| orientations.iter().map(|image| &image[0]))
+        .collect();
+    edges.sort();
+    if edges.windows(3).any(|edges| edges[0] == edges[2]) {
+        return None;
+    }
+    let side = (1..=tiles.len()).find(|side| side * side >= tiles.len())?;
+    if side * side != tiles.len() {
+        return None;
+    }
+
+    fn place(
+        tiles: &[(u64, Vec<Image>)],
+        side: usize,
+        used: &mut Vec<bool>,
+        placed: &mut Vec<(u64, Image)>,
+    ) -> bool {
+        let position = placed.len();
+        if position == tiles.len() {
+            return true;
+        }
+        for (idx, (id, orientations)) in tiles.iter().enumerate() {
+            if used[idx] {
+                continue;
+            }
+            for image in orientations {
+                let len = image.len();
+                let fits_left = position.is_multiple_of(side) || {
+                    let left = &placed[position - 1].1;
+                    (0..len).all(|y| left[y][len - 1] == image[y][0])
+                };
+                let fits_top = position < side || {
+                    let top = &placed[position - side].1;
+                    top[len - 1] == image[0]
+                };
+                if fits_left && fits_top {
+                    used[idx] = true;
+                    placed.push((*id, image.clone()));
+                    if place(tiles, side, used, placed) {
+                        return true;
+                    }
+                    placed.pop();
+                    used[idx] = false;
+                }
+            }
+        }
+        false
+    }
+    let mut placed = vec![];
+    if place(&tiles, side, &mut vec![false; tiles.len()], &mut placed) {
+        Some(placed)
+    } else {
+        None
+    }
+}
+
+fn day20_part1(input: &str) -> Option<Answer> {
+    let placed = assemble(input)?;
+    let side = (placed.len() as f64).sqrt() as usize;
+    let corners = [0, side - 1, side * (side - 1), side * side - 1];
+    Some(Answer::from(
+        corners.iter().map(|&idx| placed[idx].0).product::<u64>(),
+    ))
+}
+
+fn day20_part2(input: &str) -> Option<Answer> {
+    const MONSTER: [&str; 3] = [
+        "                  # ",
+        "#    ##    ##    ###",
+        " #  #  #  #  #  #   ",
+    ];
+    let placed = assemble(input)?;
+    let side = (placed.len() as f64).sqrt() as usize;
+    let inner = placed[0].1.len() - 2;
+    let len = side * inner;
+    let picture: Image = (0..len)
+        .map(|y| {
+            (0..len)
+                .map(|x| placed[y / inner * side + x / inner].1[y % inner + 1][x % inner + 1])
+                .collect()
+        })
+        .collect();
+    // the monster in all eight orientations, as (y, x) offsets with its height and width
+    let mut monster: Vec<(usize, usize)> = MONSTER
+        .iter()
+        .enumerate()
+        .flat_map(|(y, line)| {
+            line.chars()
+                .enumerate()
+                .filter(|&(_, c)| c == '#')
+                .map(move |(x, _)| (y, x))
+        })
+        .collect();
+    let (mut height, mut width) = (MONSTER.len(), MONSTER[0].len());
+    let mut monsters = vec![];
+    for orientation in 0..8 {
+        if orientation == 4 {
+            monster = monster.iter().map(|&(y, x)| (y, width - 1 - x)).collect();
+        }
+        monsters.push((monster.clone(), height, width));
+        // rotate clockwise
+        monster = monster.iter().map(|&(y, x)| (x, height - 1 - y)).collect();
+        (height, width) = (width, height);
+    }
+    let mut is_monster = vec![vec![false; len]; len];
+    for (monster, height, width) in &monsters {
+        for y in 0..=len.saturating_sub(*height) {
+            for x in 0..=len.saturating_sub(*width) {
+                let cells = monster.iter().map(|&(dy, dx)| (y + dy, x + dx));
+                if cells
+                    .clone()
+                    .all(|(y, x)| y < len && x < len && picture[y][x])
+                {
+                    for (y, x) in cells {
+                        is_monster[y][x] = true;
+                    }
+                }
+            }
+        }
+    }
+    let rough = (0..len)
+        .flat_map(|y| (0..len).map(move |x| (y, x)))
+        .filter(|&(y, x)| picture[y][x] && !is_monster[y][x])
+        .count();
+    Some(Answer::from(rough))
+}
@@ -0,0 +1,258 @@
+//! Runs days on a pool of worker threads and gives up on parts that take too long.
+//!
+//! Rust can't stop a thread, so a solver that timed out keeps running in the background
+//! until the process exits. Its worker moves on to the next part in the meantime.
+//!
+//! The solvers trace to the sink of the thread that calls `run`.
+
+use std::{
+    any::Any,
+    fmt,
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    solution::{Answer, Entry},
+    trace,
+};
+
+pub struct Config {
+    /// The number of days that are worked on at the same time.
+    pub jobs: usize,
+    /// How long parsing and each part may take.
+    pub timeout: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            jobs: thread::available_parallelism().map_or(1, |jobs| jobs.get()),
+            timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Some parts of a day, to be solved for an input.
+pub struct Job {
+    pub entry: &'static Entry,
+    pub parts: Vec<u8>,
+    pub input: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Solved(Answer),
+    Failed(String),
+    TimedOut(Duration),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Solved(answer) => write!(f, "{}", answer),
+            Outcome::Failed(e) => write!(f, "error: {}", e),
+            Outcome::TimedOut(timeout) => write!(f, "timed out after {:?}", timeout),
+        }
+    }
+}
+
+/// The outcome of one part of a day.
+pub struct Report {
+    pub day: u8,
+    pub part: u8,
+    pub outcome: Outcome,
+    pub parse_time: Option<Duration>,
+    pub solve_time: Option<Duration>,
+}
+
+impl Report {
+    /// Reports for `parts` of `day` that all ended before they were solved.
+    pub fn all(day: u8, parts: &[u8], outcome: Outcome, parse_time: Option<Duration>) -> Vec<Self> {
+        parts
+            .iter()
+            .map(|&part| Report {
+                day,
+                part,
+                outcome: outcome.clone(),
+                parse_time,
+                solve_time: None,
+            })
+            .collect()
+    }
+}
+
+/// Runs the jobs on `config.jobs` worker threads and returns the reports in the order
+/// of the jobs.
+pub fn run(jobs: Vec<Job>, config: &Config) -> Vec<Report> {
+    let count = jobs.len();
+    let queue = Mutex::new(jobs.into_iter().enumerate());
+    let (sender, receiver) = mpsc::channel();
+    let sink = trace::current();
+    thread::scope(|scope| {
+        for _ in 0..config.jobs.clamp(1, count.max(1)) {
+            let sender = sender.clone();
+            let queue = &queue;
+            let sink = sink.clone();
+            scope.spawn(move || {
+                trace::with_sink(sink, || loop {
+                    let next = queue.lock().unwrap().next();
+                    let Some((idx, job)) = next else {
+                        break;
+                    };
+                    sender.send((idx, run_job(job, config.timeout))).unwrap();
+                })
+            });
+        }
+    });
+    drop(sender);
+    let mut reports: Vec<(usize, Vec<Report>)> = receiver.into_iter().collect();
+    reports.sort_by_key(|&(idx, _)| idx);
+    reports
+        .into_iter()
+        .flat_map(|(_, reports)| reports)
+        .collect()
+}
+
+fn run_job(job: Job, timeout: Duration) -> Vec<Report> {
+    let Job {
+        entry,
+        parts,
+        input,
+    } = job;
+    let parsed = with_timeout(timeout, move || {
+        let start = Instant::now();
+        let parsed = entry.parse(&input);
+        (parsed, start.elapsed())
+    });
+    let (parsed, parse_time) = match parsed {
+        Ok((Ok(parsed), parse_time)) => (Arc::new(parsed), parse_time),
+        Ok((Err(e), parse_time)) => {
+            return Report::all(
+                entry.day,
+                &parts,
+                Outcome::Failed(e.to_string()),
+                Some(parse_time),
+            )
+        }
+        Err(outcome) => return Report::all(entry.day, &parts, outcome, None),
+    };
+    parts
+        .iter()
+        .map(|&part| {
+            let parsed = Arc::clone(&parsed);
+            let solved = with_timeout(timeout, move || {
+                let start = Instant::now();
+                let answer = entry.solve(&parsed, part);
+                (answer, start.elapsed())
+            });
+            let (outcome, solve_time) = match solved {
+                Ok((Ok(answer), time)) => (Outcome::Solved(answer), Some(time)),
+                Ok((Err(e), time)) => (Outcome::Failed(e.to_string()), Some(time)),
+                Err(outcome) => (outcome, None),
+            };
+            Report {
+                day: entry.day,
+                part,
+                outcome,
+                parse_time: Some(parse_time),
+                solve_time,
+            }
+        })
+        .collect()
+}
+
+/// Runs `f` on a new thread and waits for at most `timeout`. If it doesn't finish, the
+/// thread is left running.
+fn with_timeout<T: Send + 'static>(
+    timeout: Duration,
+    f: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, Outcome> {
+    let (sender, receiver) = mpsc::channel();
+    let sink = trace::current();
+    let handle = thread::spawn(move || {
+        // the receiver is gone if we timed out
+        let _ = sender.send(trace::with_sink(sink, f));
+    });
+    match receiver.recv_timeout(timeout) {
+        Ok(value) => Ok(value),
+        Err(RecvTimeoutError::Timeout) => Err(Outcome::TimedOut(timeout)),
+        Err(RecvTimeoutError::Disconnected) => {
+            let message = handle.join().err().map(panic_message).unwrap_or_default();
+            Err(Outcome::Failed(format!("panicked: {}", message)))
+        }
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_default()
+}
+
+#[test]
+fn timeouts() {
+    use crate::{
+        error::Result,
+        solution::{self, Solution},
+    };
+
+    struct Stuck;
+
+    impl Solution for Stuck {
+        type Input = ();
+        const DAY: u8 = 0;
+        const PARTS: &'static [u8] = &[1, 2, 3];
+
+        fn parse(_: &str) -> Result<()> {
+            Ok(())
+        }
+
+        fn solve(_: &(), part: u8) -> Result<Answer> {
+            match part {
+                1 => Ok(Answer::Int(1)),
+                2 => loop {
+                    thread::sleep(Duration::from_secs(1));
+                },
+                _ => panic!("part {}", part),
+            }
+        }
+    }
+
+    static STUCK: Entry = Entry::new::<Stuck>();
+    let config = Config {
+        jobs: 2,
+        timeout: Duration::from_millis(50),
+    };
+    let jobs = vec![
+        Job {
+            entry: &STUCK,
+            parts: vec![1, 2, 3],
+            input: String::new(),
+        },
+        Job {
+            entry: solution::find(2020, 1).unwrap(),
+            parts: vec![1],
+            input: "1721\nx".to_string(),
+        },
+    ];
+    let outcomes: Vec<Outcome> = run(jobs, &config)
+        .into_iter()
+        .map(|report| report.outcome)
+        .collect();
+    assert_eq!(
+        outcomes,
+        [
+            Outcome::Solved(Answer::Int(1)),
+            Outcome::TimedOut(Duration::from_millis(50)),
+            Outcome::Failed("panicked: part 3".to_string()),
+            Outcome::Failed("day 1, line 2, column 1: expected a number".to_string()),
+        ]
+    );
+}
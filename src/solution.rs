@@ -0,0 +1,259 @@
+//! A uniform interface over all days, so that tools can run them without knowing
+//! each day's input and answer types.
+
+use std::{any::Any, borrow::Cow, fmt};
+
+use crate::{
+    error::{ParseError, Result},
+    normalize::{self, Normalize},
+};
+
+/// The answer to a part of a puzzle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Int(i64),
+    /// Used for unsigned answers that don't fit into an `i64`.
+    BigInt(u128),
+    String(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Int(n) => write!(f, "{}", n),
+            Answer::BigInt(n) => write!(f, "{}", n),
+            Answer::String(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+macro_rules! answer_from_int {
+    ($($int:ty),*) => {
+        $(
+            impl From<$int> for Answer {
+                fn from(n: $int) -> Self {
+                    match i64::try_from(n) {
+                        Ok(n) => Answer::Int(n),
+                        Err(_) => Answer::BigInt(n as u128),
+                    }
+                }
+            }
+        )*
+    };
+}
+answer_from_int!(i32, i64, u32, u64, usize);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::String(s)
+    }
+}
+
+/// A day of Advent of Code.
+pub trait Solution {
+    type Input: Send + Sync + 'static;
+    const YEAR: u16 = 2020;
+    const DAY: u8;
+    /// The parts this day has, usually `[1, 2]`.
+    const PARTS: &'static [u8];
+    /// How `Entry::parse` cleans up inputs before they reach `parse`.
+    const NORMALIZE: Normalize = Normalize::ALL;
+
+    fn parse(input: &str) -> Result<Self::Input>;
+    fn solve(input: &Self::Input, part: u8) -> Result<Answer>;
+}
+
+/// A parsed input of any day, to be passed back to the `Entry` that produced it.
+pub struct Parsed(Box<dyn Any + Send + Sync>);
+
+/// A `Solution` in the registry, with its input type erased.
+pub struct Entry {
+    pub year: u16,
+    pub day: u8,
+    pub parts: &'static [u8],
+    pub normalize: Normalize,
+    parse: fn(&str) -> Result<Parsed>,
+    solve: fn(&Parsed, u8) -> Result<Answer>,
+}
+
+impl Entry {
+    pub(crate) const fn new<S: Solution>() -> Self {
+        Self {
+            year: S::YEAR,
+            day: S::DAY,
+            parts: S::PARTS,
+            normalize: S::NORMALIZE,
+            parse: |input| S::parse(input).map(|input| Parsed(Box::new(input))),
+            solve: |input, part| match input.0.downcast_ref::<S::Input>() {
+                Some(input) => S::solve(input, part),
+                None => Err(ParseError::input(
+                    S::DAY,
+                    "the input was parsed for another day",
+                )),
+            },
+        }
+    }
+
+    /// `input` the way this day wants it, see `normalize`.
+    pub fn normalize<'a>(&self, input: &'a str) -> Cow<'a, str> {
+        normalize::normalize(input, self.normalize)
+    }
+
+    /// Parses `input`, which the day normalizes first.
+    pub fn parse(&self, input: &str) -> Result<Parsed> {
+        (self.parse)(input)
+    }
+
+    pub fn solve(&self, input: &Parsed, part: u8) -> Result<Answer> {
+        (self.solve)(input, part)
+    }
+
+    /// Parses `input` and solves `part` of it.
+    pub fn run(&self, input: &str, part: u8) -> Result<Answer> {
+        self.solve(&self.parse(input)?, part)
+    }
+}
+
+fn missing_part(day: u8, part: u8) -> ParseError {
+    ParseError::input(day, format!("there is no part {}", part))
+}
+
+macro_rules! solutions {
+    ($(
+        $name:ident($day:literal) => $module:ident { $($part:literal: $solver:ident),* }
+        $(normalize: $normalize:expr)?
+    )*) => {
+        $(
+            pub struct $name;
+
+            impl Solution for $name {
+                type Input = crate::$module::Input;
+                const DAY: u8 = $day;
+                const PARTS: &'static [u8] = &[$($part),*];
+                $(const NORMALIZE: Normalize = $normalize;)?
+
+                fn parse(input: &str) -> Result<Self::Input> {
+                    crate::$module::parse(input)
+                }
+
+                fn solve(input: &Self::Input, part: u8) -> Result<Answer> {
+                    match part {
+                        $($part => crate::$module::$solver(input).map(Answer::from),)*
+                        _ => Err(missing_part(Self::DAY, part)),
+                    }
+                }
+            }
+        )*
+
+        static REGISTRY: &[Entry] = &[$(Entry::new::<$name>()),*];
+    };
+}
+
+solutions! {
+    Day1(1) => day1 { 1: part1, 2: part2 }
+    Day2(2) => day2 { 1: part1, 2: part2 }
+    Day3(3) => day3 { 1: part1, 2: part2 }
+    Day4(4) => day4 { 1: part1, 2: part2 }
+    Day5(5) => day5 { 1: part1, 2: part2 }
+    Day6(6) => day6 { 1: part1, 2: part2 }
+    Day7(7) => day7 { 1: part1, 2: part2 }
+    Day8(8) => day8 { 1: part1, 2: part2 }
+    Day9(9) => day9 { 1: part1, 2: part2 }
+    Day10(10) => day10 { 1: part1, 2: part2 }
+    Day11(11) => day11 { 1: part1, 2: part2 }
+    Day12(12) => day12 { 1: part1, 2: part2 }
+    Day13(13) => day13 { 1: part1, 2: part2 }
+    Day14(14) => day14 { 1: part1, 2: part2 }
+    Day15(15) => day15 { 1: part1, 2: part2 }
+    Day16(16) => day16 { 1: part1, 2: part2 }
+    Day17(17) => day17 { 1: part1, 2: part2 }
+    Day18(18) => day18 { 1: part1, 2: part2 }
+    Day19(19) => day19 { 1: part1, 2: part2 }
+    Day20(20) => day20 { 1: part1, 2: part2 }
+    Day21(21) => day21 { 1: part1, 2: part2 }
+    Day22(22) => day22 { 1: part1, 2: part2 }
+    Day23(23) => day23 { 1: part1, 2: part2 }
+    Day24(24) => day24 { 1: part1, 2: part2 }
+    Day25(25) => day25 { 1: part1 }
+}
+
+/// All days that have been solved, ordered by year and day.
+pub fn registry() -> &'static [Entry] {
+    REGISTRY
+}
+
+pub fn find(year: u16, day: u8) -> Option<&'static Entry> {
+    REGISTRY
+        .iter()
+        .find(|entry| entry.year == year && entry.day == day)
+}
+
+/// Every solved (year, day, part).
+pub fn parts() -> impl Iterator<Item = (u16, u8, u8)> {
+    REGISTRY.iter().flat_map(|entry| {
+        entry
+            .parts
+            .iter()
+            .map(move |&part| (entry.year, entry.day, part))
+    })
+}
+
+#[test]
+fn registry_is_complete() {
+    assert_eq!(parts().count(), 49);
+    assert!(registry().windows(2).all(|w| w[0].day < w[1].day));
+    assert_eq!(find(2020, 25).unwrap().parts, &[1]);
+    assert!(find(2019, 1).is_none());
+}
+
+#[test]
+fn run() {
+    let day1 = find(2020, 1).unwrap();
+    let input = day1.parse("1721\n979\n366\n299\n675\n1456").unwrap();
+    assert_eq!(day1.solve(&input, 1), Ok(Answer::Int(514579)));
+    assert_eq!(
+        day1.solve(&input, 3).unwrap_err().message,
+        "there is no part 3"
+    );
+    let wrong_day = find(2020, 2).unwrap().solve(&input, 1);
+    assert_eq!(wrong_day.unwrap_err().day, 2);
+}
+
+#[test]
+fn answers() {
+    assert_eq!(Answer::from(u64::MAX), Answer::BigInt(u64::MAX as u128));
+    assert_eq!(Answer::from(-3i32).to_string(), "-3");
+    assert_eq!(Answer::from("a,b".to_string()).to_string(), "a,b");
+}
+
+#[test]
+fn normalized_inputs() {
+    let day1 = find(2020, 1).unwrap();
+    let input = day1
+        .parse("\u{feff}1721\r\n979\r\n366\r\n299\r\n675\r\n1456\r\n\r\n")
+        .unwrap();
+    assert_eq!(day1.solve(&input, 1), Ok(Answer::Int(514579)));
+
+    struct Raw;
+
+    impl Solution for Raw {
+        type Input = String;
+        const DAY: u8 = 0;
+        const PARTS: &'static [u8] = &[1];
+        const NORMALIZE: Normalize = Normalize::NONE;
+
+        fn parse(input: &str) -> Result<String> {
+            Ok(input.to_string())
+        }
+
+        fn solve(input: &String, _: u8) -> Result<Answer> {
+            Ok(Answer::String(input.clone()))
+        }
+    }
+
+    let raw = Entry::new::<Raw>();
+    assert_eq!(
+        raw.run("a\r\n\n", 1),
+        Ok(Answer::String("a\r\n\n".to_string()))
+    );
+}
@@ -0,0 +1,214 @@
+//! Solvers that read their input line by line from a `BufRead`, for inputs that are too
+//! large to hold in memory.
+//!
+//! They read the input once and compute both parts along the way, so they also work
+//! on pipes.
+
+use std::{collections::VecDeque, io::BufRead, mem};
+
+use crate::{
+    error::{ParseError, Result, Source},
+    parser::Parser,
+    solution::Answer,
+};
+
+pub struct Streaming {
+    pub day: u8,
+    /// The answers to all parts, in order.
+    pub solve: fn(&mut dyn BufRead) -> Result<Vec<Answer>>,
+}
+
+pub static STREAMING: &[Streaming] = &[
+    Streaming {
+        day: 1,
+        solve: |input| answers(crate::day1::stream(input)),
+    },
+    Streaming {
+        day: 2,
+        solve: |input| answers(crate::day2::stream(input)),
+    },
+    Streaming {
+        day: 5,
+        solve: |input| answers(crate::day5::stream(input)),
+    },
+    Streaming {
+        day: 6,
+        solve: |input| answers(crate::day6::stream(input)),
+    },
+    Streaming {
+        day: 8,
+        solve: |input| {
+            let program = crate::day8::read(input)?;
+            answers(Ok((
+                crate::day8::part1(&program)?,
+                crate::day8::part2(&program)?,
+            )))
+        },
+    },
+    Streaming {
+        day: 9,
+        solve: |input| answers(crate::day9::stream(input)),
+    },
+    Streaming {
+        day: 14,
+        solve: |input| answers(crate::day14::stream(input)),
+    },
+];
+
+pub fn find(day: u8) -> Option<&'static Streaming> {
+    STREAMING.iter().find(|streaming| streaming.day == day)
+}
+
+fn answers<A: Into<Answer>, B: Into<Answer>>(answers: Result<(A, B)>) -> Result<Vec<Answer>> {
+    let (a, b) = answers?;
+    Ok(vec![a.into(), b.into()])
+}
+
+/// The lines of an input, read one at a time. Like `normalize`, it ignores a byte order
+/// mark, `\r` before line breaks and the lines at the end that are empty or only contain
+/// whitespace.
+pub(crate) struct Lines<R> {
+    day: u8,
+    reader: R,
+    line: String,
+    /// The 1-based number of `line`.
+    number: usize,
+    /// Blank lines that were read while looking for the end of the input.
+    blank: VecDeque<String>,
+    /// The line after them.
+    ahead: Option<String>,
+    /// Whether the first line was read, which might start with a byte order mark.
+    started: bool,
+}
+
+impl<R: BufRead> Lines<R> {
+    pub(crate) fn new(day: u8, reader: R) -> Self {
+        Self {
+            day,
+            reader,
+            line: String::new(),
+            number: 0,
+            blank: VecDeque::new(),
+            ahead: None,
+            started: false,
+        }
+    }
+
+    /// Runs `parser` on the next line, which it has to consume completely.
+    pub(crate) fn parse<'s, T>(&'s mut self, parser: impl Parser<'s, T>) -> Result<Option<T>> {
+        if !self.advance()? {
+            return Ok(None);
+        }
+        let this: &'s Self = self;
+        Source::new(this.day, &this.line)
+            .run(parser)
+            .map(Some)
+            .map_err(|mut error| {
+                if error.line != 0 {
+                    error.line += this.number - 1;
+                }
+                error
+            })
+    }
+
+    /// An error at the start of the current line.
+    pub(crate) fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.day, self.number, 1, message)
+    }
+
+    fn advance(&mut self) -> Result<bool> {
+        let is_blank = |line: &str| line.chars().all(char::is_whitespace);
+        if let Some(blank) = self.blank.pop_front() {
+            self.line = blank;
+        } else if let Some(ahead) = self.ahead.take() {
+            self.line = ahead;
+        } else {
+            if !self.read_line()? {
+                return Ok(false);
+            }
+            if is_blank(&self.line) {
+                // only blank lines are left if the next line that isn't blank is missing
+                let mut blank = VecDeque::from([mem::take(&mut self.line)]);
+                loop {
+                    if !self.read_line()? {
+                        return Ok(false);
+                    }
+                    if !is_blank(&self.line) {
+                        break;
+                    }
+                    blank.push_back(mem::take(&mut self.line));
+                }
+                self.ahead = Some(mem::replace(&mut self.line, blank.pop_front().unwrap()));
+                self.blank = blank;
+            }
+        }
+        self.number += 1;
+        Ok(true)
+    }
+
+    /// Reads the next line into `line` and returns whether there was one.
+    fn read_line(&mut self) -> Result<bool> {
+        self.line.clear();
+        let read = self
+            .reader
+            .read_line(&mut self.line)
+            .map_err(|e| ParseError::input(self.day, format!("can't read the input: {}", e)))?;
+        if !self.started {
+            self.started = true;
+            if self.line.starts_with('\u{feff}') {
+                self.line.drain(..'\u{feff}'.len_utf8());
+            }
+        }
+        if self.line.ends_with('\n') {
+            self.line.pop();
+            if self.line.ends_with('\r') {
+                self.line.pop();
+            }
+        }
+        Ok(read > 0)
+    }
+}
+
+#[test]
+fn lines() {
+    use crate::parser::{int, optional, take_while1, Parser};
+
+    let mut lines = Lines::new(1, "\u{feff}1\n\n\n2\r\n3x\n\n\n".as_bytes());
+    assert_eq!(lines.parse(optional(int())), Ok(Some(Some(1))));
+    assert_eq!(lines.parse(optional(int::<u8>())), Ok(Some(None)));
+    assert_eq!(lines.parse(optional(int::<u8>())), Ok(Some(None)));
+    assert_eq!(lines.parse(int()), Ok(Some(2)));
+    let error = lines.parse::<u8>(int()).unwrap_err();
+    assert_eq!((error.line, error.column), (5, 2));
+    assert_eq!(lines.parse::<u8>(int()), Ok(None));
+
+    // how many spaces a line has
+    fn spaces<'a>() -> impl Parser<'a, usize> {
+        optional(take_while1("spaces", |c| c == ' ')).map(|s: Option<&str>| s.map_or(0, str::len))
+    }
+    let mut lines = Lines::new(1, "1 \n  \n\n2\n \t\n  \r\n".as_bytes());
+    assert_eq!(lines.parse((int::<u8>(), spaces())), Ok(Some((1, 1))));
+    assert_eq!(lines.parse(spaces()), Ok(Some(2)));
+    assert_eq!(lines.parse(spaces()), Ok(Some(0)));
+    assert_eq!(lines.parse(int()), Ok(Some(2)));
+    assert_eq!(lines.parse(spaces()), Ok(None));
+}
+
+#[test]
+fn same_answers() {
+    use crate::{generators, solution};
+
+    for streaming in STREAMING {
+        let entry = solution::find(2020, streaming.day).unwrap();
+        for seed in 0..4 {
+            let input = generators::generate(streaming.day, seed, 200).unwrap();
+            let expected: Vec<Answer> = entry
+                .parts
+                .iter()
+                .map(|&part| entry.run(&input, part).unwrap())
+                .collect();
+            let answers = (streaming.solve)(&mut input.as_bytes());
+            assert_eq!(answers, Ok(expected), "day {}", streaming.day);
+        }
+    }
+}
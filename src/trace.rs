@@ -0,0 +1,160 @@
+//! Structured events from inside the solvers, written as JSON Lines, e.g. every
+//! instruction the day 8 console executes or every round of day 22.
+//!
+//! Tracing is opt-in: events are only built while a `Sink` is installed on the
+//! current thread with `with_sink`. Otherwise `trace!` costs an atomic load.
+
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use serde_json::{Map, Value};
+
+/// Emits an event for `day` if tracing is enabled, e.g.
+/// `trace!(8, "exec", { "ip": ip, "acc": acc })`. The fields are only evaluated then.
+macro_rules! trace {
+    ($day:expr, $event:expr, { $($fields:tt)* }) => {
+        if $crate::trace::enabled() {
+            $crate::trace::emit($day, $event, serde_json::json!({ $($fields)* }));
+        }
+    };
+}
+pub(crate) use trace;
+
+/// Where events go. Clones write to the same output.
+#[derive(Clone)]
+pub struct Sink(Arc<Mutex<Output>>);
+
+struct Output {
+    out: Box<dyn Write + Send>,
+    /// The first error while writing, reported by `finish`.
+    error: Option<io::Error>,
+}
+
+impl Sink {
+    pub fn new(out: impl Write + Send + 'static) -> Self {
+        Self(Arc::new(Mutex::new(Output {
+            out: Box::new(out),
+            error: None,
+        })))
+    }
+
+    /// Flushes the output and returns the first error that happened while writing.
+    pub fn finish(&self) -> io::Result<()> {
+        let mut output = self.0.lock().unwrap();
+        if let Some(error) = output.error.take() {
+            return Err(error);
+        }
+        output.out.flush()
+    }
+
+    fn write(&self, event: &Value) {
+        let mut output = self.0.lock().unwrap();
+        if output.error.is_none() {
+            if let Err(e) = writeln!(output.out, "{}", event) {
+                output.error = Some(e);
+            }
+        }
+    }
+}
+
+/// The number of threads with a sink, so that `enabled` is cheap when there are none.
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static SINK: RefCell<Option<Sink>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with `sink` receiving the events of this thread. `None` disables tracing.
+pub fn with_sink<T>(sink: Option<Sink>, f: impl FnOnce() -> T) -> T {
+    // puts the previous sink back, even if `f` panics
+    struct Restore(Option<Sink>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            install(self.0.take());
+        }
+    }
+
+    let _restore = Restore(install(sink));
+    f()
+}
+
+/// Replaces the sink of this thread and returns the previous one.
+fn install(sink: Option<Sink>) -> Option<Sink> {
+    if sink.is_some() {
+        ACTIVE.fetch_add(1, Ordering::Relaxed);
+    }
+    let previous = SINK.with(|current| current.replace(sink));
+    if previous.is_some() {
+        ACTIVE.fetch_sub(1, Ordering::Relaxed);
+    }
+    previous
+}
+
+/// The sink of this thread, to pass on to threads it spawns.
+pub fn current() -> Option<Sink> {
+    if ACTIVE.load(Ordering::Relaxed) == 0 {
+        return None;
+    }
+    SINK.with(|sink| sink.borrow().clone())
+}
+
+#[doc(hidden)]
+pub fn enabled() -> bool {
+    ACTIVE.load(Ordering::Relaxed) > 0 && SINK.with(|sink| sink.borrow().is_some())
+}
+
+#[doc(hidden)]
+pub fn emit(day: u8, event: &str, fields: Value) {
+    let mut object = Map::new();
+    object.insert("day".to_string(), day.into());
+    object.insert("event".to_string(), event.into());
+    if let Value::Object(fields) = fields {
+        object.extend(fields);
+    }
+    SINK.with(|sink| {
+        if let Some(sink) = &*sink.borrow() {
+            sink.write(&Value::Object(object));
+        }
+    });
+}
+
+#[test]
+fn events() {
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(bytes)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let buffer = Buffer::default();
+    let sink = Sink::new(buffer.clone());
+    let input = crate::day8::parse("nop +0\nacc +1\njmp -2").unwrap();
+    let answer = with_sink(Some(sink.clone()), || crate::day8::part1(&input));
+    assert_eq!(answer, Ok(1));
+    sink.finish().unwrap();
+    assert!(!enabled());
+
+    let text = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    let events: Vec<Value> = text
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[0]["event"], "exec");
+    assert_eq!(events[2]["op"], "jmp -2");
+    assert_eq!(events[2]["acc"], 1);
+}
@@ -0,0 +1,225 @@
+//! Pictures of the grid-based days, to see what the rules do: every generation of the
+//! seating simulation of day 11, every cycle of day 17 as 2D slices and the assembled
+//! picture of day 20 with its sea monsters.
+//!
+//! Frames can be written as PPM or PGM images, or as ANSI escape codes for a terminal.
+
+use std::io::{self, Write};
+
+use crate::{
+    automaton::{number_of_neighbors, Coordinates, Moore, Space},
+    day11::{self, Position},
+    day17, day20,
+    error::{ParseError, Result},
+    grid::Grid,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb(0, 0, 0);
+
+    /// The perceived brightness.
+    pub fn luma(self) -> u8 {
+        let Rgb(r, g, b) = self;
+        ((299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000) as u8
+    }
+}
+
+pub type Frame = Grid<Rgb>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Binary portable pixmap.
+    Ppm,
+    /// Binary portable graymap.
+    Pgm,
+    /// 24-bit color escape codes, two rows of cells per line of text.
+    Ansi,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Ppm => "ppm",
+            Format::Pgm => "pgm",
+            Format::Ansi => "txt",
+        }
+    }
+}
+
+/// The frames of `part` of `day` for `input`, or `None` for days that can't be drawn.
+/// Day 20 has a single frame for both parts.
+pub fn frames(day: u8, part: u8, input: &str) -> Option<Result<Vec<Frame>>> {
+    if part != 1 && part != 2 {
+        return Some(Err(ParseError::input(
+            day,
+            format!("there is no part {}", part),
+        )));
+    }
+    Some(match day {
+        11 => day11::parse(input).map(|input| {
+            day11::generations(&input, part)
+                .iter()
+                .map(|grid| grid.map(seat_color))
+                .collect()
+        }),
+        17 => day17::parse(input).map(|input| {
+            if part == 1 {
+                slices(&cubes(day17::cycles::<3>(&input)))
+            } else {
+                slices(&cubes(day17::cycles::<4>(&input)))
+            }
+        }),
+        20 => day20::parse(input)
+            .and_then(|input| day20::sea(&input))
+            .map(|sea| vec![sea.map(sea_color)]),
+        _ => return None,
+    })
+}
+
+fn seat_color(position: &Position) -> Rgb {
+    match position {
+        Position::Floor => Rgb(40, 40, 40),
+        Position::Seat => Rgb(60, 160, 60),
+        Position::Occupied => Rgb(220, 60, 40),
+    }
+}
+
+fn sea_color(pixel: &day20::Pixel) -> Rgb {
+    match pixel {
+        day20::Pixel::White => Rgb(20, 50, 120),
+        day20::Pixel::Black { hit_count: 0 } => Rgb(120, 180, 230),
+        day20::Pixel::Black { .. } => Rgb(240, 200, 40),
+    }
+}
+
+fn cubes<const N: usize>(cycles: Vec<Space<Moore<N>>>) -> Vec<Vec<Coordinates<N>>>
+where
+    [(); number_of_neighbors(N)]:,
+{
+    cycles
+        .into_iter()
+        .map(|space| space.cells.into_iter().collect())
+        .collect()
+}
+
+/// Draws every cycle of a 3D or 4D pocket dimension as a frame of 2D slices, with `z`
+/// going to the right and `w` going down. All frames show the same region.
+fn slices<const N: usize>(cycles: &[Vec<Coordinates<N>>]) -> Vec<Frame> {
+    let cubes = || cycles.iter().flatten();
+    let range = |dimension: usize| {
+        let min = cubes().map(|cube| cube[dimension]).min().unwrap_or(0);
+        let max = cubes().map(|cube| cube[dimension]).max().unwrap_or(0);
+        (min, (max - min + 1) as usize)
+    };
+    let (x_min, width) = range(0);
+    let (y_min, height) = range(1);
+    let (z_min, columns) = range(2);
+    let (w_min, rows) = if N > 3 { range(3) } else { (0, 1) };
+    // one cell between the slices
+    let frame_width = columns * (width + 1) - 1;
+    let frame_height = rows * (height + 1) - 1;
+    cycles
+        .iter()
+        .map(|cycle| {
+            let mut frame = Grid::from_fn(frame_width, frame_height, |x, y| {
+                if x % (width + 1) == width || y % (height + 1) == height {
+                    Rgb::BLACK
+                } else {
+                    Rgb(30, 30, 60)
+                }
+            });
+            for cube in cycle {
+                let column = (cube[2] - z_min) as usize;
+                let row = if N > 3 { (cube[3] - w_min) as usize } else { 0 };
+                let x = column * (width + 1) + (cube[0] - x_min) as usize;
+                let y = row * (height + 1) + (cube[1] - y_min) as usize;
+                frame[(x, y)] = Rgb(250, 250, 250);
+            }
+            frame
+        })
+        .collect()
+}
+
+/// Writes `frame` as an image with `scale * scale` pixels per cell.
+pub fn write_image(
+    frame: &Frame,
+    format: Format,
+    scale: usize,
+    mut out: impl Write,
+) -> io::Result<()> {
+    let (magic, channels) = match format {
+        Format::Ppm => ("P6", 3),
+        Format::Pgm => ("P5", 1),
+        Format::Ansi => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "ANSI frames aren't images",
+            ))
+        }
+    };
+    let (width, height) = (frame.width() * scale, frame.height() * scale);
+    write!(out, "{}\n{} {}\n255\n", magic, width, height)?;
+    let mut bytes = Vec::with_capacity(width * height * channels);
+    for row in frame.rows() {
+        let start = bytes.len();
+        for &color in row {
+            for _ in 0..scale {
+                match format {
+                    Format::Pgm => bytes.push(color.luma()),
+                    _ => bytes.extend([color.0, color.1, color.2]),
+                }
+            }
+        }
+        let line = bytes[start..].to_vec();
+        for _ in 1..scale {
+            bytes.extend_from_slice(&line);
+        }
+    }
+    out.write_all(&bytes)
+}
+
+/// `frame` as colored half blocks, each line of text showing two rows of cells.
+pub fn ansi(frame: &Frame) -> String {
+    let mut text = String::new();
+    for y in (0..frame.height()).step_by(2) {
+        for x in 0..frame.width() {
+            let Rgb(r, g, b) = frame[(x, y)];
+            let Rgb(br, bg, bb) = frame.get(x, y + 1).copied().unwrap_or(Rgb::BLACK);
+            text += &format!(
+                "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m▀",
+                r, g, b, br, bg, bb
+            );
+        }
+        text += "\x1b[0m\n";
+    }
+    text
+}
+
+#[test]
+fn images() {
+    let frame = Grid::new(2, 1, vec![Rgb(255, 0, 0), Rgb(255, 255, 255)]);
+    let mut ppm = vec![];
+    write_image(&frame, Format::Ppm, 2, &mut ppm).unwrap();
+    assert_eq!(&ppm[..11], b"P6\n4 2\n255\n");
+    assert_eq!(ppm.len(), 11 + 4 * 2 * 3);
+    assert_eq!(&ppm[11..17], [255, 0, 0, 255, 0, 0]);
+    let mut pgm = vec![];
+    write_image(&frame, Format::Pgm, 1, &mut pgm).unwrap();
+    assert_eq!(pgm, b"P5\n2 1\n255\n\x4c\xff");
+    assert_eq!(ansi(&frame).matches('▀').count(), 2);
+
+    let seats = "L.LL\nLLLL\nL.L.";
+    let generations = frames(11, 1, seats).unwrap().unwrap();
+    assert_eq!(generations.len(), 3);
+    assert_eq!(generations[1][(0, 0)], seat_color(&Position::Occupied));
+    let cycles = frames(17, 2, ".#.\n..#\n###").unwrap().unwrap();
+    assert_eq!(cycles.len(), 7);
+    let active = cycles[6]
+        .cells()
+        .filter(|&&color| color == Rgb(250, 250, 250));
+    assert_eq!(active.count(), 848);
+    assert!(frames(3, 1, "").is_none());
+}
@@ -0,0 +1,137 @@
+//! Checks every part of every registered day against the answers in answers/2020.toml.
+//! Every part is its own test, so a failure names the day and the part.
+
+use std::fs;
+
+use aoc_2020::solution;
+use toml::{Table, Value};
+
+fn expected_answers() -> Table {
+    let manifest = fs::read_to_string("answers/2020.toml").unwrap();
+    manifest.parse().unwrap()
+}
+
+/// Solves `part` of `day` for the real input and compares it with the manifest.
+fn check(day: u8, part: u8) {
+    let answers = expected_answers();
+    let expected = match answers
+        .get(&format!("day{}", day))
+        .and_then(|parts| parts.get(format!("part{}", part)))
+    {
+        Some(Value::Integer(n)) => n.to_string(),
+        Some(Value::String(s)) => s.clone(),
+        _ => panic!("day {} part {}: no answer in the manifest", day, part),
+    };
+    let entry = solution::find(2020, day).unwrap();
+    let input = fs::read_to_string(format!("input/2020/day{}.txt", day))
+        .unwrap_or_else(|e| panic!("day {}: can't read the input: {}", day, e));
+    let answer = entry
+        .run(&input, part)
+        .unwrap_or_else(|e| panic!("day {} part {}: {}", day, part, e));
+    assert_eq!(
+        answer.to_string(),
+        expected,
+        "day {} part {}: wrong answer",
+        day,
+        part
+    );
+}
+
+macro_rules! real_inputs {
+    ($($name:ident: $day:literal, $part:literal;)*) => {
+        $(
+            #[test]
+            fn $name() {
+                check($day, $part);
+            }
+        )*
+
+        /// Every day and part that has a test above.
+        const TESTED: &[(u8, u8)] = &[$(($day, $part)),*];
+    };
+}
+
+real_inputs! {
+    day1_part1: 1, 1;
+    day1_part2: 1, 2;
+    day2_part1: 2, 1;
+    day2_part2: 2, 2;
+    day3_part1: 3, 1;
+    day3_part2: 3, 2;
+    day4_part1: 4, 1;
+    day4_part2: 4, 2;
+    day5_part1: 5, 1;
+    day5_part2: 5, 2;
+    day6_part1: 6, 1;
+    day6_part2: 6, 2;
+    day7_part1: 7, 1;
+    day7_part2: 7, 2;
+    day8_part1: 8, 1;
+    day8_part2: 8, 2;
+    day9_part1: 9, 1;
+    day9_part2: 9, 2;
+    day10_part1: 10, 1;
+    day10_part2: 10, 2;
+    day11_part1: 11, 1;
+    day11_part2: 11, 2;
+    day12_part1: 12, 1;
+    day12_part2: 12, 2;
+    day13_part1: 13, 1;
+    day13_part2: 13, 2;
+    day14_part1: 14, 1;
+    day14_part2: 14, 2;
+    day15_part1: 15, 1;
+    day15_part2: 15, 2;
+    day16_part1: 16, 1;
+    day16_part2: 16, 2;
+    day17_part1: 17, 1;
+    day17_part2: 17, 2;
+    day18_part1: 18, 1;
+    day18_part2: 18, 2;
+    day19_part1: 19, 1;
+    day19_part2: 19, 2;
+    day20_part1: 20, 1;
+    day20_part2: 20, 2;
+    day21_part1: 21, 1;
+    day21_part2: 21, 2;
+    day22_part1: 22, 1;
+    day22_part2: 22, 2;
+    day23_part1: 23, 1;
+    day23_part2: 23, 2;
+    day24_part1: 24, 1;
+    day24_part2: 24, 2;
+    day25_part1: 25, 1;
+}
+
+#[test]
+fn every_part_is_tested() {
+    for entry in solution::registry() {
+        for &part in entry.parts {
+            assert!(
+                TESTED.contains(&(entry.day, part)),
+                "day {} part {} has no test",
+                entry.day,
+                part
+            );
+        }
+    }
+}
+
+#[test]
+fn no_unknown_days() {
+    for (day, parts) in expected_answers() {
+        let entry = day
+            .strip_prefix("day")
+            .and_then(|day| day.parse().ok())
+            .and_then(|day| solution::find(2020, day))
+            .unwrap_or_else(|| panic!("`{}` is not a solved day", day));
+        for part in parts.as_table().unwrap().keys() {
+            assert!(
+                entry.parts.iter().any(|p| format!("part{}", p) == *part),
+                "day {} has no {}",
+                entry.day,
+                part
+            );
+        }
+    }
+}